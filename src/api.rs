@@ -0,0 +1,604 @@
+use crate::error::SolanaClientError;
+use crate::transaction;
+use crate::webhook::{DeadLetter, WebhookEventType, WebhookRegistration};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BalanceRequest {
+    pub pubkey: String, // Public key to check balance for
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareSwapRequest {
+    pub payer_pubkey: String, // Who pays fees
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareTransactionRequest {
+    pub payer_pubkey: String, // Who pays fees and sends
+    pub to_address: String,
+    pub amount: f64,
+    pub memo: Option<String>, // Recorded on chain with an SPL Memo instruction
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubmitSignedRequest {
+    pub signed_transaction: String, // Base64 encoded signed transaction
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PriceRequest {
+    pub token: String, // Token symbol or mint address
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchRequest {
+    pub query: String, // Search term
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WalletTokensRequest {
+    pub pubkey: String, // Public key to get tokens for
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<ApiError>,
+}
+
+/// Schema of a failed `ApiResponse`, for the OpenAPI document only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorResponse {
+    success: bool,                   // Always false
+    data: Option<serde_json::Value>, // Always null
+    error: ApiError,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidAddress,
+    InvalidWalletFormat,
+    InvalidRequest,
+    InsufficientBalance,
+    TransactionFailed,
+    PolicyViolation,
+    AuthFailed,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    RateLimited,
+    WalletNotFound,
+    NetworkError,
+    ConfigError,
+    InternalError,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String, // Human readable, not meant to be parsed
+    pub details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: String) -> Self {
+        Self {
+            code,
+            message,
+            details: None,
+        }
+    }
+
+    /// Classify an error bubbling out of the client layer; `context` prefixes the message
+    pub fn from_error(context: &str, error: &anyhow::Error) -> Self {
+        Self::classify(format!("{}: {}", context, error), error)
+    }
+
+    fn classify(message: String, error: &anyhow::Error) -> Self {
+        if let Some(e) = error.downcast_ref::<SolanaClientError>() {
+            return Self {
+                code: e.code(),
+                message,
+                details: e.details(),
+            };
+        }
+
+        // Raw RPC errors: a rejected transaction is the caller's problem, anything
+        // else means the node could not be reached or misbehaved
+        if let Some(e) = error.downcast_ref::<solana_client::client_error::ClientError>() {
+            let code = match e.get_transaction_error() {
+                Some(_) => ErrorCode::TransactionFailed,
+                None => ErrorCode::NetworkError,
+            };
+            return Self::new(code, message);
+        }
+
+        if error.downcast_ref::<reqwest::Error>().is_some() {
+            return Self::new(ErrorCode::NetworkError, message);
+        }
+
+        Self::new(ErrorCode::InternalError, message)
+    }
+}
+
+impl From<&anyhow::Error> for ApiError {
+    fn from(error: &anyhow::Error) -> Self {
+        Self::classify(format!("{:#}", error), error)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BalanceResponse {
+    pub pubkey: String,
+    pub balance: f64,
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareSwapResponse {
+    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
+    pub quote_info: QuoteInfo,
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareTransactionResponse {
+    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
+    pub from: String,
+    pub to: String,
+    pub amount: f64,
+    pub memo: Option<String>,
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct QuoteInfo {
+    pub expected_output: f64,
+    pub price_impact: f64,
+    pub route_steps: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubmitResponse {
+    pub signature: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PriceResponse {
+    pub token: String,
+    pub price: f64,
+    pub currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TokenSearchResponse {
+    pub tokens: Vec<TokenInfo>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub name: String,
+    pub address: String,
+    pub decimals: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WalletTokensResponse {
+    pub pubkey: String,
+    pub tokens: Vec<WalletTokenInfo>,
+    pub total_tokens: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WalletTokenInfo {
+    pub symbol: String,
+    pub name: String,
+    pub mint: String,
+    pub balance: f64,
+    pub decimals: u8,
+    pub usd_value: Option<f64>,
+    pub kind: BalanceKind,
+}
+
+/// Native SOL and wrapped SOL share a mint address; this tells them apart
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceKind {
+    NativeSol,  // Lamports held by the wallet itself
+    WrappedSol, // SPL tokens of the native mint, in a token account
+    Token,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransactionHistoryRequest {
+    pub pubkey: String,
+    pub limit: Option<usize>,
+    pub before: Option<String>, // Signature to paginate before
+    pub memo: Option<String>,   // Only transactions whose memo contains this, ignoring case
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PendingTransactionsRequest {
+    pub pubkey: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransactionHistoryResponse {
+    pub pubkey: String,
+    pub transactions: Vec<transaction::TransactionHistory>,
+    pub total_count: usize,
+    pub has_more: bool,
+    pub next_before: Option<String>, // For pagination
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PendingTransactionsResponse {
+    pub pubkey: String,
+    pub pending_transactions: Vec<transaction::TransactionHistory>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegisterWebhookRequest {
+    pub address: String,
+    pub events: Vec<WebhookEventType>,
+    pub callback_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookListResponse {
+    pub webhooks: Vec<WebhookRegistration>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetterResponse {
+    pub dead_letters: Vec<DeadLetter>,
+    pub count: usize,
+}
+
+/// One native stake operation; the wallet is staker and withdrawer of every account it creates
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum StakeOperation {
+    /// Fund a new stake account with `amount` SOL
+    Create {
+        amount: f64,
+        seed: Option<String>, // Derive the address from the payer; generated when omitted
+    },
+    Delegate {
+        stake_account: String,
+        vote_account: String,
+    },
+    Deactivate {
+        stake_account: String,
+    },
+    Withdraw {
+        stake_account: String,
+        amount: Option<f64>, // Defaults to the whole balance
+        to: Option<String>,  // Defaults to the payer
+    },
+    /// Move `amount` SOL of stake into a new account
+    Split {
+        stake_account: String,
+        amount: f64,
+        seed: Option<String>,
+    },
+    /// Fold `source_account` into `stake_account`
+    Merge {
+        stake_account: String,
+        source_account: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareStakeRequest {
+    pub payer_pubkey: String, // Pays fees and is the stake authority
+    #[serde(flatten)]
+    pub operation: StakeOperation,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareStakeResponse {
+    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
+    pub stake_account: String,        // The new account for create and split
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StakeAccountsRequest {
+    pub pubkey: String,        // Staker or withdrawer authority
+    pub epochs: Option<usize>, // Reward history depth, default 5
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StakeAccountsResponse {
+    pub pubkey: String,
+    pub epoch: u64, // Current epoch
+    pub accounts: Vec<StakeAccountInfo>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StakeAccountInfo {
+    pub address: String,
+    pub balance: f64,
+    pub state: String, // initialized, activating, active, deactivating or inactive
+    pub vote_account: Option<String>,
+    pub delegated_stake: Option<f64>,
+    pub active_stake: Option<f64>,
+    pub activation_epoch: Option<u64>,
+    pub deactivation_epoch: Option<u64>,
+    pub staker: String,
+    pub withdrawer: String,
+    pub rewards: Vec<StakeReward>, // Most recent epoch first
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StakeReward {
+    pub epoch: u64,
+    pub amount: f64,
+    pub post_balance: f64,
+    pub commission: Option<u8>,
+}
+
+/// One SPL mint administration operation; amounts are in whole tokens
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum MintOperation {
+    /// Create and initialize a new mint; extensions make it a Token-2022 mint
+    CreateMint {
+        decimals: u8,
+        mint_authority: Option<String>,   // Defaults to the payer
+        freeze_authority: Option<String>, // None: accounts can never be frozen
+        #[serde(default)]
+        token_2022: bool,
+        #[serde(default)]
+        extensions: Vec<MintExtension>,
+        metadata: Option<TokenMetadata>, // Metaplex metadata, created in the same transaction
+        seed: Option<String>, // Derive the address from the payer; generated when omitted
+    },
+    /// Mint to `to`'s associated token account, created if missing
+    MintTo {
+        mint: String,
+        amount: f64,
+        to: Option<String>, // Owner wallet, defaults to the payer
+    },
+    /// Burn from the payer's associated token account
+    Burn {
+        mint: String,
+        amount: f64,
+    },
+    /// Freeze `owner`'s associated token account
+    Freeze {
+        mint: String,
+        owner: String,
+    },
+    Thaw {
+        mint: String,
+        owner: String,
+    },
+    SetAuthority {
+        mint: String,
+        authority: MintAuthority,
+        new_authority: Option<String>, // None revokes it for good
+    },
+    /// Metaplex metadata for an existing mint; the payer must be its mint authority
+    CreateMetadata {
+        mint: String,
+        name: String,
+        symbol: String,
+        uri: String,
+    },
+}
+
+/// Token-2022 mint extensions; every authority they need is the payer
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MintExtension {
+    TransferFee {
+        basis_points: u16,
+        maximum_fee: f64, // Whole tokens
+    },
+    InterestBearing {
+        rate_bps: i16,
+    },
+    NonTransferable,
+    PermanentDelegate {
+        delegate: String,
+    },
+    MintCloseAuthority,
+    DefaultFrozen, // New token accounts start frozen; needs a freeze authority
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TokenMetadata {
+    pub name: String,   // Up to 32 bytes
+    pub symbol: String, // Up to 10 bytes
+    pub uri: String,    // Off-chain JSON, up to 200 bytes
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MintAuthority {
+    Mint,
+    Freeze,
+    CloseMint,
+    TransferFeeConfig,
+    WithheldWithdraw,
+    InterestRate,
+    PermanentDelegate,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareMintRequest {
+    pub payer_pubkey: String, // Pays fees and signs as the current authority
+    #[serde(flatten)]
+    pub operation: MintOperation,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareMintResponse {
+    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
+    pub mint: String,                 // The new mint for create_mint
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CleanupRequest {
+    pub owner_pubkey: String, // Token account owner; pays fees and receives the rent
+    pub dust_usd: Option<f64>, // Also clear balances worth less than this
+    pub dust_action: Option<DustAction>, // Defaults to burn
+}
+
+/// What happens to a dust balance before its account is closed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum DustAction {
+    Burn,
+    Swap, // To SOL through Jupiter; the account is closed once it is empty
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupAction {
+    Close,       // Already empty
+    Consolidate, // Non-canonical: move the balance to the associated token account, then close
+    Burn,
+    Swap,
+    Skip, // Kept: cannot be closed by the owner, or receives a consolidated balance; see the note
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CleanupAccount {
+    pub address: String,
+    pub mint: String,
+    pub balance: f64,
+    pub usd_value: Option<f64>,
+    pub canonical: bool, // The owner's associated token account for the mint
+    pub action: CleanupAction,
+    pub rent: f64, // SOL returned when closed
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CleanupResponse {
+    pub owner: String,
+    pub accounts: Vec<CleanupAccount>,
+    pub reclaimable_sol: f64,
+    pub transactions: Vec<CleanupTransaction>, // Sign and submit in order
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CleanupTransaction {
+    pub unsigned_transaction: String, // Base64 encoded; legacy for closes, versioned for swaps
+    pub accounts: Vec<String>,        // Token accounts it clears
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareWrapRequest {
+    pub owner_pubkey: String, // Pays fees and owns the wrapped SOL account
+    pub amount: Option<f64>,  // SOL; required to wrap, unwrapping without it closes the account
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PrepareWrapResponse {
+    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
+    pub account: String,              // The owner's wrapped SOL associated token account
+    pub amount: f64,                  // SOL moved into or out of it
+    pub closed: bool,                 // Unwrapping everything closes the account
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+/// A Solana Pay transfer request; everything but the amount is optional
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayRequest {
+    pub recipient: Option<String>, // Defaults to pay.recipient, then the wallet
+    pub amount: Option<f64>,       // Whole tokens; the payer chooses if omitted
+    pub spl_token: Option<String>, // Symbol or mint; SOL if omitted
+    pub label: Option<String>,     // Who is asking, e.g. the shop name
+    pub message: Option<String>,   // What it is for, e.g. the order
+    pub memo: Option<String>,      // Recorded on chain by the payer's wallet
+    pub pay_with: Option<String>,  // Token the payer spends; makes this a transaction request
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayRequestResponse {
+    pub url: String, // solana: transfer request URL, for a link or QR code
+    pub recipient: String,
+    pub amount: Option<f64>,
+    pub spl_token: Option<String>, // Mint, none for SOL
+    pub reference: String,         // Unique key the payment transaction will include
+    pub expires_at: i64,           // Unix seconds
+}
+
+/// What to look for: the fields of a PayRequestResponse
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayStatusRequest {
+    pub reference: String,
+    pub recipient: String,
+    pub amount: Option<f64>,
+    pub spl_token: Option<String>,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    Pending,
+    Paid,
+    Underpaid, // Paid with the reference, but less than the amount
+    Expired,   // Nothing found before expires_at
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayStatusResponse {
+    pub reference: String,
+    pub status: PaymentStatus,
+    pub signature: Option<String>, // The payment transaction, once found
+    pub received: Option<f64>,     // What it moved to the recipient
+    pub expected: Option<f64>,
+}
+
+/// Solana Pay transaction request: what the payer's wallet shows first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayLinkMetadata {
+    pub label: String,
+    pub icon: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayLinkAccount {
+    pub account: String, // The payer, who signs the returned transaction
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayLinkTransaction {
+    pub transaction: String, // Base64 encoded unsigned transaction
+    pub message: Option<String>,
+}
+
+/// Transaction request failures, in the shape wallets display
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PayLinkError {
+    pub message: String,
+}
@@ -0,0 +1,339 @@
+use crate::app_log;
+use crate::config::Config;
+use crate::error::SolanaClientError;
+use crate::policy::{self, Intent, Spend};
+use crate::signing;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::message::VersionedMessage;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyDecision {
+    Approved,
+    Rejected,
+    NotChecked, // Failed before the policy was consulted
+}
+
+/// One journal line; `hash` covers every other field, including `prev_hash`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: String, // `cli:<user>`, `key:<id>`, `wallet:<address>`, `pay:<payer>` or `internal`
+    pub action: String, // transfer, swap, stake, liquid_stake, liquid_unstake, mint, cleanup, wrap, unwrap or submit
+    pub intent: Value,
+    pub signature: Option<String>,
+    pub policy: PolicyDecision,
+    pub violations: Vec<String>,
+    pub status: String, // prepared, signed, confirmed, rejected or failed
+    pub error: Option<String>,
+    pub prev_hash: String,
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> Result<String> {
+        let mut unsigned = self.clone();
+        unsigned.hash = String::new();
+        Ok(signing::sha256_hex(&serde_json::to_vec(&unsigned)?))
+    }
+}
+
+/// Collects what happens to one transaction, then appends it to the journal
+pub struct Audit {
+    actor: String,
+    action: String,
+    intent: Value,
+    policy: PolicyDecision,
+    violations: Vec<String>,
+    spends: Vec<(String, Spend)>, // Approved, counted once the transaction is sent
+    counted: bool,                // Spends are in the ledger under `signature`
+    pub signature: Option<String>,
+}
+
+impl Audit {
+    pub fn new(actor: &str, action: &str, intent: Value) -> Self {
+        Self {
+            actor: actor.to_string(),
+            action: action.to_string(),
+            intent,
+            policy: PolicyDecision::NotChecked,
+            violations: Vec::new(),
+            spends: Vec::new(),
+            counted: false,
+            signature: None,
+        }
+    }
+
+    pub fn check_policy(&mut self, config: &Config, intent: &Intent) -> Result<()> {
+        // Solana Pay payers are not our wallets; their spend is not ours to count
+        let result = match self.actor.starts_with("pay:") {
+            true => policy::check_external(config, intent),
+            false => policy::check(config, intent),
+        };
+        if result.is_ok()
+            && !self.actor.starts_with("pay:")
+            && let Some(spend) = &intent.spend
+        {
+            self.spends.push((intent.wallet.clone(), spend.clone()));
+        }
+        self.record_decision(&result);
+        result
+    }
+
+    /// Count what `check_policy` approved towards the daily limits. Call it
+    /// right before sending, once `signature` is known: a limit reached since
+    /// the check rejects it, and a failed send gives it back in `finish`.
+    pub fn count_spend(&mut self, config: &Config) -> Result<()> {
+        let result = policy::count(config, &self.spends, self.signature.as_deref());
+        self.counted = result.is_ok();
+        self.record_decision(&result);
+        result
+    }
+
+    /// Decode an externally signed transaction into the intent, then check it
+    /// and count its spend
+    pub fn check_submission(
+        &mut self,
+        config: &Config,
+        client: &RpcClient,
+        message: &VersionedMessage,
+    ) -> Result<()> {
+        let transfers = policy::transfers(config, client, message)?;
+        self.intent = json!({
+            "fee_payer": message.static_account_keys().first().map(|k| k.to_string()),
+            "transfers": transfers,
+            "programs": policy::program_ids(message)
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+        });
+
+        let result =
+            policy::check_submission(config, message, &transfers, self.signature.as_deref());
+        self.counted = result.is_ok();
+        self.record_decision(&result);
+        result
+    }
+
+    fn record_decision(&mut self, result: &Result<()>) {
+        match result {
+            Ok(()) => self.policy = PolicyDecision::Approved,
+            Err(e) => {
+                if let Some(SolanaClientError::PolicyViolation { violations }) = e.downcast_ref() {
+                    self.policy = PolicyDecision::Rejected;
+                    self.violations = violations.clone();
+                }
+            }
+        }
+    }
+
+    /// Journal the outcome and pass it on; `status` describes a successful
+    /// result. A transaction that could not be journaled is not handed out, and
+    /// one already confirmed marks the process as failed (see `write_failed`).
+    pub fn finish<T>(self, config: &Config, result: Result<T>, status: &str) -> Result<T> {
+        let on_chain = status == "confirmed";
+
+        // A transaction that did not go through leaves the daily limit as it was
+        if result.is_err()
+            && self.counted
+            && let Some(signature) = &self.signature
+            && let Err(e) = policy::uncount(config, signature)
+        {
+            app_log!(
+                error,
+                "Failed to take back the spend of {}: {}",
+                signature,
+                e
+            );
+        }
+
+        let (status, error) = match &result {
+            Ok(_) => (status.to_string(), None),
+            Err(_) if self.policy == PolicyDecision::Rejected => ("rejected".to_string(), None),
+            Err(e) => ("failed".to_string(), Some(e.to_string())),
+        };
+
+        let entry = AuditEntry {
+            seq: 0,
+            timestamp: Utc::now(),
+            actor: self.actor,
+            action: self.action,
+            intent: self.intent,
+            signature: self.signature,
+            policy: self.policy,
+            violations: self.violations,
+            status,
+            error,
+            prev_hash: String::new(),
+            hash: String::new(),
+        };
+
+        let Err(e) = append(&config.audit.path, entry) else {
+            return result;
+        };
+        app_log!(error, "Failed to write audit entry: {}", e);
+
+        match result {
+            Ok(_) if on_chain => {
+                WRITE_FAILED.store(true, Ordering::Relaxed);
+                result
+            }
+            Ok(_) => Err(e.context("Failed to write audit entry")),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// Set once a confirmed transaction is missing from the journal
+static WRITE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether an audit entry for a confirmed transaction failed to be written
+pub fn write_failed() -> bool {
+    WRITE_FAILED.load(Ordering::Relaxed)
+}
+
+/// Actor recorded for commands run from this terminal
+pub fn cli_actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("cli:{}", user)
+}
+
+/// Last entry of a journal as of its length in bytes
+struct Tail {
+    path: String,
+    len: u64,
+    seq: u64,
+    hash: String,
+}
+
+// Serializes appends within this process; the file lock covers other processes.
+// The cached tail is only trusted while the file has not grown behind our back.
+static JOURNAL: Mutex<Option<Tail>> = Mutex::new(None);
+
+fn append(path: &str, mut entry: AuditEntry) -> Result<()> {
+    let mut tail = JOURNAL.lock().unwrap();
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.lock()?;
+
+    let len = file.metadata()?.len();
+    let (seq, prev_hash) = match tail.as_ref() {
+        Some(t) if t.path == path && t.len == len => (t.seq + 1, t.hash.clone()),
+        _ => match read_entries(path)?.last() {
+            Some(last) => (last.seq + 1, last.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        },
+    };
+    entry.seq = seq;
+    entry.prev_hash = prev_hash;
+    entry.hash = entry.compute_hash()?;
+
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    file.write_all(line.as_bytes())?;
+    file.flush()?;
+
+    *tail = Some(Tail {
+        path: path.to_string(),
+        len: len + line.len() as u64,
+        seq: entry.seq,
+        hash: entry.hash,
+    });
+    Ok(())
+}
+
+pub fn read_entries(path: &str) -> Result<Vec<AuditEntry>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// Walk the chain; returns the number of entries, or the first broken one
+pub fn verify(path: &str) -> Result<usize> {
+    let entries = read_entries(path)?;
+    let mut prev_hash = GENESIS_HASH.to_string();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let problem = if entry.seq != index as u64 {
+            Some(format!("expected sequence {}, found {}", index, entry.seq))
+        } else if entry.prev_hash != prev_hash {
+            Some("does not link to the previous entry".to_string())
+        } else if entry.compute_hash()? != entry.hash {
+            Some("content does not match its hash".to_string())
+        } else {
+            None
+        };
+
+        if let Some(problem) = problem {
+            anyhow::bail!("Audit log broken at entry {}: {}", index, problem);
+        }
+        prev_hash = entry.hash.clone();
+    }
+
+    Ok(entries.len())
+}
+
+#[derive(Default)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub status: Option<String>,
+    pub signature: Option<String>,
+    pub address: Option<String>, // Anywhere in the intent
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+/// Matching entries, most recent last
+pub fn query(path: &str, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+    let mut matches: Vec<AuditEntry> = read_entries(path)?
+        .into_iter()
+        .filter(|e| {
+            query
+                .actor
+                .as_ref()
+                .is_none_or(|a| e.actor.contains(a.as_str()))
+        })
+        .filter(|e| query.action.as_ref().is_none_or(|a| e.action == *a))
+        .filter(|e| query.status.as_ref().is_none_or(|s| e.status == *s))
+        .filter(|e| {
+            query
+                .signature
+                .as_ref()
+                .is_none_or(|s| e.signature.as_ref() == Some(s))
+        })
+        .filter(|e| {
+            query
+                .address
+                .as_ref()
+                .is_none_or(|a| e.intent.to_string().contains(a.as_str()))
+        })
+        .filter(|e| query.since.is_none_or(|since| e.timestamp >= since))
+        .filter(|e| query.until.is_none_or(|until| e.timestamp <= until))
+        .collect();
+
+    if matches.len() > query.limit {
+        matches.drain(..matches.len() - query.limit);
+    }
+    Ok(matches)
+}
@@ -0,0 +1,297 @@
+use crate::app_log;
+use crate::{config::Config, error::SolanaClientError};
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuthChallenge {
+    pub wallet_address: String,
+    pub challenge: String, // Full message the wallet must sign
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuthVerification {
+    pub wallet_address: String,
+    pub signature: String, // Base58 ed25519 signature over `challenge`
+    pub challenge: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuthTokens {
+    pub wallet_address: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccessClaims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
+struct PendingChallenge {
+    wallet_address: String,
+    message: String,
+    expires_at: i64,
+}
+
+struct RefreshSession {
+    wallet_address: String,
+    expires_at: i64,
+}
+
+/// Sign-In-With-Solana: single-use challenges, HMAC-signed access tokens and
+/// rotating refresh tokens. State is in memory, so a restart logs everyone out.
+pub struct AuthService {
+    config: Config,
+    /// Keyed by nonce, so a new challenge never voids someone else's
+    challenges: Mutex<HashMap<String, PendingChallenge>>,
+    /// Challenges issued per client address in the current minute
+    challenge_requests: Mutex<HashMap<IpAddr, (i64, u32)>>,
+    refresh_sessions: Mutex<HashMap<String, RefreshSession>>,
+}
+
+impl AuthService {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            challenges: Mutex::new(HashMap::new()),
+            challenge_requests: Mutex::new(HashMap::new()),
+            refresh_sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Issue a fresh challenge; earlier ones for the wallet stay valid until
+    /// they expire. `client` is the requesting address, for rate limiting.
+    pub fn issue_challenge(
+        &self,
+        wallet_address: &str,
+        client: Option<IpAddr>,
+    ) -> Result<AuthChallenge> {
+        parse_wallet(wallet_address)?;
+
+        let now = chrono::Utc::now();
+        if let Some(client) = client {
+            self.throttle(client, now.timestamp())?;
+        }
+
+        let expires_at = now.timestamp() + self.config.auth.challenge_ttl_secs;
+        let nonce = random_token(16);
+
+        let message = format!(
+            "{domain} wants you to sign in with your Solana account:\n{address}\n\nNonce: {nonce}\nIssued At: {issued}\nExpiration Time: {expires}",
+            domain = self.config.auth.domain,
+            address = wallet_address,
+            nonce = nonce,
+            issued = now.to_rfc3339(),
+            expires = chrono::DateTime::from_timestamp(expires_at, 0)
+                .unwrap_or(now)
+                .to_rfc3339(),
+        );
+
+        let mut challenges = self.challenges.lock().unwrap();
+        challenges.retain(|_, c| c.expires_at > now.timestamp());
+        if challenges.len() >= self.config.auth.max_challenges {
+            return Err(rate_limited(
+                "Too many outstanding challenges, try again later",
+            ));
+        }
+        challenges.insert(
+            nonce,
+            PendingChallenge {
+                wallet_address: wallet_address.to_string(),
+                message: message.clone(),
+                expires_at,
+            },
+        );
+
+        app_log!(info, "Issued auth challenge for {}", wallet_address);
+
+        Ok(AuthChallenge {
+            wallet_address: wallet_address.to_string(),
+            challenge: message,
+            expires_at,
+        })
+    }
+
+    /// Check the signed challenge and start a session
+    pub fn verify(&self, verification: &AuthVerification) -> Result<AuthTokens> {
+        let pubkey = parse_wallet(&verification.wallet_address)?;
+
+        let nonce = verification
+            .challenge
+            .lines()
+            .find_map(|line| line.strip_prefix("Nonce: "))
+            .ok_or_else(|| auth_error("Challenge does not match"))?;
+
+        // Challenges are single-use: consume it before checking anything else
+        let pending = self
+            .challenges
+            .lock()
+            .unwrap()
+            .remove(nonce)
+            .ok_or_else(|| auth_error("No outstanding challenge with this nonce"))?;
+
+        if pending.wallet_address != verification.wallet_address {
+            return Err(auth_error("Challenge was issued to another wallet"));
+        }
+        if pending.expires_at <= chrono::Utc::now().timestamp() {
+            return Err(auth_error("Challenge expired"));
+        }
+        if pending.message != verification.challenge {
+            return Err(auth_error("Challenge does not match"));
+        }
+
+        let signature = Signature::from_str(&verification.signature)
+            .map_err(|_| auth_error("Malformed signature"))?;
+        if !signature.verify(pubkey.as_ref(), pending.message.as_bytes()) {
+            return Err(auth_error("Signature verification failed"));
+        }
+
+        app_log!(info, "Wallet {} authenticated", verification.wallet_address);
+        self.issue_tokens(&verification.wallet_address)
+    }
+
+    /// Exchange a refresh token for a new token pair; the old one is revoked
+    pub fn refresh(&self, refresh_token: &str) -> Result<AuthTokens> {
+        let session = self
+            .refresh_sessions
+            .lock()
+            .unwrap()
+            .remove(refresh_token)
+            .ok_or_else(|| auth_error("Unknown refresh token"))?;
+
+        if session.expires_at <= chrono::Utc::now().timestamp() {
+            return Err(auth_error("Refresh token expired"));
+        }
+
+        self.issue_tokens(&session.wallet_address)
+    }
+
+    /// Count a challenge against the client's allowance for this minute
+    fn throttle(&self, client: IpAddr, now: i64) -> Result<()> {
+        let window = now / 60;
+        let mut requests = self.challenge_requests.lock().unwrap();
+        requests.retain(|_, (start, _)| *start == window);
+
+        let (_, count) = requests.entry(client).or_insert((window, 0));
+        if *count >= self.config.auth.challenges_per_minute {
+            return Err(rate_limited(
+                "Too many challenges requested, try again later",
+            ));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Return the wallet an access token was issued to, if it is still valid
+    pub fn validate_access_token(&self, token: &str) -> Option<String> {
+        let (payload, mac) = token.split_once('.')?;
+        let mac = BASE64_URL.decode(mac).ok()?;
+
+        let mut expected = self.mac().ok()?;
+        expected.update(payload.as_bytes());
+        expected.verify_slice(&mac).ok()?;
+
+        let claims: AccessClaims =
+            serde_json::from_slice(&BASE64_URL.decode(payload).ok()?).ok()?;
+        if claims.exp <= chrono::Utc::now().timestamp() {
+            return None;
+        }
+
+        Some(claims.sub)
+    }
+
+    fn issue_tokens(&self, wallet_address: &str) -> Result<AuthTokens> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = AccessClaims {
+            sub: wallet_address.to_string(),
+            iat: now,
+            exp: now + self.config.auth.access_token_ttl_secs,
+        };
+
+        let payload = BASE64_URL.encode(serde_json::to_vec(&claims)?);
+        let mut mac = self.mac()?;
+        mac.update(payload.as_bytes());
+        let access_token = format!(
+            "{}.{}",
+            payload,
+            BASE64_URL.encode(mac.finalize().into_bytes())
+        );
+
+        let refresh_token = random_token(32);
+        let mut sessions = self.refresh_sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(
+            refresh_token.clone(),
+            RefreshSession {
+                wallet_address: wallet_address.to_string(),
+                expires_at: now + self.config.auth.refresh_token_ttl_secs,
+            },
+        );
+
+        Ok(AuthTokens {
+            wallet_address: wallet_address.to_string(),
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_at: claims.exp,
+        })
+    }
+
+    fn mac(&self) -> Result<Hmac<Sha256>> {
+        let secret = self
+            .config
+            .auth
+            .token_secret
+            .as_deref()
+            .unwrap_or(&self.config.internal.secret);
+        Ok(Hmac::<Sha256>::new_from_slice(secret.as_bytes())?)
+    }
+}
+
+fn parse_wallet(wallet_address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(wallet_address).map_err(|_| {
+        SolanaClientError::InvalidAddress {
+            address: wallet_address.to_string(),
+        }
+        .into()
+    })
+}
+
+fn rate_limited(message: &str) -> anyhow::Error {
+    SolanaClientError::RateLimited {
+        message: message.to_string(),
+    }
+    .into()
+}
+
+fn auth_error(reason: &str) -> anyhow::Error {
+    SolanaClientError::AuthFailed {
+        reason: reason.to_string(),
+    }
+    .into()
+}
+
+fn random_token(len: usize) -> String {
+    let bytes: Vec<u8> = (0..len).map(|_| rand::random::<u8>()).collect();
+    BASE64_URL.encode(bytes)
+}
@@ -0,0 +1,515 @@
+use crate::api::{
+    CleanupAccount, CleanupAction, CleanupRequest, CleanupResponse, CleanupTransaction, DustAction,
+};
+use crate::app_log;
+use crate::audit::Audit;
+use crate::policy::{Intent, Spend};
+use crate::{config::Config, jupiter, rpc, transaction, wallet};
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::Serialize;
+use serde_json::{Value, json};
+use solana_account_decoder::UiAccountData;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    native_token::LAMPORTS_PER_SOL,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, VersionedTransaction},
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::instruction as token_instruction;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Outcome of a cleanup run with the local wallet
+#[derive(Debug, Serialize)]
+pub struct CleanupResult {
+    pub owner: String,
+    pub dry_run: bool,
+    pub accounts: Vec<CleanupAccount>,
+    pub reclaimable_sol: f64,
+    pub reclaimed_sol: f64,
+    pub signatures: Vec<String>,
+    pub failures: Vec<String>, // Batches or swaps that failed; the rest went through
+}
+
+// A token account found for the owner, with what cleanup would do to it
+struct Candidate {
+    address: Pubkey,
+    mint: Pubkey,
+    program_id: Pubkey,
+    amount: u64,
+    decimals: u8,
+    summary: CleanupAccount,
+}
+
+// Token accounts closed together in one transaction
+struct Batch {
+    candidates: Vec<usize>,
+    instructions: Vec<Instruction>,
+}
+
+/// Close the wallet's empty and non-canonical token accounts, and with
+/// `dust_usd` also burn or swap balances worth less than that first.
+/// Each transaction is audited; a failed batch does not stop the others.
+pub async fn execute(
+    config: &Config,
+    actor: &str,
+    dust_usd: Option<f64>,
+    dust_action: DustAction,
+    dry_run: bool,
+) -> Result<CleanupResult> {
+    let keypair = wallet::load_keypair(config).await?;
+    let owner = keypair.pubkey();
+    let client = rpc::client(config);
+
+    let mut candidates = scan(config, &client, &owner, dust_usd, dust_action).await?;
+    let mut result = CleanupResult {
+        owner: owner.to_string(),
+        dry_run,
+        accounts: candidates.iter().map(|c| c.summary.clone()).collect(),
+        reclaimable_sol: reclaimable(&candidates),
+        reclaimed_sol: 0.0,
+        signatures: Vec::new(),
+        failures: Vec::new(),
+    };
+    if dry_run {
+        return Ok(result);
+    }
+
+    let mut errors = Vec::new();
+
+    // Swapped accounts are empty afterwards, so scan again to close them too
+    let swaps: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.summary.action == CleanupAction::Swap)
+        .collect();
+    if !swaps.is_empty() {
+        for candidate in swaps {
+            let mut audit = Audit::new(actor, "cleanup", intent_for(&[candidate]));
+            let swapped: Result<String> = async {
+                let quote = jupiter::get_quote(
+                    config,
+                    &candidate.mint.to_string(),
+                    &config.tokens.sol,
+                    candidate.amount,
+                )
+                .await?;
+                let signature =
+                    jupiter::execute_quote(config, &mut audit, &keypair, quote, spend(candidate))
+                        .await?;
+                Ok(signature.to_string())
+            }
+            .await;
+            match audit.finish(config, swapped, "confirmed") {
+                Ok(signature) => result.signatures.push(signature),
+                Err(e) => {
+                    app_log!(warn, "Dust swap of {} failed: {}", candidate.address, e);
+                    result
+                        .failures
+                        .push(format!("swap {}: {}", candidate.address, e));
+                    errors.push(e);
+                }
+            }
+        }
+        candidates = scan(config, &client, &owner, dust_usd, dust_action).await?;
+    }
+
+    for batch in batches(&owner, &candidates)? {
+        let members: Vec<&Candidate> = batch.candidates.iter().map(|&i| &candidates[i]).collect();
+        let mut audit = Audit::new(actor, "cleanup", intent_for(&members));
+        let sent: Result<String> = async {
+            check_batch(config, &mut audit, &owner, &members, &batch.instructions)?;
+            let signature =
+                transaction::sign_and_send(config, &mut audit, &keypair, &[], &batch.instructions)?;
+            Ok(signature.to_string())
+        }
+        .await;
+        match audit.finish(config, sent, "confirmed") {
+            Ok(signature) => {
+                result.reclaimed_sol += members.iter().map(|c| c.summary.rent).sum::<f64>();
+                result.signatures.push(signature);
+            }
+            Err(e) => {
+                app_log!(warn, "Cleanup batch failed: {}", e);
+                result.failures.push(format!(
+                    "{} account(s) starting at {}: {}",
+                    members.len(),
+                    members[0].address,
+                    e
+                ));
+                errors.push(e);
+            }
+        }
+    }
+
+    // Nothing went through at all: surface the error itself for the exit code
+    if result.signatures.is_empty() && !errors.is_empty() {
+        return Err(errors.remove(0));
+    }
+
+    app_log!(
+        info,
+        "Cleanup reclaimed {} SOL in {} transaction(s)",
+        result.reclaimed_sol,
+        result.signatures.len()
+    );
+    Ok(result)
+}
+
+/// Build the cleanup unsigned for `owner`: Jupiter swaps of dust first, then
+/// the batched closes. Swapped accounts are closed by a later cleanup, once
+/// the swaps have landed and left them empty.
+pub async fn prepare(
+    config: &Config,
+    actor: &str,
+    owner: &Pubkey,
+    request: &CleanupRequest,
+) -> Result<CleanupResponse> {
+    let mut audit = Audit::new(actor, "cleanup", json!(request));
+    let result: Result<CleanupResponse> = async {
+        let client = rpc::client(config);
+        let dust_action = request.dust_action.unwrap_or(DustAction::Burn);
+        let candidates = scan(config, &client, owner, request.dust_usd, dust_action).await?;
+
+        let mut transactions = Vec::new();
+        for candidate in candidates
+            .iter()
+            .filter(|c| c.summary.action == CleanupAction::Swap)
+        {
+            let quote = jupiter::get_quote(
+                config,
+                &candidate.mint.to_string(),
+                &config.tokens.sol,
+                candidate.amount,
+            )
+            .await?;
+            let price_impact = quote.price_impact_pct.parse::<f64>()?;
+            let slippage_bps = quote.slippage_bps;
+            let swap = jupiter::get_swap_transaction(config, quote, owner).await?;
+            let unsigned: VersionedTransaction =
+                bincode::deserialize(&BASE64.decode(&swap.swap_transaction)?)?;
+            audit.check_policy(
+                config,
+                &Intent::swap(
+                    owner,
+                    spend(candidate),
+                    price_impact,
+                    slippage_bps,
+                    &unsigned.message,
+                ),
+            )?;
+
+            transactions.push(CleanupTransaction {
+                unsigned_transaction: swap.swap_transaction,
+                accounts: vec![candidate.address.to_string()],
+                required_signers: vec![owner.to_string()],
+                recent_blockhash: unsigned.message.recent_blockhash().to_string(),
+            });
+        }
+
+        for batch in batches(owner, &candidates)? {
+            let members: Vec<&Candidate> =
+                batch.candidates.iter().map(|&i| &candidates[i]).collect();
+            check_batch(config, &mut audit, owner, &members, &batch.instructions)?;
+
+            let prepared = transaction::prepare_unsigned(config, owner, &batch.instructions)?;
+            transactions.push(CleanupTransaction {
+                unsigned_transaction: prepared.unsigned_transaction,
+                accounts: members.iter().map(|c| c.address.to_string()).collect(),
+                required_signers: prepared.required_signers,
+                recent_blockhash: prepared.recent_blockhash,
+            });
+        }
+
+        app_log!(
+            info,
+            "Prepared cleanup for {}: {} account(s), {} transaction(s)",
+            owner,
+            candidates.len(),
+            transactions.len()
+        );
+
+        Ok(CleanupResponse {
+            owner: owner.to_string(),
+            reclaimable_sol: reclaimable(&candidates),
+            accounts: candidates.into_iter().map(|c| c.summary).collect(),
+            transactions,
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+// Every token account of `owner` under both token programs that cleanup would touch
+async fn scan(
+    config: &Config,
+    client: &RpcClient,
+    owner: &Pubkey,
+    dust_usd: Option<f64>,
+    dust_action: DustAction,
+) -> Result<Vec<Candidate>> {
+    let mut candidates = Vec::new();
+
+    for program_id in [spl_token::id(), spl_token_2022::id()] {
+        let accounts = client
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(program_id))?;
+
+        for keyed in accounts {
+            let UiAccountData::Json(parsed) = &keyed.account.data else {
+                continue;
+            };
+            let Some(info) = parsed.parsed.get("info") else {
+                continue;
+            };
+            let (Some(address), Some(mint)) = (
+                Pubkey::from_str(&keyed.pubkey).ok(),
+                info.get("mint")
+                    .and_then(Value::as_str)
+                    .and_then(|m| Pubkey::from_str(m).ok()),
+            ) else {
+                continue;
+            };
+
+            let token_amount = &info["tokenAmount"];
+            let amount = token_amount["amount"]
+                .as_str()
+                .and_then(|a| a.parse::<u64>().ok())
+                .unwrap_or(0);
+            let decimals = token_amount["decimals"].as_u64().unwrap_or(0) as u8;
+            let balance = token_amount["uiAmount"].as_f64().unwrap_or(0.0);
+            let canonical =
+                get_associated_token_address_with_program_id(owner, &mint, &program_id) == address;
+
+            let mut usd_value = None;
+            let action = if let Some(reason) = blocker(info, owner, amount) {
+                Some((CleanupAction::Skip, Some(reason)))
+            } else if amount == 0 {
+                Some((CleanupAction::Close, None))
+            } else if let Some(threshold) = dust_usd
+                && let Ok(price) = jupiter::get_token_price(config, &mint.to_string()).await
+                && balance * price < threshold
+            {
+                usd_value = Some(balance * price);
+                match dust_action {
+                    DustAction::Burn => Some((CleanupAction::Burn, None)),
+                    DustAction::Swap => Some((CleanupAction::Swap, None)),
+                }
+            } else if !canonical {
+                Some((CleanupAction::Consolidate, None))
+            } else {
+                None
+            };
+
+            let Some((action, note)) = action else {
+                continue;
+            };
+            candidates.push(Candidate {
+                address,
+                mint,
+                program_id,
+                amount,
+                decimals,
+                summary: CleanupAccount {
+                    address: address.to_string(),
+                    mint: mint.to_string(),
+                    balance,
+                    usd_value,
+                    canonical,
+                    action,
+                    rent: keyed.account.lamports as f64 / LAMPORTS_PER_SOL as f64,
+                    note,
+                },
+            });
+        }
+    }
+
+    // Consolidating refills the canonical account, so it cannot be closed as well
+    let targets: HashSet<(Pubkey, Pubkey)> = candidates
+        .iter()
+        .filter(|c| c.summary.action == CleanupAction::Consolidate)
+        .map(|c| (c.mint, c.program_id))
+        .collect();
+    for candidate in candidates.iter_mut().filter(|c| {
+        c.summary.canonical
+            && matches!(c.summary.action, CleanupAction::Close | CleanupAction::Burn)
+            && targets.contains(&(c.mint, c.program_id))
+    }) {
+        candidate.summary.action = CleanupAction::Skip;
+        candidate.summary.note = Some("receives the consolidated balance".to_string());
+    }
+
+    app_log!(
+        info,
+        "Found {} token account(s) to clean up for {}",
+        candidates.len(),
+        owner
+    );
+    Ok(candidates)
+}
+
+// Why the owner cannot close an account that is otherwise a candidate
+fn blocker(info: &Value, owner: &Pubkey, amount: u64) -> Option<String> {
+    if info["state"].as_str() == Some("frozen") {
+        return Some("frozen".to_string());
+    }
+    if let Some(close_authority) = info["closeAuthority"].as_str()
+        && close_authority != owner.to_string()
+    {
+        return Some(format!("close authority is {}", close_authority));
+    }
+    if info["isNative"].as_bool() == Some(true) && amount > 0 {
+        return Some("wrapped SOL, use unwrap".to_string());
+    }
+    let withheld = info["extensions"].as_array().and_then(|extensions| {
+        extensions
+            .iter()
+            .find(|e| e["extension"] == "transferFeeAmount")
+            .and_then(|e| e["state"]["withheldAmount"].as_u64())
+    });
+    if withheld.unwrap_or(0) > 0 {
+        return Some("withheld transfer fees must be harvested first".to_string());
+    }
+    None
+}
+
+// Closes, with a burn or a transfer to the canonical account first where
+// needed, packed into as few transactions as fit the packet size
+fn batches(owner: &Pubkey, candidates: &[Candidate]) -> Result<Vec<Batch>> {
+    let mut batches: Vec<Batch> = Vec::new();
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        let instructions = match candidate.summary.action {
+            CleanupAction::Skip | CleanupAction::Swap => continue,
+            action => clear_and_close(owner, candidate, action)?,
+        };
+
+        if let Some(batch) = batches.last_mut() {
+            let mut combined = batch.instructions.clone();
+            combined.extend(instructions.iter().cloned());
+            if fits(owner, &combined)? {
+                batch.candidates.push(i);
+                batch.instructions = combined;
+                continue;
+            }
+        }
+        batches.push(Batch {
+            candidates: vec![i],
+            instructions,
+        });
+    }
+
+    Ok(batches)
+}
+
+fn clear_and_close(
+    owner: &Pubkey,
+    candidate: &Candidate,
+    action: CleanupAction,
+) -> Result<Vec<Instruction>> {
+    let program_id = &candidate.program_id;
+    let mut instructions = Vec::new();
+
+    match action {
+        CleanupAction::Burn => instructions.push(token_instruction::burn_checked(
+            program_id,
+            &candidate.address,
+            &candidate.mint,
+            owner,
+            &[],
+            candidate.amount,
+            candidate.decimals,
+        )?),
+        CleanupAction::Consolidate => {
+            let canonical =
+                get_associated_token_address_with_program_id(owner, &candidate.mint, program_id);
+            instructions.push(create_associated_token_account_idempotent(
+                owner,
+                owner,
+                &candidate.mint,
+                program_id,
+            ));
+            instructions.push(token_instruction::transfer_checked(
+                program_id,
+                &candidate.address,
+                &candidate.mint,
+                &canonical,
+                owner,
+                &[],
+                candidate.amount,
+                candidate.decimals,
+            )?);
+        }
+        _ => {}
+    }
+
+    instructions.push(token_instruction::close_account(
+        program_id,
+        &candidate.address,
+        owner,
+        owner,
+        &[],
+    )?);
+    Ok(instructions)
+}
+
+fn fits(owner: &Pubkey, instructions: &[Instruction]) -> Result<bool> {
+    let transaction = Transaction::new_unsigned(Message::new(instructions, Some(owner)));
+    Ok(bincode::serialized_size(&transaction)? as usize <= PACKET_DATA_SIZE)
+}
+
+// Closing is not a spend, but burnt dust is, like any burn
+fn check_batch(
+    config: &Config,
+    audit: &mut Audit,
+    owner: &Pubkey,
+    members: &[&Candidate],
+    instructions: &[Instruction],
+) -> Result<()> {
+    audit.check_policy(config, &Intent::instructions(owner, instructions))?;
+    for candidate in members
+        .iter()
+        .filter(|c| c.summary.action == CleanupAction::Burn)
+    {
+        audit.check_policy(
+            config,
+            &Intent {
+                spend: Some(spend(candidate)),
+                ..Intent::instructions(owner, instructions)
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn spend(candidate: &Candidate) -> Spend {
+    Spend {
+        symbol: candidate.mint.to_string(),
+        mint: candidate.mint.to_string(),
+        amount: candidate.summary.balance,
+    }
+}
+
+fn intent_for(candidates: &[&Candidate]) -> Value {
+    json!({
+        "accounts": candidates
+            .iter()
+            .map(|c| json!({ "address": c.summary.address, "mint": c.summary.mint, "action": c.summary.action }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn reclaimable(candidates: &[Candidate]) -> f64 {
+    candidates
+        .iter()
+        .filter(|c| c.summary.action != CleanupAction::Skip)
+        .map(|c| c.summary.rent)
+        .sum()
+}
@@ -1,21 +1,29 @@
 use crate::app_log;
-use crate::{config::Config, jupiter, token, transaction, wallet};
+use crate::contacts::{self, AddressBook};
+use crate::validation::{self, Severity};
+use crate::{audit, config::Config, jupiter, signing, token, transaction, wallet};
 use anyhow::Result;
-use dialoguer::{Confirm, Input, Select, theme::ColorfulTheme};
+use dialoguer::{Completion, Confirm, Input, Select, theme::ColorfulTheme};
 use solana_sdk::signature::Signer;
 
 pub struct InteractiveMenu {
+    base: Config, // Before any profile, so switching starts from a clean slate
     config: Config,
+    mainnet_confirmed: bool, // --confirm-mainnet was given
 }
 
 impl InteractiveMenu {
-    pub fn new(config: Config) -> Self {
-        Self { config }
+    pub fn new(base: Config, config: Config, mainnet_confirmed: bool) -> Self {
+        Self {
+            base,
+            config,
+            mainnet_confirmed,
+        }
     }
 
-    pub async fn run(&self) -> Result<()> {
-        app_log!(info, "\n🚀 Solana CLI Client - Interactive Mode");
-        app_log!(info, "=====================================\n");
+    pub async fn run(&mut self) -> Result<()> {
+        println!("\n🚀 Solana CLI Client - Interactive Mode");
+        println!("=====================================\n");
 
         loop {
             let options = vec![
@@ -31,6 +39,7 @@ impl InteractiveMenu {
                 "📜 Transaction History",
                 "⏳ Pending Transactions",
                 "⚙️  Show Config",
+                "🌐 Switch Profile",
                 "❌ Exit",
             ];
 
@@ -53,26 +62,47 @@ impl InteractiveMenu {
                 9 => self.handle_transaction_history().await?, // Add this line
                 10 => self.handle_pending_transactions().await?, // Add this line
                 11 => self.handle_show_config()?,              // Update: was 9
-                12 => {
+                12 => self.handle_switch_profile()?,
+                13 => {
                     // Update: was 10
-                    app_log!(info, "👋 Goodbye!");
+                    println!("👋 Goodbye!");
                     break;
                 }
                 _ => unreachable!(),
             }
 
-            app_log!(info, "\n{}\n", "=".repeat(50));
+            println!("\n{}\n", "=".repeat(50));
         }
 
         Ok(())
     }
 
     async fn handle_list_wallet_tokens(&self) -> Result<()> {
-        match wallet::list_wallet_tokens(&self.config).await {
-            Ok(_) => {}
+        let keypair = wallet::load_keypair(&self.config).await?;
+
+        match wallet::get_wallet_holdings(&self.config, &keypair.pubkey()).await {
+            Ok(tokens) if tokens.is_empty() => println!("💸 No tokens found in wallet"),
+            Ok(tokens) => {
+                println!("🪙 Wallet Token Holdings:");
+                println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                for (i, token) in tokens.iter().enumerate() {
+                    println!(
+                        "{}. {} ({}) - {} tokens",
+                        i + 1,
+                        token.symbol,
+                        token.name,
+                        wallet::format_balance(token.balance)
+                    );
+                    if let Some(usd_value) = token.usd_value {
+                        println!("   💲 ~${:.2}", usd_value);
+                    }
+                    println!("   📍 {}", token.mint);
+                    println!();
+                }
+            }
             Err(e) => {
                 app_log!(error, "Failed to list wallet tokens: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -86,9 +116,12 @@ impl InteractiveMenu {
             .interact()?;
 
         if confirm {
-            wallet::generate_wallet(&self.config).await?;
+            let pubkey = wallet::generate_wallet(&self.config).await?;
+            println!("✅ Wallet generated successfully!");
+            println!("📍 Public Key: {}", pubkey);
+            println!("💾 Saved to: {}", self.config.wallet.keypair_path);
         } else {
-            app_log!(info, "Operation cancelled.");
+            println!("Operation cancelled.");
         }
 
         Ok(())
@@ -97,11 +130,11 @@ impl InteractiveMenu {
     async fn handle_check_balance(&self) -> Result<()> {
         match wallet::get_balance(&self.config).await {
             Ok(balance) => {
-                app_log!(info, "💰 Current Balance: {} SOL", balance);
+                println!("💰 Current Balance: {} SOL", balance);
             }
             Err(e) => {
                 app_log!(error, "Failed to get balance: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -115,15 +148,19 @@ impl InteractiveMenu {
             .interact()?;
 
         if amount <= 0.0 {
-            app_log!(info, "❌ Amount must be positive");
+            println!("❌ Amount must be positive");
             return Ok(());
         }
 
         match wallet::request_airdrop(&self.config, amount).await {
-            Ok(_) => app_log!(info, "✅ Airdrop completed successfully!"),
+            Ok((signature, balance)) => {
+                println!("✅ Airdrop completed successfully!");
+                println!("🔗 Signature: {}", signature);
+                println!("💰 New balance: {} SOL", balance);
+            }
             Err(e) => {
                 app_log!(error, "Airdrop failed: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -131,28 +168,66 @@ impl InteractiveMenu {
     }
 
     async fn handle_create_transaction(&self) -> Result<()> {
-        let to_address: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Recipient address")
-            .interact()?;
+        if !self.confirm_mainnet("Create a transaction")? {
+            return Ok(());
+        }
+
+        let book = AddressBook::load(&self.config)?;
+        let input: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Recipient address or @contact (Tab completes)")
+            .completion_with(&ContactCompletion(&book))
+            .interact_text()?;
+        let to_address = match book.resolve(input.trim()) {
+            Ok(address) => address,
+            Err(e) => {
+                println!("❌ {}", e);
+                return Ok(());
+            }
+        };
+
+        if contacts::is_new_recipient(&self.config, &to_address)?
+            && !Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "⚠️  {} is not a contact and was never sent to before. Continue?",
+                    to_address
+                ))
+                .default(false)
+                .interact()?
+        {
+            return Ok(());
+        }
 
         let amount: f64 = Input::with_theme(&ColorfulTheme::default())
             .with_prompt("Amount (SOL)")
             .interact()?;
 
         if amount <= 0.0 {
-            app_log!(info, "❌ Amount must be positive");
+            println!("❌ Amount must be positive");
             return Ok(());
         }
 
-        match transaction::create_transaction(&self.config, &to_address, amount).await {
+        let memo: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Memo (optional)")
+            .allow_empty(true)
+            .interact()?;
+
+        match transaction::create_transaction(
+            &self.config,
+            &audit::cli_actor(),
+            &to_address,
+            amount,
+            Some(memo.as_str()).filter(|memo| !memo.is_empty()),
+        )
+        .await
+        {
             Ok(tx_data) => {
-                app_log!(info, "✅ Transaction created successfully!");
-                app_log!(info, "📋 Copy this transaction data to send later:");
-                app_log!(info, "{}", tx_data);
+                println!("✅ Transaction created successfully!");
+                println!("📋 Copy this transaction data to send later:");
+                println!("{}", tx_data);
             }
             Err(e) => {
                 app_log!(error, "Transaction creation failed: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -160,6 +235,10 @@ impl InteractiveMenu {
     }
 
     async fn handle_send_transaction(&self) -> Result<()> {
+        if !self.confirm_mainnet("Send a transaction")? {
+            return Ok(());
+        }
+
         let tx_data: String = Input::with_theme(&ColorfulTheme::default())
             .with_prompt("Transaction data")
             .interact()?;
@@ -170,15 +249,18 @@ impl InteractiveMenu {
             .interact()?;
 
         if !confirm {
-            app_log!(info, "Transaction cancelled.");
+            println!("Transaction cancelled.");
             return Ok(());
         }
 
-        match transaction::send_transaction(&self.config, &tx_data).await {
-            Ok(_) => app_log!(info, "✅ Transaction sent successfully!"),
+        match transaction::send_transaction(&self.config, &audit::cli_actor(), &tx_data).await {
+            Ok(signature) => {
+                println!("✅ Transaction sent successfully!");
+                println!("🔗 Signature: {}", signature);
+            }
             Err(e) => {
                 app_log!(error, "Transaction send failed: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -186,20 +268,23 @@ impl InteractiveMenu {
     }
 
     async fn handle_swap_tokens(&self) -> Result<()> {
-        app_log!(info, "🔄 Token Swap");
+        if !self.confirm_mainnet("Swap tokens")? {
+            return Ok(());
+        }
+
+        println!("🔄 Token Swap");
 
         // First, show available tokens in wallet
         let wallet_tokens = wallet::get_wallet_tokens(&self.config).await?;
 
         if wallet_tokens.is_empty() {
-            app_log!(info, "❌ No tokens found in wallet. Get some tokens first!");
+            println!("❌ No tokens found in wallet. Get some tokens first!");
             return Ok(());
         }
 
-        app_log!(info, "\n💼 Available tokens in your wallet:");
+        println!("\n💼 Available tokens in your wallet:");
         for (i, token) in wallet_tokens.iter().enumerate().take(10) {
-            app_log!(
-                info,
+            println!(
                 "{}. {} - {} tokens",
                 i + 1,
                 token.symbol,
@@ -220,7 +305,7 @@ impl InteractiveMenu {
             .interact()?;
 
         if amount <= 0.0 {
-            app_log!(info, "❌ Amount must be positive");
+            println!("❌ Amount must be positive");
             return Ok(());
         }
 
@@ -228,15 +313,14 @@ impl InteractiveMenu {
         match jupiter::get_token_price(&self.config, &from_token).await {
             Ok(price) => {
                 let estimated_value = amount * price;
-                app_log!(
-                    info,
+                println!(
                     "💲 Current {} price: ${:.6}",
                     from_token.to_uppercase(),
                     price
                 );
-                app_log!(info, "💰 Estimated value: ${:.2}", estimated_value);
+                println!("💰 Estimated value: ${:.2}", estimated_value);
             }
-            Err(_) => app_log!(info, "⚠️  Could not fetch current price"),
+            Err(_) => println!("⚠️  Could not fetch current price"),
         }
 
         let confirm = Confirm::with_theme(&ColorfulTheme::default())
@@ -245,15 +329,34 @@ impl InteractiveMenu {
             .interact()?;
 
         if !confirm {
-            app_log!(info, "Swap cancelled.");
+            println!("Swap cancelled.");
             return Ok(());
         }
 
-        match jupiter::swap_tokens(&self.config, &from_token, &to_token, amount).await {
-            Ok(_) => app_log!(info, "✅ Swap completed successfully!"),
+        match jupiter::swap_tokens(
+            &self.config,
+            &audit::cli_actor(),
+            &from_token,
+            &to_token,
+            amount,
+        )
+        .await
+        {
+            Ok(swap) => {
+                println!("✅ Swap completed successfully!");
+                println!("🔗 Signature: {}", swap.signature);
+                println!(
+                    "💰 Swapped {} {} for ~{:.6} {} (price impact {:.4}%)",
+                    swap.amount,
+                    swap.from_token,
+                    swap.quote.expected_output,
+                    swap.to_token,
+                    swap.quote.price_impact
+                );
+            }
             Err(e) => {
                 app_log!(error, "Swap failed: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -268,23 +371,18 @@ impl InteractiveMenu {
 
         match jupiter::get_token_price(&self.config, &token).await {
             Ok(price) => {
-                app_log!(info, "💲 {} price: ${:.6}", token.to_uppercase(), price);
+                println!("💲 {} price: ${:.6}", token.to_uppercase(), price);
 
                 // Also show token info if available
                 if let Ok(Some(token_info)) = token::get_token_info(&self.config, &token).await {
-                    app_log!(
-                        info,
-                        "📝 Token: {} ({})",
-                        token_info.name,
-                        token_info.symbol
-                    );
-                    app_log!(info, "📍 Address: {}", token_info.address);
-                    app_log!(info, "🔢 Decimals: {}", token_info.decimals);
+                    println!("📝 Token: {} ({})", token_info.name, token_info.symbol);
+                    println!("📍 Address: {}", token_info.address);
+                    println!("🔢 Decimals: {}", token_info.decimals);
                 }
             }
             Err(e) => {
                 app_log!(error, "Failed to get price: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -297,19 +395,18 @@ impl InteractiveMenu {
             .interact()?;
 
         if query.trim().is_empty() {
-            app_log!(info, "❌ Search query cannot be empty");
+            println!("❌ Search query cannot be empty");
             return Ok(());
         }
 
         match token::search_tokens(&self.config, &query).await {
             Ok(tokens) => {
                 if tokens.is_empty() {
-                    app_log!(info, "🔍 No tokens found for '{}'", query);
+                    println!("🔍 No tokens found for '{}'", query);
                 } else {
-                    app_log!(info, "\n📋 Search Results:");
+                    println!("\n📋 Search Results:");
                     for (i, token) in tokens.iter().enumerate() {
-                        app_log!(
-                            info,
+                        println!(
                             "{}. {} ({}) - {}",
                             i + 1,
                             token.symbol,
@@ -321,7 +418,7 @@ impl InteractiveMenu {
                         if let Ok(price) =
                             jupiter::get_token_price(&self.config, &token.symbol).await
                         {
-                            app_log!(info, "   💲 Price: ${:.6}", price);
+                            println!("   💲 Price: ${:.6}", price);
                         }
                     }
 
@@ -346,7 +443,7 @@ impl InteractiveMenu {
             }
             Err(e) => {
                 app_log!(error, "Token search failed: {}", e);
-                app_log!(info, "❌ Error: {}", e);
+                println!("❌ Error: {}", e);
             }
         }
 
@@ -354,38 +451,95 @@ impl InteractiveMenu {
     }
 
     async fn show_token_details(&self, token: &token::TokenInfo) -> Result<()> {
-        app_log!(info, "\n🪙 Token Details:");
-        app_log!(info, "━━━━━━━━━━━━━━━━━━━━━━");
-        app_log!(info, "📛 Symbol: {}", token.symbol);
-        app_log!(info, "📝 Name: {}", token.name);
-        app_log!(info, "📍 Address: {}", token.address);
-        app_log!(info, "🔢 Decimals: {}", token.decimals);
+        println!("\n🪙 Token Details:");
+        println!("━━━━━━━━━━━━━━━━━━━━━━");
+        println!("📛 Symbol: {}", token.symbol);
+        println!("📝 Name: {}", token.name);
+        println!("📍 Address: {}", token.address);
+        println!("🔢 Decimals: {}", token.decimals);
 
         if !token.tags.is_empty() {
-            app_log!(info, "🏷️  Tags: {}", token.tags.join(", "));
+            println!("🏷️  Tags: {}", token.tags.join(", "));
         }
 
         // Get current price
         match jupiter::get_token_price(&self.config, &token.symbol).await {
-            Ok(price) => app_log!(info, "💲 Current Price: ${:.6}", price),
-            Err(_) => app_log!(info, "💲 Price: Not available"),
+            Ok(price) => println!("💲 Current Price: ${:.6}", price),
+            Err(_) => println!("💲 Price: Not available"),
         }
 
         if let Some(logo) = &token.logo_uri {
-            app_log!(info, "🖼️  Logo: {}", logo);
+            println!("🖼️  Logo: {}", logo);
+        }
+
+        Ok(())
+    }
+
+    // On mainnet every fund-moving action is confirmed, unless --confirm-mainnet was given
+    fn confirm_mainnet(&self, operation: &str) -> Result<bool> {
+        if !self.config.is_mainnet() || self.mainnet_confirmed {
+            return Ok(true);
+        }
+
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("⚠️  {} on MAINNET with real funds?", operation))
+            .default(false)
+            .interact()?;
+
+        if !confirmed {
+            println!("Cancelled.");
+        }
+        Ok(confirmed)
+    }
+
+    fn handle_switch_profile(&mut self) -> Result<()> {
+        let mut names: Vec<&String> = self.base.profiles.keys().collect();
+        names.sort();
+
+        if names.is_empty() {
+            println!("❌ No profiles configured");
+            return Ok(());
+        }
+
+        let current = names
+            .iter()
+            .position(|name| Some(*name) == self.config.profile.as_ref())
+            .unwrap_or(0);
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Profile")
+            .items(&names)
+            .default(current)
+            .interact()?;
+
+        match self.base.with_profile(names[selection]) {
+            Ok(config) => {
+                self.config = config;
+                println!(
+                    "✅ Switched to {} ({})",
+                    names[selection], self.config.solana.rpc_url
+                );
+                if self.config.is_mainnet() {
+                    println!("⚠️  MAINNET: transactions move real funds");
+                }
+            }
+            Err(e) => println!("❌ Error: {}", e),
         }
 
         Ok(())
     }
 
     fn handle_show_config(&self) -> Result<()> {
-        app_log!(info, "⚙️  Current Configuration:");
-        app_log!(info, "Network: {}", self.config.solana.network);
-        app_log!(info, "RPC URL: {}", self.config.solana.rpc_url);
-        app_log!(info, "Wallet Path: {}", self.config.wallet.keypair_path);
-        app_log!(info, "Log Level: {}", self.config.logging.level);
-        app_log!(info, "Jupiter API: {}", self.config.jupiter.api_url);
-        app_log!(info, "Slippage: {}bps", self.config.jupiter.slippage_bps);
+        println!("⚙️  Current Configuration:");
+        println!(
+            "Profile: {}",
+            self.config.profile.as_deref().unwrap_or("(none)")
+        );
+        println!("Network: {}", self.config.solana.network);
+        println!("RPC URL: {}", self.config.solana.rpc_url);
+        println!("Wallet Path: {}", self.config.wallet.keypair_path);
+        println!("Log Level: {}", self.config.logging.level);
+        println!("Jupiter API: {}", self.config.jupiter.api_url);
+        println!("Slippage: {}bps", self.config.jupiter.slippage_bps);
 
         Ok(())
     }
@@ -408,25 +562,27 @@ impl InteractiveMenu {
         {
             Ok(history) => {
                 if history.is_empty() {
-                    app_log!(info, "No transactions found");
+                    println!("No transactions found");
                 } else {
-                    app_log!(info, "\nTransaction History:");
+                    println!("\nTransaction History:");
                     for (i, tx) in history.iter().enumerate() {
-                        app_log!(info, "{}. {} | {:?}", i + 1, &tx.signature[..8], tx.status);
+                        println!("{}. {} | {:?}", i + 1, &tx.signature[..8], tx.status);
                         if let Some(amount) = tx.amount {
-                            app_log!(
-                                info,
+                            println!(
                                 "   Amount: {} {}",
                                 amount,
                                 tx.token_symbol.as_deref().unwrap_or("Unknown")
                             );
                         }
+                        if let Some(memo) = &tx.memo {
+                            println!("   Memo: {}", memo);
+                        }
                     }
                 }
             }
             Err(e) => {
                 app_log!(error, "Failed to get transaction history: {}", e);
-                app_log!(info, "Error: {}", e);
+                println!("Error: {}", e);
             }
         }
         Ok(())
@@ -438,19 +594,104 @@ impl InteractiveMenu {
         match transaction::fetch_pending_transactions(&self.config, &keypair.pubkey()).await {
             Ok(pending) => {
                 if pending.is_empty() {
-                    app_log!(info, "No pending transactions");
+                    println!("No pending transactions");
                 } else {
-                    app_log!(info, "\nPending Transactions:");
+                    println!("\nPending Transactions:");
                     for (i, tx) in pending.iter().enumerate() {
-                        app_log!(info, "{}. {} | {:?}", i + 1, &tx.signature[..8], tx.status);
+                        println!("{}. {} | {:?}", i + 1, &tx.signature[..8], tx.status);
                     }
                 }
             }
             Err(e) => {
                 app_log!(error, "Failed to get pending transactions: {}", e);
-                app_log!(info, "Error: {}", e);
+                println!("Error: {}", e);
             }
         }
         Ok(())
     }
 }
+
+// Completes `@` followed by the start of a contact's name
+struct ContactCompletion<'a>(&'a AddressBook);
+
+impl Completion for ContactCompletion<'_> {
+    fn get(&self, input: &str) -> Option<String> {
+        let prefix = input.strip_prefix('@')?;
+        self.0
+            .names_starting_with(prefix)
+            .next()
+            .map(|name| format!("@{}", name))
+    }
+}
+
+/// Ask for the few settings that differ per network and write a complete config.
+/// Prompts go to stderr, like dialoguer's; returns whether the file was written.
+pub fn config_wizard(path: &str) -> Result<bool> {
+    let theme = ColorfulTheme::default();
+    eprintln!("\n🛠️  Solanize configuration wizard");
+
+    if std::path::Path::new(path).exists()
+        && !Confirm::with_theme(&theme)
+            .with_prompt(format!("{} already exists, overwrite it?", path))
+            .default(false)
+            .interact()?
+    {
+        return Ok(false);
+    }
+
+    let networks = [
+        ("devnet", "https://api.devnet.solana.com"),
+        ("testnet", "https://api.testnet.solana.com"),
+        ("mainnet-beta", "https://api.mainnet-beta.solana.com"),
+        ("localnet", "http://127.0.0.1:8899"),
+    ];
+    let names: Vec<&str> = networks.iter().map(|(name, _)| *name).collect();
+    let selection = Select::with_theme(&theme)
+        .with_prompt("Network")
+        .items(&names)
+        .default(0)
+        .interact()?;
+    let (network, default_rpc) = networks[selection];
+
+    let mut config = Config::default();
+    config.solana.network = network.to_string();
+    config.solana.rpc_url = Input::with_theme(&theme)
+        .with_prompt("RPC URL")
+        .default(default_rpc.to_string())
+        .interact_text()?;
+    config.wallet.keypair_path = Input::with_theme(&theme)
+        .with_prompt("Wallet keypair path")
+        .default(config.wallet.keypair_path.clone())
+        .interact_text()?;
+    config.jupiter.slippage_bps = Input::with_theme(&theme)
+        .with_prompt("Swap slippage (bps)")
+        .default(config.jupiter.slippage_bps)
+        .interact_text()?;
+
+    // Jupiter only routes mainnet liquidity; elsewhere USDC is Circle's test mint
+    if network != "mainnet-beta" {
+        config.jupiter.enabled = false;
+        config.tokens.usdc = "4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".to_string();
+    }
+
+    let secret: [u8; 32] = rand::random();
+    config.internal.secret = signing::to_hex(&secret);
+
+    let issues = validation::validate(&config);
+    for issue in &issues {
+        eprintln!("⚠️  {}: {}", issue.key, issue.message);
+    }
+    if issues.iter().any(|i| i.severity == Severity::Error)
+        && !Confirm::with_theme(&theme)
+            .with_prompt("The config has errors, write it anyway?")
+            .default(false)
+            .interact()?
+    {
+        return Ok(false);
+    }
+
+    std::fs::write(path, serde_yaml::to_string(&config)?)?;
+    app_log!(info, "Wrote {} config to {}", network, path);
+
+    Ok(true)
+}
@@ -1,10 +1,22 @@
 use crate::app_log;
+use crate::error::SolanaClientError;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Resolved in layers: built-in defaults, the YAML file, the selected profile,
+/// `SOLANIZE_*` environment variables, then command-line flags (see
+/// `Config::load` and `Config::with_profile`).
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Config {
+    /// Profile applied when `--profile` is not given
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileConfig>,
     pub solana: SolanaConfig,
     pub wallet: WalletConfig,
     pub faucet: FaucetConfig,
@@ -12,6 +24,35 @@ pub struct Config {
     pub jupiter: JupiterConfig,
     pub tokens: TokensConfig,
     pub internal: InternalConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub liquid_staking: LiquidStakingConfig,
+    #[serde(default)]
+    pub pay: PayConfig,
+    #[serde(default)]
+    pub contacts: ContactsConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    /// Where each non-default value came from, keyed by dotted path
+    #[serde(skip)]
+    pub sources: BTreeMap<String, String>,
+    /// Environment and flag layers as (dotted path, raw value, source), kept
+    /// so they still win over a profile applied afterwards
+    #[serde(skip)]
+    overrides: Vec<(String, String, String)>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -19,13 +60,376 @@ pub struct InternalConfig {
     /// Shared secret used by the gateway to authenticate requests.
     /// Override at runtime via CLI_INTERNAL_SECRET env var.
     pub secret: String,
+    /// HMAC request-signing keys. Once any key is configured the bare
+    /// `Bearer <secret>` header is no longer accepted.
+    #[serde(default)]
+    pub keys: Vec<ApiKeyConfig>,
+    /// How far a signed request's timestamp may drift from server time
+    #[serde(default = "default_max_clock_skew_secs")]
+    pub max_clock_skew_secs: i64,
+}
+
+fn default_max_clock_skew_secs() -> i64 {
+    300
+}
+
+impl Default for InternalConfig {
+    fn default() -> Self {
+        Self {
+            secret: "change-me-in-production".to_string(),
+            keys: Vec::new(),
+            max_clock_skew_secs: default_max_clock_skew_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ApiKeyConfig {
+    pub id: String, // Sent as X-Solanize-Key-Id
+    pub secret: String,
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>, // RFC 3339; keep the old key until then when rotating
+    #[serde(default = "default_key_scopes")]
+    pub scopes: Vec<KeyScope>,
+}
+
+fn default_key_scopes() -> Vec<KeyScope> {
+    vec![KeyScope::Read]
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyScope {
+    Read,   // Queries, quotes and unsigned transaction preparation
+    Submit, // Also broadcasting transactions and managing webhooks
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Where webhook registrations are persisted
+    pub store_path: String,
+    /// Deliveries that exhausted their retries are appended here (JSON lines)
+    pub dead_letter_path: String,
+    /// Last processed signature per watched address, to catch up after a
+    /// reconnect or restart
+    pub cursor_path: String,
+    /// Signatures looked back over per account when catching up
+    pub max_backfill: usize,
+    /// HMAC key for the signature header; falls back to the internal secret.
+    /// Override at runtime via CLI_WEBHOOK_SECRET env var.
+    pub signing_secret: Option<String>,
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+    /// Ceiling for the doubled retry delay
+    pub max_backoff_ms: u64,
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            store_path: "./webhooks.json".to_string(),
+            dead_letter_path: "./webhooks-dead-letter.jsonl".to_string(),
+            cursor_path: "./webhook-cursors.json".to_string(),
+            max_backfill: 1000,
+            signing_secret: None,
+            max_retries: 5,
+            retry_backoff_ms: 1000,
+            max_backoff_ms: 60000,
+            timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Domain shown in the Sign-In-With-Solana message
+    pub domain: String,
+    pub challenge_ttl_secs: i64,
+    /// Challenges one client address may request per minute
+    pub challenges_per_minute: u32,
+    /// Outstanding challenges kept at once, across all wallets
+    pub max_challenges: usize,
+    pub access_token_ttl_secs: i64,
+    pub refresh_token_ttl_secs: i64,
+    /// HMAC key for access tokens; falls back to the internal secret.
+    /// Override at runtime via CLI_AUTH_TOKEN_SECRET env var.
+    pub token_secret: Option<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            domain: "solanize".to_string(),
+            challenge_ttl_secs: 300,
+            challenges_per_minute: 10,
+            max_challenges: 10000,
+            access_token_ttl_secs: 900,
+            refresh_token_ttl_secs: 7 * 24 * 3600,
+            token_secret: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    /// Responses for Idempotency-Key requests are persisted here
+    pub store_path: String,
+    /// How long a key is remembered
+    pub window_secs: i64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            store_path: "./idempotency.json".to_string(),
+            window_secs: 86400,
+        }
+    }
+}
+
+/// Spending rules every transaction is checked against before it is signed or
+/// handed out. Empty lists and missing limits mean "no restriction".
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Keyed by token symbol (`SOL`, `USDC`) or mint address, in whole tokens
+    pub limits: HashMap<String, TokenLimit>,
+    /// When non-empty, transfers may only go to these addresses
+    pub allowed_recipients: Vec<String>,
+    pub denied_recipients: Vec<String>,
+    pub max_price_impact_pct: Option<f64>,
+    pub max_slippage_bps: Option<u16>,
+    /// When non-empty, every instruction must invoke one of these program ids
+    pub allowed_programs: Vec<String>,
+    /// Approved spends of the last 24h, per wallet, for the daily limits
+    pub ledger_path: String,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            limits: HashMap::new(),
+            allowed_recipients: Vec::new(),
+            denied_recipients: Vec::new(),
+            max_price_impact_pct: None,
+            max_slippage_bps: None,
+            allowed_programs: Vec::new(),
+            ledger_path: "./policy-ledger.json".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct TokenLimit {
+    pub per_transaction: Option<f64>,
+    pub daily: Option<f64>, // Rolling 24h window
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Hash-chained journal of every prepared, signed or submitted transaction
+    pub path: String,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            path: "./audit.jsonl".to_string(),
+        }
+    }
+}
+
+/// Address book behind `contacts` and `@name` addresses
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ContactsConfig {
+    pub path: String,
+}
+
+impl Default for ContactsConfig {
+    fn default() -> Self {
+        Self {
+            path: "./contacts.json".to_string(),
+        }
+    }
+}
+
+/// Transaction history reads
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Transactions a memo search looks at per call before handing back a cursor
+    pub max_scanned: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self { max_scanned: 500 }
+    }
+}
+
+/// SPL stake pools `liquid-stake` can deposit into
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct LiquidStakingConfig {
+    /// Stake pool address by LST symbol; the program and mint are read from the pool
+    pub pools: BTreeMap<String, String>,
+}
+
+impl Default for LiquidStakingConfig {
+    fn default() -> Self {
+        Self {
+            pools: BTreeMap::from([
+                (
+                    "JITOSOL".to_string(),
+                    "Jito4APyf642JPZPx3hGc6WWJ8zPKtRbRs4P815Awbb".to_string(),
+                ),
+                (
+                    "BSOL".to_string(),
+                    "stk9ApL5HeVAwPLr3TLhDXdZS8ptVu7zp6ov8HFDuMi".to_string(),
+                ),
+            ]),
+        }
+    }
+}
+
+impl LiquidStakingConfig {
+    /// Pool address for a configured symbol, case-insensitive
+    pub fn pool_for(&self, symbol: &str) -> Option<&String> {
+        self.pools
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(symbol))
+            .map(|(_, pool)| pool)
+    }
+}
+
+/// Solana Pay transfer requests
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct PayConfig {
+    /// Merchant address paid when a request names none; defaults to the wallet
+    pub recipient: Option<String>,
+    /// Shown by the payer's wallet when a request has no label of its own
+    pub label: Option<String>,
+    /// Icon (SVG, PNG or WebP) served to wallets opening a transaction request
+    pub icon: Option<String>,
+    /// Public URL of the `/solana` mount, for transaction request links
+    pub public_url: Option<String>,
+    /// How long a request stays payable
+    pub expires_in_secs: u64,
+    /// How often a watcher looks the reference up
+    pub poll_interval_secs: u64,
+}
+
+impl Default for PayConfig {
+    fn default() -> Self {
+        Self {
+            recipient: None,
+            label: None,
+            icon: None,
+            public_url: None,
+            expires_in_secs: 900,
+            poll_interval_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Keep on localhost unless a container network sits in front
+    pub address: String,
+    pub port: u16,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1".to_string(),
+            port: 9876,
+        }
+    }
+}
+
+/// Remote server the CLI authenticates against
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Base URL including the mount point, e.g. http://127.0.0.1:9876/solana
+    pub url: Option<String>,
+    /// Where `auth login` stores the access and refresh tokens
+    pub session_path: String,
+    /// Sign requests with this key instead of using the stored session.
+    /// Override the secret at runtime via CLI_REMOTE_KEY_SECRET env var.
+    pub key_id: Option<String>,
+    pub key_secret: Option<String>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            session_path: "./session.json".to_string(),
+            key_id: None,
+            key_secret: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SolanaConfig {
     pub network: String,
     pub rpc_url: String,
+    /// PubSub endpoint; derived from `rpc_url` when omitted
+    #[serde(default)]
+    pub ws_url: Option<String>,
     pub commitment: String,
+    /// How long a signature watch waits to reach finalized
+    #[serde(default = "default_signature_timeout_secs")]
+    pub signature_timeout_secs: u64,
+}
+
+fn default_signature_timeout_secs() -> u64 {
+    120
+}
+
+impl Default for SolanaConfig {
+    fn default() -> Self {
+        Self {
+            network: "devnet".to_string(),
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            ws_url: None,
+            commitment: "confirmed".to_string(),
+            signature_timeout_secs: default_signature_timeout_secs(),
+        }
+    }
+}
+
+impl SolanaConfig {
+    pub fn websocket_url(&self) -> String {
+        match &self.ws_url {
+            Some(url) => url.clone(),
+            None => self
+                .rpc_url
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1),
+        }
+    }
+
+    pub fn commitment_config(&self) -> Result<CommitmentConfig> {
+        CommitmentConfig::from_str(&self.commitment).map_err(|_| {
+            SolanaClientError::ConfigError {
+                message: format!("Unknown commitment level: {}", self.commitment),
+            }
+            .into()
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -33,15 +437,68 @@ pub struct WalletConfig {
     pub keypair_path: String,
 }
 
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self {
+            keypair_path: "./wallet.json".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct FaucetConfig {
     pub airdrop_amount: f64,
 }
 
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            airdrop_amount: 1.0,
+        }
+    }
+}
+
+/// Diagnostic logs only; command results always go to stdout
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct LoggingConfig {
-    pub level: String,
-    pub format: String,
+    pub level: String,  // Default for every module
+    pub format: String, // pretty or json
+    /// `stderr`, `stdout`, or a file path
+    pub output: String,
+    /// Levels by tracing target, overriding `level`, e.g. `cli_solanize::web: debug`
+    pub modules: BTreeMap<String, String>,
+    pub rotation: LogRotationConfig,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: "pretty".to_string(),
+            output: "stderr".to_string(),
+            modules: BTreeMap::from([("rocket".to_string(), "off".to_string())]),
+            rotation: LogRotationConfig::default(),
+        }
+    }
+}
+
+/// Applies when `output` is a file: it is renamed to `<file>.1` once it grows
+/// past `max_size_mb`, shifting older ones up to `<file>.<max_files>`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct LogRotationConfig {
+    pub max_size_mb: u64, // 0 disables rotation
+    pub max_files: usize,
+}
+
+impl Default for LogRotationConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: 10,
+            max_files: 5,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -49,33 +506,441 @@ pub struct JupiterConfig {
     pub api_url: String,
     pub price_api_url: String,
     pub slippage_bps: u16,
+    /// How long the full token list is reused before refetching
+    #[serde(default = "default_token_list_ttl_secs")]
+    pub token_list_ttl_secs: u64,
+    /// Jupiter only routes mainnet liquidity; profiles turn this off elsewhere
+    #[serde(default = "default_jupiter_enabled")]
+    pub enabled: bool,
+}
+
+fn default_jupiter_enabled() -> bool {
+    true
+}
+
+impl Default for JupiterConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://quote-api.jup.ag/v6".to_string(),
+            price_api_url: "https://lite-api.jup.ag/price/v3".to_string(),
+            slippage_bps: 50,
+            token_list_ttl_secs: default_token_list_ttl_secs(),
+            enabled: default_jupiter_enabled(),
+        }
+    }
+}
+
+fn default_token_list_ttl_secs() -> u64 {
+    3600
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TokensConfig {
     pub sol: String,
     pub usdc: String,
+    /// Further symbols, e.g. `bonk: "DezX..."`
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+impl Default for TokensConfig {
+    fn default() -> Self {
+        Self {
+            sol: "So11111111111111111111111111111111111111112".to_string(),
+            usdc: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+            other: HashMap::new(),
+        }
+    }
+}
+
+impl TokensConfig {
+    /// Mint for a known symbol, case-insensitive
+    pub fn mint_for(&self, symbol: &str) -> Option<&String> {
+        match symbol.to_uppercase().as_str() {
+            "SOL" => Some(&self.sol),
+            "USDC" => Some(&self.usdc),
+            _ => self
+                .other
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(symbol))
+                .map(|(_, mint)| mint),
+        }
+    }
+
+    /// Uppercase symbol configured for a mint
+    pub fn symbol_for(&self, mint: &str) -> Option<String> {
+        if mint == self.sol {
+            return Some("SOL".to_string());
+        }
+        if mint == self.usdc {
+            return Some("USDC".to_string());
+        }
+        self.other
+            .iter()
+            .find(|(_, other)| *other == mint)
+            .map(|(name, _)| name.to_uppercase())
+    }
+}
+
+/// Everything that differs between clusters. Unset fields keep the values
+/// from the top-level sections.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProfileConfig {
+    /// devnet, testnet, mainnet or localnet; defaults to the profile name
+    #[serde(default)]
+    pub network: Option<String>,
+    pub rpc_url: String,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    #[serde(default)]
+    pub commitment: Option<String>,
+    #[serde(default)]
+    pub tokens: Option<TokensConfig>,
+    #[serde(default)]
+    pub jupiter: Option<bool>, // Whether swaps and prices are available
+    #[serde(default)]
+    pub keypair_path: Option<String>,
 }
 
 impl Config {
-    pub fn load(path: &str) -> Result<Self> {
-        app_log!(info, "Loading config from: {}", path);
-        let content = fs::read_to_string(path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
-
-        // Allow overriding the internal secret via environment variable
-        // (so config.yaml can stay on disk with a placeholder)
-        if let Ok(secret) = std::env::var("CLI_INTERNAL_SECRET") {
-            if !secret.is_empty() {
-                config.internal.secret = secret;
+    /// This config with the named profile applied over the file layer;
+    /// environment variables and flags still take precedence over it
+    pub fn with_profile(&self, name: &str) -> Result<Config> {
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            let mut known: Vec<&String> = self.profiles.keys().collect();
+            known.sort();
+            SolanaClientError::ConfigError {
+                message: format!("Unknown profile '{}' (available: {:?})", name, known),
             }
+        })?;
+
+        let mut config = self.clone();
+        let mut applied = vec!["solana.network", "solana.rpc_url", "solana.ws_url"];
+        config.profile = Some(name.to_string());
+        config.solana.network = profile.network.clone().unwrap_or_else(|| name.to_string());
+        config.solana.rpc_url = profile.rpc_url.clone();
+        config.solana.ws_url = profile.ws_url.clone();
+        if let Some(commitment) = &profile.commitment {
+            config.solana.commitment = commitment.clone();
+            applied.push("solana.commitment");
+        }
+        if let Some(tokens) = &profile.tokens {
+            config.tokens = tokens.clone();
+            applied.push("tokens");
+        }
+        if let Some(enabled) = profile.jupiter {
+            config.jupiter.enabled = enabled;
+            applied.push("jupiter.enabled");
         }
+        if let Some(keypair_path) = &profile.keypair_path {
+            config.wallet.keypair_path = keypair_path.clone();
+            applied.push("wallet.keypair_path");
+        }
+        for path in applied {
+            config
+                .sources
+                .retain(|key, _| key != path && !key.starts_with(&format!("{}.", path)));
+            config
+                .sources
+                .insert(path.to_string(), format!("profile {}", name));
+        }
+        let config = config.reapply_overrides()?;
+
+        app_log!(
+            info,
+            "Using profile {} ({}, {})",
+            name,
+            config.solana.network,
+            config.solana.rpc_url
+        );
+        Ok(config)
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        self.solana.network.starts_with("mainnet")
+    }
 
-        if config.internal.secret == "change-me-in-production" || config.internal.secret.len() < 16 {
-            app_log!(warn, "cli-solanize internal secret is weak or default — set CLI_INTERNAL_SECRET");
+    /// Resolve the configuration. Each layer overrides the previous one:
+    /// 1. built-in defaults
+    /// 2. the YAML file at `path`, if present
+    /// 3. environment: `SOLANIZE_<SECTION>__<KEY>` (double underscore between
+    ///    levels, e.g. `SOLANIZE_SOLANA__RPC_URL`); `..._FILE` reads the value
+    ///    from a file, and the older `CLI_*_SECRET` / `ROCKET_PORT` still apply
+    /// 4. `flags`, as (dotted path, value) pairs from the command line
+    pub fn load(path: &str, flags: &[(String, String)]) -> Result<Self> {
+        let mut tree = serde_json::to_value(Config::default())?;
+        let mut sources = BTreeMap::new();
+
+        if std::path::Path::new(path).exists() {
+            let content = fs::read_to_string(path)?;
+            let file: serde_json::Value = serde_yaml::from_str(&content)?;
+            let source = format!("file {}", path);
+            let mut leaves = Vec::new();
+            flatten("", &file, &mut leaves);
+            for (key, _) in leaves {
+                sources.insert(key, source.clone());
+            }
+            merge(&mut tree, file);
+        }
+
+        let mut overrides = env_overrides()?;
+        overrides.extend(
+            flags
+                .iter()
+                .map(|(key, raw)| (key.clone(), raw.clone(), "flag".to_string())),
+        );
+
+        for (key, raw, source) in &overrides {
+            set_path(&mut tree, key, raw)?;
+            sources.insert(key.clone(), source.clone());
+        }
+
+        let mut config = from_tree(tree)?;
+
+        // `set_path` creates whatever it is given; a key no setting reads is
+        // dropped when deserializing, so look for it in what came out
+        let effective = serde_json::to_value(&config)?;
+        if let Some((key, _, source)) = overrides
+            .iter()
+            .find(|(key, _, _)| lookup(&effective, key).is_none())
+        {
+            return Err(SolanaClientError::ConfigError {
+                message: format!("Cannot set {} (from {}): no such setting", key, source),
+            }
+            .into());
         }
 
-        app_log!(info, "Config loaded successfully");
+        config.sources = sources;
+        config.overrides = overrides;
         Ok(config)
     }
+
+    // Set the environment and flag values again, e.g. over a profile. The
+    // profile choice itself is not reapplied, whichever layer it came from.
+    fn reapply_overrides(self) -> Result<Config> {
+        let mut tree = serde_json::to_value(&self)?;
+        let overrides: Vec<_> = self
+            .overrides
+            .iter()
+            .filter(|(key, _, _)| key != "profile")
+            .collect();
+        for (key, raw, _) in &overrides {
+            set_path(&mut tree, key, raw)?;
+        }
+
+        let mut config = from_tree(tree)?;
+        config.sources = self.sources.clone();
+        for (key, _, source) in overrides {
+            config.sources.insert(key.clone(), source.clone());
+        }
+        config.overrides = self.overrides;
+        Ok(config)
+    }
+
+    /// Every effective value as `(path, value, source)`, secrets redacted
+    pub fn resolved(&self) -> Result<Vec<(String, serde_json::Value, String)>> {
+        let mut tree = serde_json::to_value(self)?;
+        redact(&mut tree);
+
+        let mut leaves = Vec::new();
+        flatten("", &tree, &mut leaves);
+        Ok(leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let source = self.source_of(&key);
+                (key, value, source)
+            })
+            .collect())
+    }
+
+    /// The effective config as YAML, secrets redacted
+    pub fn to_redacted_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(&self.redacted()?)?)
+    }
+
+    /// The effective config as a JSON tree, secrets redacted
+    pub fn redacted(&self) -> Result<serde_json::Value> {
+        let mut tree = serde_json::to_value(self)?;
+        redact(&mut tree);
+        Ok(tree)
+    }
+
+    // The most specific layer that set `key` or one of its parents
+    fn source_of(&self, key: &str) -> String {
+        let mut prefix = key;
+        loop {
+            if let Some(source) = self.sources.get(prefix) {
+                return source.clone();
+            }
+            match prefix.rfind('.') {
+                Some(index) => prefix = &prefix[..index],
+                None => return "default".to_string(),
+            }
+        }
+    }
+}
+
+/// The value at a dotted path, list items by index
+pub fn lookup<'a>(tree: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(tree, |node, segment| match node {
+        serde_json::Value::Object(map) => map.get(segment),
+        serde_json::Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+fn from_tree(tree: serde_json::Value) -> Result<Config> {
+    serde_json::from_value(tree).map_err(|e| {
+        SolanaClientError::ConfigError {
+            message: format!("Invalid configuration: {}", e),
+        }
+        .into()
+    })
+}
+
+// Older names kept so existing deployments keep working
+const LEGACY_ENV: &[(&str, &str)] = &[
+    ("CLI_INTERNAL_SECRET", "internal.secret"),
+    ("CLI_WEBHOOK_SECRET", "webhooks.signing_secret"),
+    ("CLI_AUTH_TOKEN_SECRET", "auth.token_secret"),
+    ("CLI_REMOTE_KEY_SECRET", "remote.key_secret"),
+    ("ROCKET_PORT", "server.port"),
+];
+
+/// Environment layer as (dotted path, raw value, source), legacy names first
+/// so the `SOLANIZE_*` form wins when both are set
+fn env_overrides() -> Result<Vec<(String, String, String)>> {
+    let mut overrides = Vec::new();
+
+    for (var, key) in LEGACY_ENV {
+        if let Ok(value) = std::env::var(var)
+            && !value.is_empty()
+        {
+            overrides.push((key.to_string(), value, format!("env {}", var)));
+        }
+    }
+
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(var, _)| var.starts_with("SOLANIZE_"))
+        .collect();
+    vars.sort();
+
+    for (var, value) in vars {
+        let name = &var["SOLANIZE_".len()..];
+        let (name, value) = match name.strip_suffix("_FILE") {
+            Some(name) => {
+                let content =
+                    fs::read_to_string(&value).map_err(|e| SolanaClientError::ConfigError {
+                        message: format!("{}: cannot read {}: {}", var, value, e),
+                    })?;
+                (name, content.trim_end_matches(['\n', '\r']).to_string())
+            }
+            None => (name, value),
+        };
+        let key = name.to_lowercase().replace("__", ".");
+        overrides.push((key, value, format!("env {}", var)));
+    }
+
+    Ok(overrides)
+}
+
+// Deep-merge `overlay` into `base`; anything but a mapping replaces outright
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Set a dotted path from a string. Values are read as YAML so numbers, bools
+/// and lists work, except where a string is already expected. An index past
+/// the end of a list adds entries up to it, so new items can be built field by
+/// field in any order.
+fn set_path(tree: &mut serde_json::Value, key: &str, raw: &str) -> Result<()> {
+    let invalid = || SolanaClientError::ConfigError {
+        message: format!("Cannot set {}: no such setting", key),
+    };
+
+    let mut node = tree;
+    for segment in key.split('.') {
+        if node.is_null() {
+            *node = serde_json::Value::Object(Default::default());
+        }
+        node = match node {
+            serde_json::Value::Object(map) => map
+                .entry(segment.to_string())
+                .or_insert(serde_json::Value::Null),
+            serde_json::Value::Array(items) => {
+                let index = segment.parse::<usize>().map_err(|_| invalid())?;
+                if index >= items.len() {
+                    items.resize(index + 1, serde_json::Value::Null);
+                }
+                &mut items[index]
+            }
+            _ => return Err(invalid().into()),
+        };
+    }
+
+    let is_secret = key.rsplit('.').next().is_some_and(is_secret_key);
+    *node = match node {
+        serde_json::Value::String(_) => serde_json::Value::String(raw.to_string()),
+        _ if is_secret => serde_json::Value::String(raw.to_string()),
+        _ => {
+            serde_yaml::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+        }
+    };
+    Ok(())
+}
+
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut Vec<(String, serde_json::Value)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                flatten(&join(key), child, out);
+            }
+        }
+        serde_json::Value::Array(items) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                flatten(&join(&index.to_string()), child, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    key == "secret" || key.ends_with("_secret")
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if is_secret_key(key) && !child.is_null() {
+                    *child = serde_json::Value::String("<redacted>".to_string());
+                } else {
+                    redact(child);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
 }
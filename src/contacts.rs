@@ -0,0 +1,198 @@
+//! Address book: names for addresses, with notes and tags. `@name` stands in
+//! for the address in any address argument of the CLI and the menu prompts.
+
+use crate::{audit, config::Config, error::SolanaClientError};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::fs;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Contact {
+    pub name: String, // Lowercase, used as `@name`
+    pub address: String,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub added_at: i64,
+}
+
+/// Contacts persisted as JSON in `contacts.path`
+pub struct AddressBook {
+    path: String,
+    contacts: Vec<Contact>, // Sorted by name
+}
+
+impl AddressBook {
+    pub fn load(config: &Config) -> Result<Self> {
+        let path = config.contacts.path.clone();
+        let contacts = if std::path::Path::new(&path).exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, contacts })
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Save a new contact. Names are case-insensitive and unique.
+    pub fn add(
+        &mut self,
+        name: &str,
+        address: &str,
+        note: Option<String>,
+        tags: &[String],
+    ) -> Result<Contact> {
+        let name = normalize(name);
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid(format!(
+                "Contact names may only use letters, digits, '-' and '_', got '{}'",
+                name
+            )));
+        }
+        if self.contact(&name).is_some() {
+            return Err(invalid(format!(
+                "@{} already exists, remove it first",
+                name
+            )));
+        }
+        let address = Pubkey::from_str(address)
+            .map_err(|_| SolanaClientError::InvalidAddress {
+                address: address.to_string(),
+            })?
+            .to_string();
+
+        let mut tags: Vec<String> = tags
+            .iter()
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        let contact = Contact {
+            name,
+            address,
+            note,
+            tags,
+            added_at: chrono::Utc::now().timestamp(),
+        };
+        self.contacts.push(contact.clone());
+        self.contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        self.save()?;
+        Ok(contact)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Contact> {
+        let name = normalize(name);
+        let index = self
+            .contacts
+            .iter()
+            .position(|c| c.name == name)
+            .ok_or_else(|| unknown(&name))?;
+
+        let contact = self.contacts.remove(index);
+        self.save()?;
+        Ok(contact)
+    }
+
+    /// By name, with or without the `@`
+    pub fn contact(&self, name: &str) -> Option<&Contact> {
+        let name = normalize(name);
+        self.contacts.iter().find(|c| c.name == name)
+    }
+
+    /// The contact saved for `address`, if any
+    pub fn find(&self, address: &str) -> Option<&Contact> {
+        self.contacts.iter().find(|c| c.address == address)
+    }
+
+    /// Names starting with `prefix`, for completing `@` in prompts
+    pub fn names_starting_with(&self, prefix: &str) -> impl Iterator<Item = &str> {
+        let prefix = normalize(prefix);
+        self.contacts
+            .iter()
+            .map(|c| c.name.as_str())
+            .filter(move |name| name.starts_with(&prefix))
+    }
+
+    /// `@name` becomes the contact's address; anything else is returned as is
+    pub fn resolve(&self, value: &str) -> Result<String> {
+        match value.strip_prefix('@') {
+            Some(name) => self
+                .contact(name)
+                .map(|c| c.address.clone())
+                .ok_or_else(|| unknown(name)),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    pub fn resolve_in_place(&self, value: &mut String) -> Result<()> {
+        *value = self.resolve(value)?;
+        Ok(())
+    }
+
+    pub fn resolve_option(&self, value: &mut Option<String>) -> Result<()> {
+        match value {
+            Some(value) => self.resolve_in_place(value),
+            None => Ok(()),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.contacts)?)?;
+        Ok(())
+    }
+}
+
+/// Neither a contact nor the recipient of anything in the audit log, so a
+/// transfer to it deserves a second look
+pub fn is_new_recipient(config: &Config, address: &str) -> Result<bool> {
+    if AddressBook::load(config)?.find(address).is_some() {
+        return Ok(false);
+    }
+
+    let sent_before = audit::read_entries(&config.audit.path)?
+        .iter()
+        .filter(|e| matches!(e.status.as_str(), "prepared" | "signed" | "confirmed"))
+        .any(|e| recipients(&e.intent).any(|to| to == address));
+    Ok(!sent_before)
+}
+
+// `to` or `recipient` of an intent, and the `transfers` of a submitted transaction
+fn recipients(intent: &Value) -> impl Iterator<Item = &str> {
+    let transfers = intent["transfers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|transfer| &transfer["to"]);
+
+    [&intent["to"], &intent["recipient"]]
+        .into_iter()
+        .chain(transfers)
+        .filter_map(Value::as_str)
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().trim_start_matches('@').to_lowercase()
+}
+
+fn unknown(name: &str) -> anyhow::Error {
+    invalid(format!("No contact named @{}", name))
+}
+
+fn invalid(message: impl Into<String>) -> anyhow::Error {
+    SolanaClientError::InvalidRequest {
+        message: message.into(),
+    }
+    .into()
+}
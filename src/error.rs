@@ -1,3 +1,4 @@
+use crate::api::ErrorCode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -24,4 +25,55 @@ pub enum SolanaClientError {
 
     #[error("Config error: {message}")]
     ConfigError { message: String },
+
+    #[error("Authentication failed: {reason}")]
+    AuthFailed { reason: String },
+
+    #[error("Invalid request: {message}")]
+    InvalidRequest { message: String },
+
+    #[error("Rate limited: {message}")]
+    RateLimited { message: String },
+
+    #[error("Policy violation: {}", violations.join("; "))]
+    PolicyViolation { violations: Vec<String> },
+
+    #[error("{message}")]
+    Remote { code: ErrorCode, message: String }, // Error returned by a REST server
+}
+
+impl SolanaClientError {
+    /// Stable machine-readable code for the REST API
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WalletNotFound { .. } => ErrorCode::WalletNotFound,
+            Self::InvalidWalletFormat => ErrorCode::InvalidWalletFormat,
+            Self::NetworkError { .. } => ErrorCode::NetworkError,
+            Self::TransactionFailed { .. } => ErrorCode::TransactionFailed,
+            Self::InsufficientBalance { .. } => ErrorCode::InsufficientBalance,
+            Self::InvalidAddress { .. } => ErrorCode::InvalidAddress,
+            Self::ConfigError { .. } => ErrorCode::ConfigError,
+            Self::AuthFailed { .. } => ErrorCode::AuthFailed,
+            Self::InvalidRequest { .. } => ErrorCode::InvalidRequest,
+            Self::PolicyViolation { .. } => ErrorCode::PolicyViolation,
+            Self::RateLimited { .. } => ErrorCode::RateLimited,
+            Self::Remote { code, .. } => *code,
+        }
+    }
+
+    /// Structured fields a client can use without parsing the message
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::WalletNotFound { path } => Some(serde_json::json!({ "path": path })),
+            Self::InsufficientBalance { current, required } => {
+                Some(serde_json::json!({ "current": current, "required": required }))
+            }
+            Self::InvalidAddress { address } => Some(serde_json::json!({ "address": address })),
+            Self::TransactionFailed { reason } => Some(serde_json::json!({ "reason": reason })),
+            Self::PolicyViolation { violations } => {
+                Some(serde_json::json!({ "violations": violations }))
+            }
+            _ => None,
+        }
+    }
 }
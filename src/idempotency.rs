@@ -0,0 +1,113 @@
+use crate::app_log;
+use crate::config::Config;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Mutex;
+
+/// A completed request, replayed verbatim when its key is reused
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredResponse {
+    pub fingerprint: String, // Hex SHA-256 of route and body
+    pub status: u16,
+    pub body: serde_json::Value,
+    pub created_at: i64,
+}
+
+pub enum Lookup {
+    New,                    // Caller should run the request, then `complete` or `abandon`
+    Replay(StoredResponse), // Same key and body: return the original response
+    Mismatch,               // Same key, different body
+    InProgress,             // Same key is still being processed
+}
+
+/// Idempotency keys for submit/prepare endpoints, persisted so a retry after a
+/// restart still replays instead of re-executing. Keys belong to the caller
+/// that sent them: another caller reusing one neither sees nor blocks its response.
+pub struct IdempotencyStore {
+    config: Config,
+    responses: Mutex<HashMap<String, StoredResponse>>,
+    in_flight: Mutex<HashSet<String>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(config: Config) -> Result<Self> {
+        let mut responses = load_responses(&config.idempotency.store_path)?;
+        prune(&mut responses, config.idempotency.window_secs);
+
+        app_log!(
+            info,
+            "Loaded {} idempotency records from {}",
+            responses.len(),
+            config.idempotency.store_path
+        );
+
+        Ok(Self {
+            config,
+            responses: Mutex::new(responses),
+            in_flight: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Claim `actor`'s `key` for a request, or report what happened to it before
+    pub fn begin(&self, actor: &str, key: &str, fingerprint: &str) -> Lookup {
+        let key = scoped(actor, key);
+        let mut responses = self.responses.lock().unwrap();
+        prune(&mut responses, self.config.idempotency.window_secs);
+
+        if let Some(stored) = responses.get(&key) {
+            return if stored.fingerprint == fingerprint {
+                Lookup::Replay(stored.clone())
+            } else {
+                Lookup::Mismatch
+            };
+        }
+
+        if !self.in_flight.lock().unwrap().insert(key) {
+            return Lookup::InProgress;
+        }
+
+        Lookup::New
+    }
+
+    pub fn complete(&self, actor: &str, key: &str, response: StoredResponse) {
+        let key = scoped(actor, key);
+        let mut responses = self.responses.lock().unwrap();
+        self.in_flight.lock().unwrap().remove(&key);
+        responses.insert(key, response);
+
+        if let Err(e) = save_responses(&self.config.idempotency.store_path, &responses) {
+            app_log!(error, "Failed to persist idempotency records: {}", e);
+        }
+    }
+
+    /// Release a key without recording a response, so the request can be retried
+    pub fn abandon(&self, actor: &str, key: &str) {
+        self.in_flight.lock().unwrap().remove(&scoped(actor, key));
+    }
+}
+
+// Header values cannot contain a newline, so no two callers' keys collide
+fn scoped(actor: &str, key: &str) -> String {
+    format!("{}\n{}", actor, key)
+}
+
+fn prune(responses: &mut HashMap<String, StoredResponse>, window_secs: i64) {
+    let cutoff = chrono::Utc::now().timestamp() - window_secs;
+    responses.retain(|_, r| r.created_at > cutoff);
+}
+
+fn load_responses(path: &str) -> Result<HashMap<String, StoredResponse>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_responses(path: &str, responses: &HashMap<String, StoredResponse>) -> Result<()> {
+    fs::write(path, serde_json::to_string(responses)?)?;
+    Ok(())
+}
@@ -1,10 +1,14 @@
 use crate::app_log;
-use crate::{config::Config, error::SolanaClientError, wallet::load_keypair};
+use crate::audit::Audit;
+use crate::policy::{Intent, Spend};
+use crate::{config::Config, error::SolanaClientError, metrics, rpc, wallet::load_keypair};
 use anyhow::Result;
-use base64;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use solana_sdk::signature::Signer;
+use serde_json::json;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::signature::{Signature, Signer};
 use solana_sdk::transaction::VersionedTransaction;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, transaction::Transaction};
 use std::str::FromStr;
@@ -108,11 +112,13 @@ pub struct SwapResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct PriceResponseV3 {
     // V3 returns direct token mapping
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct PriceDataV3 {
     #[serde(rename = "usdPrice")]
     pub usd_price: f64,
@@ -124,121 +130,177 @@ pub struct PriceDataV3 {
 }
 
 pub async fn get_token_mint(config: &Config, symbol: &str) -> Result<String> {
-    let symbol_upper = symbol.to_uppercase();
-
-    match symbol_upper.as_str() {
-        "SOL" => Ok(config.tokens.sol.clone()),
-        "USDC" => Ok(config.tokens.usdc.clone()),
-        _ => {
-            // Try to parse as direct mint address
-            if let Ok(_) = Pubkey::from_str(symbol) {
-                Ok(symbol.to_string())
-            } else {
-                Err(SolanaClientError::InvalidAddress {
-                    address: format!("Unknown token: {}", symbol),
-                }
-                .into())
-            }
+    if let Some(mint) = config.tokens.mint_for(symbol) {
+        return Ok(mint.clone());
+    }
+
+    // Try to parse as direct mint address
+    if Pubkey::from_str(symbol).is_ok() {
+        Ok(symbol.to_string())
+    } else {
+        Err(SolanaClientError::InvalidAddress {
+            address: format!("Unknown token: {}", symbol),
         }
+        .into())
     }
 }
 
 pub async fn prepare_swap_transaction(
     config: &Config,
+    actor: &str,
     from_symbol: &str,
     to_symbol: &str,
     amount: f64,
     payer_pubkey: &Pubkey,
-) -> Result<(String, crate::web::QuoteInfo, Vec<String>, String)> {
-    // Get token mints
-    let input_mint = get_token_mint(config, from_symbol).await?;
-    let output_mint = get_token_mint(config, to_symbol).await?;
-
-    // Convert amount to smallest unit
-    let decimals = if from_symbol.to_uppercase() == "SOL" {
-        9
-    } else {
-        6
-    };
-    let amount_units = (amount * 10_f64.powi(decimals)) as u64;
-
-    app_log!(
-        info,
-        "Preparing swap: {} {} for {} (payer: {})",
-        amount,
-        from_symbol.to_uppercase(),
-        to_symbol.to_uppercase(),
-        payer_pubkey
+) -> Result<(String, crate::api::QuoteInfo, Vec<String>, String)> {
+    let mut audit = Audit::new(
+        actor,
+        "swap",
+        json!({ "from_token": from_symbol, "to_token": to_symbol, "amount": amount, "payer": payer_pubkey.to_string() }),
     );
+    let result: Result<(String, crate::api::QuoteInfo, Vec<String>, String)> = async {
+        // Get token mints
+        let input_mint = get_token_mint(config, from_symbol).await?;
+        let output_mint = get_token_mint(config, to_symbol).await?;
 
-    // Get quote
-    let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
-
-    let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
-        / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+        // Convert amount to smallest unit
+        let decimals = if from_symbol.to_uppercase() == "SOL" {
             9
         } else {
             6
-        });
-    let price_impact = quote.price_impact_pct.parse::<f64>()?;
-
-    app_log!(
-        info,
-        "Quote: {} {} -> {:.6} {}, price impact: {:.4}%",
-        amount,
-        from_symbol.to_uppercase(),
-        out_amount_f64,
-        to_symbol.to_uppercase(),
-        price_impact
-    );
+        };
+        let amount_units = to_units(amount, decimals)?;
+
+        app_log!(
+            info,
+            "Preparing swap: {} {} for {} (payer: {})",
+            amount,
+            from_symbol.to_uppercase(),
+            to_symbol.to_uppercase(),
+            payer_pubkey
+        );
+
+        // Get quote
+        let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
+
+        let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
+            / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+                9
+            } else {
+                6
+            });
+        let price_impact = quote.price_impact_pct.parse::<f64>()?;
+        let slippage_bps = quote.slippage_bps;
+
+        app_log!(
+            info,
+            "Quote: {} {} -> {:.6} {}, price impact: {:.4}%",
+            amount,
+            from_symbol.to_uppercase(),
+            out_amount_f64,
+            to_symbol.to_uppercase(),
+            price_impact
+        );
+
+        // Get swap transaction (unsigned)
+        let swap_response = get_swap_transaction(config, quote, payer_pubkey).await?;
+
+        // Decode versioned transaction to extract info
+        let tx_bytes = BASE64.decode(&swap_response.swap_transaction)?;
+        let versioned_tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
+
+        audit.check_policy(
+            config,
+            &Intent::swap(
+                payer_pubkey,
+                Spend {
+                    symbol: from_symbol.to_uppercase(),
+                    mint: input_mint,
+                    amount,
+                },
+                price_impact,
+                slippage_bps,
+                &versioned_tx.message,
+            ),
+        )?;
+
+        // Extract required signers from the transaction
+        let required_signers = match &versioned_tx.message {
+            solana_sdk::message::VersionedMessage::Legacy(legacy_msg) => legacy_msg
+                .account_keys
+                .iter()
+                .take(legacy_msg.header.num_required_signatures as usize)
+                .map(|key| key.to_string())
+                .collect(),
+            solana_sdk::message::VersionedMessage::V0(v0_msg) => v0_msg
+                .account_keys
+                .iter()
+                .take(v0_msg.header.num_required_signatures as usize)
+                .map(|key| key.to_string())
+                .collect(),
+        };
+
+        // Get recent blockhash
+        let client = rpc::client(config);
+        let recent_blockhash = client.get_latest_blockhash()?.to_string();
+
+        let quote_info = crate::api::QuoteInfo {
+            expected_output: out_amount_f64,
+            price_impact,
+            route_steps: 1, // Simplified for now
+        };
+
+        Ok((
+            swap_response.swap_transaction, // Return unsigned transaction as-is
+            quote_info,
+            required_signers,
+            recent_blockhash,
+        ))
+    }
+    .await;
 
-    // Get swap transaction (unsigned)
-    let swap_response = get_swap_transaction(config, quote, payer_pubkey).await?;
-
-    // Decode versioned transaction to extract info
-    let tx_bytes = base64::decode(&swap_response.swap_transaction)?;
-    let versioned_tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
-
-    // Extract required signers from the transaction
-    let required_signers = match &versioned_tx.message {
-        solana_sdk::message::VersionedMessage::Legacy(legacy_msg) => legacy_msg
-            .account_keys
-            .iter()
-            .take(legacy_msg.header.num_required_signatures as usize)
-            .map(|key| key.to_string())
-            .collect(),
-        solana_sdk::message::VersionedMessage::V0(v0_msg) => v0_msg
-            .account_keys
-            .iter()
-            .take(v0_msg.header.num_required_signatures as usize)
-            .map(|key| key.to_string())
-            .collect(),
-    };
+    audit.finish(config, result, "prepared")
+}
 
-    // Get recent blockhash
-    let client = solana_client::rpc_client::RpcClient::new(&config.solana.rpc_url);
-    let recent_blockhash = client.get_latest_blockhash()?.to_string();
+// Profiles for clusters Jupiter does not serve switch it off
+fn ensure_available(config: &Config) -> Result<()> {
+    if config.jupiter.enabled {
+        Ok(())
+    } else {
+        Err(SolanaClientError::ConfigError {
+            message: format!("Jupiter is not available on {}", config.solana.network),
+        }
+        .into())
+    }
+}
 
-    let quote_info = crate::web::QuoteInfo {
-        expected_output: out_amount_f64,
-        price_impact,
-        route_steps: 1, // Simplified for now
-    };
+pub async fn get_quote(
+    config: &Config,
+    input_mint: &str,
+    output_mint: &str,
+    amount: u64,
+) -> Result<QuoteResponse> {
+    quote(config, input_mint, output_mint, amount, "ExactIn").await
+}
 
-    Ok((
-        swap_response.swap_transaction, // Return unsigned transaction as-is
-        quote_info,
-        required_signers,
-        recent_blockhash,
-    ))
+/// Quote for receiving exactly `amount` of the output; the input is what varies
+pub async fn get_exact_out_quote(
+    config: &Config,
+    input_mint: &str,
+    output_mint: &str,
+    amount: u64,
+) -> Result<QuoteResponse> {
+    quote(config, input_mint, output_mint, amount, "ExactOut").await
 }
 
-pub async fn get_quote(
+async fn quote(
     config: &Config,
     input_mint: &str,
     output_mint: &str,
     amount: u64,
+    swap_mode: &str,
 ) -> Result<QuoteResponse> {
+    ensure_available(config)?;
     let client = Client::new();
     let url = format!("{}/quote", config.jupiter.api_url);
 
@@ -249,33 +311,48 @@ pub async fn get_quote(
         output_mint
     );
 
-    let response = client
-        .get(&url)
-        .query(&[
-            ("inputMint", input_mint),
-            ("outputMint", output_mint),
-            ("amount", &amount.to_string()),
-            ("slippageBps", &config.jupiter.slippage_bps.to_string()),
-        ])
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(SolanaClientError::NetworkError {
-            source: error_text.into(),
+    metrics::time_jupiter("quote", async {
+        let response = client
+            .get(&url)
+            .query(&[
+                ("inputMint", input_mint),
+                ("outputMint", output_mint),
+                ("amount", &amount.to_string()),
+                ("slippageBps", &config.jupiter.slippage_bps.to_string()),
+                ("swapMode", swap_mode),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(SolanaClientError::NetworkError {
+                source: error_text.into(),
+            }
+            .into());
         }
-        .into());
-    }
 
-    let quote: QuoteResponse = response.json().await?;
-    Ok(quote)
+        let quote: QuoteResponse = response.json().await?;
+        Ok(quote)
+    })
+    .await
 }
 
 pub async fn get_swap_transaction(
     config: &Config,
     quote: QuoteResponse,
     user_pubkey: &Pubkey,
+) -> Result<SwapResponse> {
+    get_swap_transaction_to(config, quote, user_pubkey, None).await
+}
+
+/// Like `get_swap_transaction`, with the output delivered to `destination`,
+/// a token account for the output mint that need not belong to the user
+pub async fn get_swap_transaction_to(
+    config: &Config,
+    quote: QuoteResponse,
+    user_pubkey: &Pubkey,
+    destination: Option<&Pubkey>,
 ) -> Result<SwapResponse> {
     let client = Client::new();
     let url = format!("{}/swap", config.jupiter.api_url);
@@ -291,22 +368,25 @@ pub async fn get_swap_transaction(
         prioritization_fee_lamports: Some(1000),
         as_legacy_transaction: false,
         use_token_ledger: false,
-        destination_token_account: None,
+        destination_token_account: destination.map(|d| d.to_string()),
     };
 
     app_log!(info, "Getting swap transaction from Jupiter");
 
-    let response = client.post(&url).json(&request).send().await?;
+    let swap_response: SwapResponse = metrics::time_jupiter("swap", async {
+        let response = client.post(&url).json(&request).send().await?;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(SolanaClientError::NetworkError {
-            source: error_text.into(),
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(SolanaClientError::NetworkError {
+                source: error_text.into(),
+            }
+            .into());
         }
-        .into());
-    }
 
-    let swap_response: SwapResponse = response.json().await?;
+        Ok(response.json().await?)
+    })
+    .await?;
 
     if let Some(error) = &swap_response.simulation_error {
         return Err(SolanaClientError::TransactionFailed {
@@ -318,176 +398,296 @@ pub async fn get_swap_transaction(
     Ok(swap_response)
 }
 
+/// Outcome of a confirmed swap signed with the local wallet
+#[derive(Debug, Serialize)]
+pub struct SwapResult {
+    pub signature: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: f64,
+    pub quote: crate::api::QuoteInfo,
+}
+
 pub async fn swap_tokens(
     config: &Config,
+    actor: &str,
     from_symbol: &str,
     to_symbol: &str,
     amount: f64,
-) -> Result<()> {
-    let keypair = load_keypair(config).await?;
-
-    // Get token mints
-    let input_mint = get_token_mint(config, from_symbol).await?;
-    let output_mint = get_token_mint(config, to_symbol).await?;
-
-    // Convert amount to smallest unit
-    let decimals = if from_symbol.to_uppercase() == "SOL" {
-        9
-    } else {
-        6
-    }; // USDC has 6 decimals
-    let amount_units = (amount * 10_f64.powi(decimals)) as u64;
-
-    app_log!(
-        info,
-        "🔄 Swapping {} {} for {}...",
-        amount,
-        from_symbol.to_uppercase(),
-        to_symbol.to_uppercase()
+) -> Result<SwapResult> {
+    let mut audit = Audit::new(
+        actor,
+        "swap",
+        json!({ "from_token": from_symbol, "to_token": to_symbol, "amount": amount }),
     );
+    let result: Result<SwapResult> = async {
+        let keypair = load_keypair(config).await?;
 
-    // Get quote
-    let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
+        // Get token mints
+        let input_mint = get_token_mint(config, from_symbol).await?;
+        let output_mint = get_token_mint(config, to_symbol).await?;
 
-    let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
-        / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+        // Convert amount to smallest unit
+        let decimals = if from_symbol.to_uppercase() == "SOL" {
             9
         } else {
             6
-        });
-    let price_impact = quote.price_impact_pct.parse::<f64>()?;
-
-    app_log!(info, "📊 Quote received:");
-    app_log!(
-        info,
-        "   Expected output: {:.6} {}",
-        out_amount_f64,
-        to_symbol.to_uppercase()
-    );
-    app_log!(info, "   Price impact: {:.4}%", price_impact);
-    app_log!(info, "   Route: {} steps", quote.route_plan.len());
-
-    // Get swap transaction
-    let swap_response = get_swap_transaction(config, quote, &keypair.pubkey()).await?;
-
-    // Decode and sign transaction
-    let tx_bytes = bs58::decode(&swap_response.swap_transaction).into_vec()?;
-    let mut transaction: Transaction = bincode::deserialize(&tx_bytes)?;
+        }; // USDC has 6 decimals
+        let amount_units = to_units(amount, decimals)?;
+
+        app_log!(
+            info,
+            "🔄 Swapping {} {} for {}...",
+            amount,
+            from_symbol.to_uppercase(),
+            to_symbol.to_uppercase()
+        );
+
+        // Get quote
+        let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
+
+        let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
+            / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+                9
+            } else {
+                6
+            });
+        let price_impact = quote.price_impact_pct.parse::<f64>()?;
+        let slippage_bps = quote.slippage_bps;
+
+        let quote_info = crate::api::QuoteInfo {
+            expected_output: out_amount_f64,
+            price_impact,
+            route_steps: quote.route_plan.len(),
+        };
+        app_log!(
+            info,
+            "Quote: {:.6} {}, price impact {:.4}%, {} route steps",
+            out_amount_f64,
+            to_symbol.to_uppercase(),
+            price_impact,
+            quote_info.route_steps
+        );
+
+        // Get swap transaction
+        let swap_response = get_swap_transaction(config, quote, &keypair.pubkey()).await?;
+
+        // Decode and sign transaction
+        let tx_bytes = bs58::decode(&swap_response.swap_transaction).into_vec()?;
+        let mut transaction: Transaction = bincode::deserialize(&tx_bytes)?;
+
+        audit.check_policy(
+            config,
+            &Intent::swap(
+                &keypair.pubkey(),
+                Spend {
+                    symbol: from_symbol.to_uppercase(),
+                    mint: input_mint,
+                    amount,
+                },
+                price_impact,
+                slippage_bps,
+                &VersionedMessage::Legacy(transaction.message.clone()),
+            ),
+        )?;
+
+        // Sign transaction
+        transaction.sign(&[&keypair], transaction.message.recent_blockhash);
+        audit.signature = Some(transaction.signatures[0].to_string());
+
+        // Send transaction
+        audit.count_spend(config)?;
+        let client = rpc::client(config);
+
+        match client.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => {
+                metrics::transaction_confirmed();
+                app_log!(info, "Swap confirmed: {}", signature);
+                Ok(SwapResult {
+                    signature: signature.to_string(),
+                    from_token: from_symbol.to_uppercase(),
+                    to_token: to_symbol.to_uppercase(),
+                    amount,
+                    quote: quote_info,
+                })
+            }
+            Err(e) => {
+                app_log!(error, "Swap failed: {}", e);
+                Err(SolanaClientError::TransactionFailed {
+                    reason: format!("Swap failed: {}", e),
+                }
+                .into())
+            }
+        }
+    }
+    .await;
 
-    // Sign transaction
-    transaction.sign(&[&keypair], transaction.message.recent_blockhash);
+    audit.finish(config, result, "confirmed")
+}
 
-    // Send transaction
-    let client = solana_client::rpc_client::RpcClient::new(&config.solana.rpc_url);
+/// Sign and send the swap for an already fetched `quote`, checking the policy
+/// against `spend` first. Used where the quote was compared with other routes.
+pub async fn execute_quote(
+    config: &Config,
+    audit: &mut Audit,
+    keypair: &Keypair,
+    quote: QuoteResponse,
+    spend: Spend,
+) -> Result<Signature> {
+    let price_impact = quote.price_impact_pct.parse::<f64>()?;
+    let slippage_bps = quote.slippage_bps;
 
+    let swap_response = get_swap_transaction(config, quote, &keypair.pubkey()).await?;
+    let tx_bytes = BASE64.decode(&swap_response.swap_transaction)?;
+    let unsigned: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
+
+    audit.check_policy(
+        config,
+        &Intent::swap(
+            &keypair.pubkey(),
+            spend,
+            price_impact,
+            slippage_bps,
+            &unsigned.message,
+        ),
+    )?;
+
+    let transaction = VersionedTransaction::try_new(unsigned.message, &[keypair])?;
+    audit.signature = Some(transaction.signatures[0].to_string());
+    audit.count_spend(config)?;
+
+    let client = rpc::client(config);
     match client.send_and_confirm_transaction(&transaction) {
         Ok(signature) => {
-            app_log!(info, "✅ Swap completed successfully!");
-            app_log!(info, "🔗 Signature: {}", signature);
-            app_log!(
-                info,
-                "💰 Swapped {} {} for ~{:.6} {}",
-                amount,
-                from_symbol.to_uppercase(),
-                out_amount_f64,
-                to_symbol.to_uppercase()
-            );
+            metrics::transaction_confirmed();
+            app_log!(info, "Swap confirmed: {}", signature);
+            Ok(signature)
         }
         Err(e) => {
             app_log!(error, "Swap failed: {}", e);
-            return Err(SolanaClientError::TransactionFailed {
+            Err(SolanaClientError::TransactionFailed {
                 reason: format!("Swap failed: {}", e),
             }
-            .into());
+            .into())
         }
     }
-
-    Ok(())
 }
 
+#[allow(dead_code)]
 pub async fn swap_tokens_with_keypair(
     config: &Config,
+    actor: &str,
     from_symbol: &str,
     to_symbol: &str,
     amount: f64,
     keypair: Option<&Keypair>,
 ) -> Result<String> {
-    let kp = match keypair {
-        Some(k) => k,
-        None => &crate::wallet::load_keypair(config).await?,
-    };
-
-    // Get token mints
-    let input_mint = get_token_mint(config, from_symbol).await?;
-    let output_mint = get_token_mint(config, to_symbol).await?;
-
-    // Convert amount to smallest unit
-    let decimals = if from_symbol.to_uppercase() == "SOL" {
-        9
-    } else {
-        6
-    }; // USDC has 6 decimals
-    let amount_units = (amount * 10_f64.powi(decimals)) as u64;
-
-    app_log!(
-        info,
-        "Swapping {} {} for {} with keypair {}",
-        amount,
-        from_symbol.to_uppercase(),
-        to_symbol.to_uppercase(),
-        kp.pubkey()
+    let mut audit = Audit::new(
+        actor,
+        "swap",
+        json!({ "from_token": from_symbol, "to_token": to_symbol, "amount": amount }),
     );
-
-    // Get quote
-    let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
-
-    let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
-        / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+    let result: Result<String> = async {
+        let kp = match keypair {
+            Some(k) => k,
+            None => &crate::wallet::load_keypair(config).await?,
+        };
+
+        // Get token mints
+        let input_mint = get_token_mint(config, from_symbol).await?;
+        let output_mint = get_token_mint(config, to_symbol).await?;
+
+        // Convert amount to smallest unit
+        let decimals = if from_symbol.to_uppercase() == "SOL" {
             9
         } else {
             6
-        });
-    let price_impact = quote.price_impact_pct.parse::<f64>()?;
-
-    app_log!(
-        info,
-        "Quote: {} {} -> {:.6} {}, price impact: {:.4}%",
-        amount,
-        from_symbol.to_uppercase(),
-        out_amount_f64,
-        to_symbol.to_uppercase(),
-        price_impact
-    );
-
-    // Get swap transaction
-    let swap_response = get_swap_transaction(config, quote, &kp.pubkey()).await?;
-
-    // Decode versioned transaction
-    let tx_bytes = base64::decode(&swap_response.swap_transaction)?;
-    let versioned_tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
-
-    // Sign versioned transaction by creating a new one with signers
-    let signed_tx = VersionedTransaction::try_new(versioned_tx.message, &[&kp])?;
-
-    // Send signed transaction
-    let client = solana_client::rpc_client::RpcClient::new(&config.solana.rpc_url);
-
-    match client.send_and_confirm_transaction(&signed_tx) {
-        Ok(signature) => {
-            app_log!(info, "Swap completed: {}", signature);
-            Ok(signature.to_string())
-        }
-        Err(e) => {
-            app_log!(error, "Swap failed: {}", e);
-            Err(crate::error::SolanaClientError::TransactionFailed {
-                reason: format!("Swap failed: {}", e),
+        }; // USDC has 6 decimals
+        let amount_units = to_units(amount, decimals)?;
+
+        app_log!(
+            info,
+            "Swapping {} {} for {} with keypair {}",
+            amount,
+            from_symbol.to_uppercase(),
+            to_symbol.to_uppercase(),
+            kp.pubkey()
+        );
+
+        // Get quote
+        let quote = get_quote(config, &input_mint, &output_mint, amount_units).await?;
+
+        let out_amount_f64 = quote.out_amount.parse::<u64>()? as f64
+            / 10_f64.powi(if to_symbol.to_uppercase() == "SOL" {
+                9
+            } else {
+                6
+            });
+        let price_impact = quote.price_impact_pct.parse::<f64>()?;
+        let slippage_bps = quote.slippage_bps;
+
+        app_log!(
+            info,
+            "Quote: {} {} -> {:.6} {}, price impact: {:.4}%",
+            amount,
+            from_symbol.to_uppercase(),
+            out_amount_f64,
+            to_symbol.to_uppercase(),
+            price_impact
+        );
+
+        // Get swap transaction
+        let swap_response = get_swap_transaction(config, quote, &kp.pubkey()).await?;
+
+        // Decode versioned transaction
+        let tx_bytes = BASE64.decode(&swap_response.swap_transaction)?;
+        let versioned_tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
+
+        audit.check_policy(
+            config,
+            &Intent::swap(
+                &kp.pubkey(),
+                Spend {
+                    symbol: from_symbol.to_uppercase(),
+                    mint: input_mint,
+                    amount,
+                },
+                price_impact,
+                slippage_bps,
+                &versioned_tx.message,
+            ),
+        )?;
+
+        // Sign versioned transaction by creating a new one with signers
+        let signed_tx = VersionedTransaction::try_new(versioned_tx.message, &[&kp])?;
+        audit.signature = Some(signed_tx.signatures[0].to_string());
+
+        // Send signed transaction
+        audit.count_spend(config)?;
+        let client = rpc::client(config);
+
+        match client.send_and_confirm_transaction(&signed_tx) {
+            Ok(signature) => {
+                metrics::transaction_confirmed();
+                app_log!(info, "Swap completed: {}", signature);
+                Ok(signature.to_string())
+            }
+            Err(e) => {
+                app_log!(error, "Swap failed: {}", e);
+                Err(crate::error::SolanaClientError::TransactionFailed {
+                    reason: format!("Swap failed: {}", e),
+                }
+                .into())
             }
-            .into())
         }
     }
+    .await;
+
+    audit.finish(config, result, "confirmed")
 }
 
 pub async fn get_token_price(config: &Config, symbol: &str) -> Result<f64> {
+    ensure_available(config)?;
     let client = Client::new();
 
     // Get token mint
@@ -497,17 +697,21 @@ pub async fn get_token_price(config: &Config, symbol: &str) -> Result<f64> {
 
     app_log!(info, "Getting price for token: {}", symbol);
 
-    let response = client.get(&url).send().await?;
+    // V3 API returns direct mapping: { "mint_address": { "usdPrice": 123.45, ... } }
+    let price_response: std::collections::HashMap<String, PriceDataV3> =
+        metrics::time_jupiter("price", async {
+            let response = client.get(&url).send().await?;
 
-    if !response.status().is_success() {
-        return Err(SolanaClientError::NetworkError {
-            source: "Failed to fetch price".into(),
-        }
-        .into());
-    }
+            if !response.status().is_success() {
+                return Err(SolanaClientError::NetworkError {
+                    source: "Failed to fetch price".into(),
+                }
+                .into());
+            }
 
-    // V3 API returns direct mapping: { "mint_address": { "usdPrice": 123.45, ... } }
-    let price_response: std::collections::HashMap<String, PriceDataV3> = response.json().await?;
+            Ok(response.json().await?)
+        })
+        .await?;
 
     if let Some(price_data) = price_response.get(&mint) {
         Ok(price_data.usd_price)
@@ -518,3 +722,14 @@ pub async fn get_token_price(config: &Config, symbol: &str) -> Result<f64> {
         .into())
     }
 }
+
+// Smallest units of a positive amount
+fn to_units(amount: f64, decimals: i32) -> Result<u64> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(SolanaClientError::InvalidRequest {
+            message: format!("Amount must be positive, got {}", amount),
+        }
+        .into());
+    }
+    Ok((amount * 10_f64.powi(decimals)).round() as u64)
+}
@@ -0,0 +1,583 @@
+use crate::app_log;
+use crate::audit::Audit;
+use crate::jupiter::{self, QuoteResponse};
+use crate::policy::{Intent, Spend};
+use crate::stake::{to_lamports, to_sol};
+use crate::stake_pool::{self, StakePool};
+use crate::{config::Config, error::SolanaClientError, rpc, transaction, wallet};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use solana_stake_interface::instruction as stake_instruction;
+use solana_stake_interface::program::ID as STAKE_PROGRAM_ID;
+use solana_stake_interface::state::StakeStateV2;
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::str::FromStr;
+
+const POOL_ROUTE: &str = "stake_pool";
+const JUPITER_ROUTE: &str = "jupiter";
+
+/// How `liquid-unstake` turns pool tokens back into SOL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum UnstakeMode {
+    /// SOL now: the pool reserve or a Jupiter swap, whichever pays more
+    Instant,
+    /// A stake account split off a pool validator, withdrawable after it cools down
+    Delayed,
+}
+
+/// What one route would pay out, or why it cannot be used
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteQuote {
+    pub route: &'static str,
+    pub output: Option<f64>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LiquidStakeResult {
+    pub direction: &'static str, // stake or unstake
+    pub token: String,
+    pub pool: String,
+    pub mint: String,
+    pub amount: f64,
+    pub mode: Option<UnstakeMode>,
+    pub routes: Vec<RouteQuote>,
+    pub chosen: Option<&'static str>,
+    pub expected_output: Option<f64>,
+    pub signature: Option<String>,     // None when only quoting
+    pub stake_account: Option<String>, // Delayed unstake only
+}
+
+// A configured pool as read from the chain
+struct PoolContext {
+    address: Pubkey,
+    program_id: Pubkey,
+    pool: StakePool,
+    decimals: u8,
+    stale: Option<String>, // Why deposits and withdrawals would fail this epoch
+}
+
+// Both routes for one direction; the Jupiter quote is kept so it can be executed
+struct Comparison {
+    context: PoolContext,
+    input: u64, // Lamports or pool token units
+    routes: Vec<RouteQuote>,
+    jupiter_quote: Option<QuoteResponse>,
+}
+
+impl Comparison {
+    // Highest payout; the pool wins ties since it has no slippage
+    fn best(&self) -> Option<&RouteQuote> {
+        self.routes.iter().filter(|r| r.output.is_some()).fold(
+            None,
+            |best: Option<&RouteQuote>, r| match best {
+                Some(b) if b.output >= r.output => Some(b),
+                _ => Some(r),
+            },
+        )
+    }
+
+    fn result(
+        &self,
+        direction: &'static str,
+        token: &str,
+        amount: f64,
+        mode: Option<UnstakeMode>,
+    ) -> LiquidStakeResult {
+        let best = self.best();
+        LiquidStakeResult {
+            direction,
+            token: token.to_uppercase(),
+            pool: self.context.address.to_string(),
+            mint: self.context.pool.pool_mint.to_string(),
+            amount,
+            mode,
+            routes: self.routes.clone(),
+            chosen: best.map(|r| r.route),
+            expected_output: best.and_then(|r| r.output),
+            signature: None,
+            stake_account: None,
+        }
+    }
+
+    // The error when nothing can be executed, listing why each route was ruled out
+    fn no_route(&self) -> anyhow::Error {
+        let reasons: Vec<String> = self
+            .routes
+            .iter()
+            .map(|r| format!("{}: {}", r.route, r.note.as_deref().unwrap_or("no quote")))
+            .collect();
+        SolanaClientError::InvalidRequest {
+            message: format!("No route available ({})", reasons.join("; ")),
+        }
+        .into()
+    }
+}
+
+/// Turn `amount` SOL into the pool's LST through whichever of a direct pool
+/// deposit or a Jupiter swap yields more; with `quote_only` nothing is sent
+pub async fn stake(
+    config: &Config,
+    actor: &str,
+    token: &str,
+    amount: f64,
+    quote_only: bool,
+) -> Result<LiquidStakeResult> {
+    if quote_only {
+        let comparison = compare_stake(config, token, amount).await?;
+        return Ok(comparison.result("stake", token, amount, None));
+    }
+
+    let mut audit = Audit::new(
+        actor,
+        "liquid_stake",
+        json!({ "token": token, "amount": amount }),
+    );
+    let result: Result<LiquidStakeResult> = async {
+        let keypair = wallet::load_keypair(config).await?;
+        let comparison = compare_stake(config, token, amount).await?;
+        let mut result = comparison.result("stake", token, amount, None);
+        let sol = Spend {
+            symbol: "SOL".to_string(),
+            mint: config.tokens.sol.clone(),
+            amount,
+        };
+
+        let signature = match result.chosen {
+            Some(POOL_ROUTE) => {
+                let context = &comparison.context;
+                let owner = keypair.pubkey();
+                let pool_tokens_to = pool_token_account(context, &owner);
+                let instructions = vec![
+                    create_associated_token_account_idempotent(
+                        &owner,
+                        &owner,
+                        &context.pool.pool_mint,
+                        &context.pool.token_program_id,
+                    ),
+                    stake_pool::deposit_sol(
+                        &context.program_id,
+                        &context.address,
+                        &context.pool,
+                        &owner,
+                        &pool_tokens_to,
+                        comparison.input,
+                    ),
+                ];
+                audit.check_policy(
+                    config,
+                    &Intent {
+                        spend: Some(sol),
+                        ..Intent::instructions(&owner, &instructions)
+                    },
+                )?;
+                app_log!(
+                    info,
+                    "Depositing {} SOL into pool {}",
+                    amount,
+                    context.address
+                );
+                transaction::sign_and_send(config, &mut audit, &keypair, &[], &instructions)?
+            }
+            Some(_) => {
+                let quote = comparison
+                    .jupiter_quote
+                    .expect("a Jupiter route only wins with a quote");
+                app_log!(
+                    info,
+                    "Swapping {} SOL for {} via Jupiter",
+                    amount,
+                    result.token
+                );
+                jupiter::execute_quote(config, &mut audit, &keypair, quote, sol).await?
+            }
+            None => return Err(comparison.no_route()),
+        };
+
+        result.signature = Some(signature.to_string());
+        Ok(result)
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+/// Turn `amount` of the pool's LST back into SOL. Instant picks the better of
+/// a pool SOL withdrawal and a Jupiter swap; delayed withdraws a stake account
+/// and deactivates it, to be withdrawn with `stake withdraw` once inactive.
+pub async fn unstake(
+    config: &Config,
+    actor: &str,
+    token: &str,
+    amount: f64,
+    mode: UnstakeMode,
+    quote_only: bool,
+) -> Result<LiquidStakeResult> {
+    if quote_only {
+        let comparison = compare_unstake(config, token, amount, mode).await?;
+        return Ok(comparison.result("unstake", token, amount, Some(mode)));
+    }
+
+    let mut audit = Audit::new(
+        actor,
+        "liquid_unstake",
+        json!({ "token": token, "amount": amount, "mode": mode }),
+    );
+    let result: Result<LiquidStakeResult> = async {
+        let keypair = wallet::load_keypair(config).await?;
+        let owner = keypair.pubkey();
+        let comparison = compare_unstake(config, token, amount, mode).await?;
+        let mut result = comparison.result("unstake", token, amount, Some(mode));
+        let context = &comparison.context;
+        let pool_tokens_from = pool_token_account(context, &owner);
+
+        let client = rpc::client(config);
+        ensure_pool_tokens(&client, &pool_tokens_from, amount)?;
+
+        let lst = Spend {
+            symbol: result.token.clone(),
+            mint: context.pool.pool_mint.to_string(),
+            amount,
+        };
+
+        let signature = match (result.chosen, mode) {
+            (None, _) => return Err(comparison.no_route()),
+            (Some(JUPITER_ROUTE), _) => {
+                let quote = comparison
+                    .jupiter_quote
+                    .expect("a Jupiter route only wins with a quote");
+                app_log!(
+                    info,
+                    "Swapping {} {} for SOL via Jupiter",
+                    amount,
+                    result.token
+                );
+                jupiter::execute_quote(config, &mut audit, &keypair, quote, lst).await?
+            }
+            (Some(_), UnstakeMode::Instant) => {
+                let instructions = vec![stake_pool::withdraw_sol(
+                    &context.program_id,
+                    &context.address,
+                    &context.pool,
+                    &owner,
+                    &pool_tokens_from,
+                    comparison.input,
+                )];
+                audit.check_policy(
+                    config,
+                    &Intent {
+                        spend: Some(lst),
+                        ..Intent::instructions(&owner, &instructions)
+                    },
+                )?;
+                app_log!(info, "Withdrawing SOL from pool {}", context.address);
+                transaction::sign_and_send(config, &mut audit, &keypair, &[], &instructions)?
+            }
+            (Some(_), UnstakeMode::Delayed) => {
+                let validator = withdraw_validator(&client, context)?;
+                let stake_to_split = stake_pool::validator_stake_address(
+                    &context.program_id,
+                    &context.address,
+                    &validator,
+                );
+                let new_account = Keypair::new();
+                let space = StakeStateV2::size_of();
+                let reserve = client.get_minimum_balance_for_rent_exemption(space)?;
+
+                // The pool splits into an uninitialized stake account the wallet funds
+                let instructions = vec![
+                    system_instruction::create_account(
+                        &owner,
+                        &new_account.pubkey(),
+                        reserve,
+                        space as u64,
+                        &STAKE_PROGRAM_ID,
+                    ),
+                    stake_pool::withdraw_stake(
+                        &context.program_id,
+                        &context.address,
+                        &context.pool,
+                        &stake_to_split,
+                        &new_account.pubkey(),
+                        &owner,
+                        &pool_tokens_from,
+                        comparison.input,
+                    ),
+                    stake_instruction::deactivate_stake(&new_account.pubkey(), &owner),
+                ];
+                audit.check_policy(
+                    config,
+                    &Intent {
+                        spend: Some(lst),
+                        ..Intent::instructions(&owner, &instructions)
+                    },
+                )?;
+                app_log!(
+                    info,
+                    "Withdrawing stake from validator {} into {}",
+                    validator.vote_account,
+                    new_account.pubkey()
+                );
+                result.stake_account = Some(new_account.pubkey().to_string());
+                transaction::sign_and_send(
+                    config,
+                    &mut audit,
+                    &keypair,
+                    &[&new_account],
+                    &instructions,
+                )?
+            }
+        };
+
+        result.signature = Some(signature.to_string());
+        Ok(result)
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+async fn compare_stake(config: &Config, token: &str, amount: f64) -> Result<Comparison> {
+    let lamports = to_lamports(amount)?;
+    let client = rpc::client(config);
+    let context = load_pool(config, &client, token)?;
+    let scale = 10_f64.powi(context.decimals as i32);
+
+    let pool_route = match (&context.stale, context.pool.sol_deposit_authority) {
+        (Some(reason), _) => unavailable(POOL_ROUTE, reason.clone()),
+        (None, Some(_)) => unavailable(POOL_ROUTE, "SOL deposits are restricted".to_string()),
+        (None, None) => RouteQuote {
+            route: POOL_ROUTE,
+            output: Some(context.pool.sol_deposit_output(lamports) as f64 / scale),
+            note: None,
+        },
+    };
+
+    let mint = context.pool.pool_mint.to_string();
+    let (jupiter_route, jupiter_quote) =
+        jupiter_route(config, &config.tokens.sol, &mint, lamports, scale).await;
+
+    Ok(Comparison {
+        context,
+        input: lamports,
+        routes: vec![pool_route, jupiter_route],
+        jupiter_quote,
+    })
+}
+
+async fn compare_unstake(
+    config: &Config,
+    token: &str,
+    amount: f64,
+    mode: UnstakeMode,
+) -> Result<Comparison> {
+    let client = rpc::client(config);
+    let context = load_pool(config, &client, token)?;
+    let pool = &context.pool;
+    let pool_tokens = to_units(amount, context.decimals)?;
+
+    if mode == UnstakeMode::Delayed {
+        let fee = pool.stake_withdrawal_fee.apply(pool_tokens);
+        let route = match &context.stale {
+            Some(reason) => unavailable(POOL_ROUTE, reason.clone()),
+            None => RouteQuote {
+                route: POOL_ROUTE,
+                output: Some(to_sol(pool.lamports_for_withdrawal(pool_tokens - fee))),
+                note: Some("as a deactivating stake account".to_string()),
+            },
+        };
+        return Ok(Comparison {
+            context,
+            input: pool_tokens,
+            routes: vec![route],
+            jupiter_quote: None,
+        });
+    }
+
+    let fee = pool.sol_withdrawal_fee.apply(pool_tokens);
+    let lamports = pool.lamports_for_withdrawal(pool_tokens - fee);
+    let pool_route = if let Some(reason) = &context.stale {
+        unavailable(POOL_ROUTE, reason.clone())
+    } else if pool.sol_withdraw_authority.is_some() {
+        unavailable(POOL_ROUTE, "SOL withdrawals are restricted".to_string())
+    } else {
+        // The reserve has to stay rent exempt
+        let reserve = client.get_balance(&pool.reserve_stake)?;
+        let rent = client.get_minimum_balance_for_rent_exemption(StakeStateV2::size_of())?;
+        let available = reserve.saturating_sub(rent);
+        if lamports > available {
+            unavailable(
+                POOL_ROUTE,
+                format!("the pool reserve only holds {} SOL", to_sol(available)),
+            )
+        } else {
+            RouteQuote {
+                route: POOL_ROUTE,
+                output: Some(to_sol(lamports)),
+                note: None,
+            }
+        }
+    };
+
+    let mint = pool.pool_mint.to_string();
+    let (jupiter_route, jupiter_quote) = jupiter_route(
+        config,
+        &mint,
+        &config.tokens.sol,
+        pool_tokens,
+        10_f64.powi(9),
+    )
+    .await;
+
+    Ok(Comparison {
+        context,
+        input: pool_tokens,
+        routes: vec![pool_route, jupiter_route],
+        jupiter_quote,
+    })
+}
+
+fn load_pool(config: &Config, client: &RpcClient, token: &str) -> Result<PoolContext> {
+    let address =
+        config
+            .liquid_staking
+            .pool_for(token)
+            .ok_or_else(|| SolanaClientError::InvalidRequest {
+                message: format!(
+                    "No stake pool configured for {}; add it under liquid_staking.pools",
+                    token
+                ),
+            })?;
+    let address = Pubkey::from_str(address).map_err(|_| SolanaClientError::ConfigError {
+        message: format!("Invalid stake pool address for {}: {}", token, address),
+    })?;
+
+    let account = client.get_account(&address)?;
+    let pool = StakePool::decode(&account.data)?;
+    let decimals = client.get_token_supply(&pool.pool_mint)?.decimals;
+
+    // Deposits and withdrawals fail until someone runs the pool's epoch update
+    let epoch = client.get_epoch_info()?.epoch;
+    let stale = (pool.last_update_epoch < epoch).then(|| {
+        format!(
+            "the pool was last updated in epoch {}, current epoch is {}",
+            pool.last_update_epoch, epoch
+        )
+    });
+
+    Ok(PoolContext {
+        address,
+        program_id: account.owner,
+        pool,
+        decimals,
+        stale,
+    })
+}
+
+// A Jupiter quote for the same trade; failures only rule the route out
+async fn jupiter_route(
+    config: &Config,
+    input_mint: &str,
+    output_mint: &str,
+    amount: u64,
+    output_scale: f64,
+) -> (RouteQuote, Option<QuoteResponse>) {
+    let quote = match jupiter::get_quote(config, input_mint, output_mint, amount).await {
+        Ok(quote) => quote,
+        Err(e) => return (unavailable(JUPITER_ROUTE, e.to_string()), None),
+    };
+    match quote.out_amount.parse::<u64>() {
+        Ok(out) => (
+            RouteQuote {
+                route: JUPITER_ROUTE,
+                output: Some(out as f64 / output_scale),
+                note: Some(format!("{} bps slippage", quote.slippage_bps)),
+            },
+            Some(quote),
+        ),
+        Err(e) => (
+            unavailable(JUPITER_ROUTE, format!("unreadable quote: {}", e)),
+            None,
+        ),
+    }
+}
+
+// The preferred withdraw validator if the pool sets one, else the active one with the most stake
+fn withdraw_validator(
+    client: &RpcClient,
+    context: &PoolContext,
+) -> Result<stake_pool::ValidatorStake> {
+    let data = client.get_account_data(&context.pool.validator_list)?;
+    let validators = stake_pool::decode_validator_list(&data)?;
+
+    let preferred = context.pool.preferred_withdraw_validator.and_then(|vote| {
+        validators
+            .iter()
+            .find(|v| v.vote_account == vote && v.active)
+    });
+    preferred
+        .or_else(|| {
+            validators
+                .iter()
+                .filter(|v| v.active)
+                .max_by_key(|v| v.active_stake_lamports)
+        })
+        .cloned()
+        .ok_or_else(|| {
+            SolanaClientError::InvalidRequest {
+                message: "The pool has no active validator stake to withdraw".to_string(),
+            }
+            .into()
+        })
+}
+
+fn ensure_pool_tokens(client: &RpcClient, account: &Pubkey, amount: f64) -> Result<()> {
+    let balance = client
+        .get_token_account_balance(account)
+        .ok()
+        .and_then(|b| b.ui_amount)
+        .unwrap_or(0.0);
+    if balance < amount {
+        return Err(SolanaClientError::InsufficientBalance {
+            current: balance,
+            required: amount,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn pool_token_account(context: &PoolContext, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(
+        owner,
+        &context.pool.pool_mint,
+        &context.pool.token_program_id,
+    )
+}
+
+fn to_units(amount: f64, decimals: u8) -> Result<u64> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(SolanaClientError::InvalidRequest {
+            message: format!("Amount must be positive, got {}", amount),
+        }
+        .into());
+    }
+    Ok((amount * 10_f64.powi(decimals as i32)).round() as u64)
+}
+
+fn unavailable(route: &'static str, note: String) -> RouteQuote {
+    RouteQuote {
+        route,
+        output: None,
+        note: Some(note),
+    }
+}
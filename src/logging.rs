@@ -0,0 +1,125 @@
+use crate::config::LoggingConfig;
+use crate::error::SolanaClientError;
+use anyhow::Result;
+use graflog::tracing_subscriber::fmt::writer::BoxMakeWriter;
+use graflog::tracing_subscriber::prelude::*;
+use graflog::tracing_subscriber::{EnvFilter, Layer, Registry, fmt};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+
+/// Install the global subscriber described by `config.logging`. Command
+/// results are printed separately and never pass through here.
+pub fn init(config: &LoggingConfig) -> Result<()> {
+    let filter = filter(config)?;
+
+    let (writer, ansi) = match config.output.as_str() {
+        "stderr" => (BoxMakeWriter::new(io::stderr), io::stderr().is_terminal()),
+        "stdout" => (BoxMakeWriter::new(io::stdout), io::stdout().is_terminal()),
+        path => (
+            BoxMakeWriter::new(Mutex::new(RotatingFile::open(path, config)?)),
+            false,
+        ),
+    };
+
+    // Server requests run inside a `request` span, so JSON lines carry its request_id
+    let layer: Box<dyn Layer<Registry> + Send + Sync> = match config.format.as_str() {
+        "json" => fmt::layer()
+            .json()
+            .with_writer(writer)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+        _ => fmt::layer().with_writer(writer).with_ansi(ansi).boxed(),
+    };
+
+    graflog::tracing_subscriber::registry()
+        .with(layer)
+        .with(filter)
+        .try_init()
+        .map_err(|e| SolanaClientError::ConfigError {
+            message: format!("Failed to initialize logging: {}", e),
+        })?;
+    Ok(())
+}
+
+fn filter(config: &LoggingConfig) -> Result<EnvFilter> {
+    let mut directives = vec![config.level.clone()];
+    directives.extend(
+        config
+            .modules
+            .iter()
+            .map(|(target, level)| format!("{}={}", target, level)),
+    );
+
+    let mut filter = EnvFilter::default();
+    for directive in directives {
+        filter = filter.add_directive(directive.parse().map_err(|e| {
+            SolanaClientError::ConfigError {
+                message: format!("Invalid log level '{}': {}", directive, e),
+            }
+        })?);
+    }
+    Ok(filter)
+}
+
+/// Appending log file that rotates itself by size
+struct RotatingFile {
+    path: String,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &str, config: &LoggingConfig) -> Result<Self> {
+        let file = open_append(path).map_err(|e| SolanaClientError::ConfigError {
+            message: format!("Cannot open log file {}: {}", path, e),
+        })?;
+        Ok(Self {
+            path: path.to_string(),
+            size: file.metadata()?.len(),
+            file,
+            max_bytes: config.rotation.max_size_mb * 1024 * 1024,
+            max_files: config.rotation.max_files,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                let from = format!("{}.{}", self.path, n);
+                if fs::metadata(&from).is_ok() {
+                    fs::rename(&from, format!("{}.{}", self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, format!("{}.1", self.path))?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &str) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
@@ -1,22 +1,48 @@
+mod api;
+mod audit;
+mod auth;
+mod cleanup;
 mod cli;
 mod config;
+mod contacts;
 mod error;
+mod idempotency;
 mod jupiter;
+mod liquid_stake;
+mod logging;
+mod metaplex;
+mod metrics;
+mod mint;
+mod openapi;
+mod output;
+mod pay;
+mod policy;
+mod rpc;
+mod signing;
+mod solana_client;
+mod stake;
+mod stake_pool;
 mod token;
 mod transaction;
+mod validation;
 mod wallet;
+mod watch;
 mod web;
+mod webhook;
+mod wrap;
 
 use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use clap::{Parser, Subcommand};
 use graflog::app_log;
-use graflog::init_logging;
+use serde_json::json;
 use solana_sdk::signature::Signer;
+use std::process::ExitCode;
 use std::str::FromStr;
-use graflog::LogOption;
 
 use crate::cli::InteractiveMenu;
 use crate::config::Config;
+use crate::output::{OutputFormat, Report};
 
 #[derive(Parser)]
 #[command(name = "solana-cli-client")]
@@ -27,6 +53,30 @@ struct Cli {
 
     #[arg(short, long, default_value = "config.yaml")]
     config: String,
+
+    /// Run against a REST server instead of RPC (`--remote=<url>`, defaults to remote.url)
+    #[arg(long, require_equals = true, num_args = 0..=1)]
+    remote: Option<Option<String>>,
+
+    /// Print RPC/Jupiter call statistics when the command finishes
+    #[arg(long, global = true)]
+    stats: bool,
+
+    /// Network profile from `profiles` (devnet, testnet, mainnet, localnet, ...)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Allow commands that move funds on mainnet
+    #[arg(long, global = true)]
+    confirm_mainnet: bool,
+
+    /// Override any config value, e.g. `--set solana.rpc_url=http://127.0.0.1:8899`
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    /// How results are printed on stdout; logs go to `logging.output`
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Plain)]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -48,6 +98,8 @@ enum Commands {
         to: String,
         #[arg(short, long)]
         amount: f64,
+        #[arg(short, long)]
+        memo: Option<String>, // Recorded on chain, e.g. an invoice reference
     },
     /// Send a transaction
     SendTx {
@@ -76,7 +128,15 @@ enum Commands {
     /// List all tokens in wallet
     ListTokens,
     /// Start web server
-    Server,
+    Server {
+        #[arg(short, long)]
+        port: Option<u16>, // Defaults to server.port
+    },
+    /// Print the OpenAPI document for the web server
+    Openapi {
+        #[arg(short, long)]
+        file: Option<String>, // Write to a file instead of stdout
+    },
     /// Get transaction history for wallet
     History {
         #[arg(short, long, default_value = "50")]
@@ -85,185 +145,2275 @@ enum Commands {
         before: Option<String>,
         #[arg(short, long)]
         pubkey: Option<String>, // Optional: check other wallet
+        #[arg(short, long)]
+        memo: Option<String>, // Only transactions whose memo contains this
     },
     /// Get pending transactions
     Pending {
         #[arg(short, long)]
         pubkey: Option<String>, // Optional: check other wallet
     },
+    /// Authenticate against a remote server (Sign-In-With-Solana)
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommands,
+    },
+    /// Watch a wallet or a submitted signature in real time
+    Watch {
+        #[arg(short, long)]
+        pubkey: Option<String>, // Optional: watch other wallet
+        #[arg(short, long, conflicts_with = "pubkey")]
+        signature: Option<String>, // Track a submitted transaction instead
+    },
+    /// Inspect the resolved configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Inspect the transaction audit log
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommands,
+    },
+    /// Address book; a contact's `@name` works wherever an address does
+    Contacts {
+        #[command(subcommand)]
+        action: ContactsCommands,
+    },
+    /// Manage native stake accounts; the wallet is their stake and withdraw authority
+    Stake {
+        #[command(subcommand)]
+        action: StakeCommands,
+    },
+    /// Move SOL into the wallet's wrapped SOL (wSOL) account, creating it if needed
+    Wrap {
+        #[arg(short, long)]
+        amount: f64, // SOL
+    },
+    /// Move wrapped SOL back to native SOL
+    Unwrap {
+        #[arg(short, long)]
+        amount: Option<f64>, // SOL; omit to close the account and reclaim its rent
+    },
+    /// Close empty and non-canonical token accounts to reclaim their rent
+    Cleanup {
+        #[arg(long)]
+        dust_usd: Option<f64>, // Also clear balances worth less than this many USD
+        #[arg(long, value_enum, default_value_t = api::DustAction::Burn, requires = "dust_usd")]
+        dust_action: api::DustAction,
+        #[arg(long)]
+        dry_run: bool, // Only list what would be closed
+    },
+    /// Create and administer SPL token mints; the wallet pays and signs as authority
+    Token {
+        #[command(subcommand)]
+        action: TokenCommands,
+    },
+    /// Solana Pay: request a payment and detect it on chain
+    Pay {
+        #[command(subcommand)]
+        action: PayCommands,
+    },
+    /// Swap SOL for a liquid staking token by pool deposit or Jupiter, whichever yields more
+    LiquidStake {
+        #[arg(short, long)]
+        token: String, // LST symbol from liquid_staking.pools
+        #[arg(short, long)]
+        amount: f64,
+        #[arg(long)]
+        quote_only: bool, // Compare the routes without sending anything
+    },
+    /// Turn a liquid staking token back into SOL
+    LiquidUnstake {
+        #[arg(short, long)]
+        token: String,
+        #[arg(short, long)]
+        amount: f64,
+        #[arg(short, long, value_enum, default_value_t = liquid_stake::UnstakeMode::Instant)]
+        mode: liquid_stake::UnstakeMode,
+        #[arg(long)]
+        quote_only: bool,
+    },
+}
+
+impl Commands {
+    // Swap `@name` for the contact's address in every address argument
+    fn resolve_aliases(&mut self, book: &contacts::AddressBook) -> Result<()> {
+        match self {
+            Commands::CreateTx { to, .. } => book.resolve_in_place(to),
+            Commands::Swap { from, to, .. } => {
+                book.resolve_in_place(from)?;
+                book.resolve_in_place(to)
+            }
+            Commands::Price { token } => book.resolve_in_place(token),
+            Commands::History { pubkey, .. }
+            | Commands::Pending { pubkey }
+            | Commands::Watch { pubkey, .. } => book.resolve_option(pubkey),
+            Commands::Stake { action } => action.resolve_aliases(book),
+            Commands::Token { action } => action.resolve_aliases(book),
+            Commands::Pay { action } => action.resolve_aliases(book),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum StakeCommands {
+    /// Fund a new stake account from the wallet
+    Create {
+        #[arg(short, long)]
+        amount: f64,
+        #[arg(short, long)]
+        seed: Option<String>, // Derive the address from the wallet instead of a new keypair
+    },
+    /// Delegate a stake account to a validator's vote account
+    Delegate {
+        #[arg(long)]
+        stake_account: String,
+        #[arg(long)]
+        vote_account: String,
+    },
+    /// Start cooling down a delegated stake account
+    Deactivate {
+        #[arg(long)]
+        stake_account: String,
+    },
+    /// Move SOL out of a stake account, by default all of it to the wallet
+    Withdraw {
+        #[arg(long)]
+        stake_account: String,
+        #[arg(short, long)]
+        amount: Option<f64>,
+        #[arg(short, long)]
+        to: Option<String>,
+    },
+    /// Move part of a stake account into a new one
+    Split {
+        #[arg(long)]
+        stake_account: String,
+        #[arg(short, long)]
+        amount: f64,
+        #[arg(short, long)]
+        seed: Option<String>,
+    },
+    /// Fold the source account into the stake account
+    Merge {
+        #[arg(long)]
+        stake_account: String,
+        #[arg(long)]
+        source: String,
+    },
+    /// List stake accounts with activation state and recent rewards
+    List {
+        #[arg(short, long)]
+        pubkey: Option<String>, // Optional: check other wallet
+        #[arg(short, long, default_value_t = stake::DEFAULT_REWARD_EPOCHS)]
+        epochs: usize,
+    },
+}
+
+impl StakeCommands {
+    fn resolve_aliases(&mut self, book: &contacts::AddressBook) -> Result<()> {
+        match self {
+            StakeCommands::Create { .. } => Ok(()),
+            StakeCommands::Delegate {
+                stake_account,
+                vote_account,
+            } => {
+                book.resolve_in_place(stake_account)?;
+                book.resolve_in_place(vote_account)
+            }
+            StakeCommands::Deactivate { stake_account }
+            | StakeCommands::Split { stake_account, .. } => book.resolve_in_place(stake_account),
+            StakeCommands::Withdraw {
+                stake_account, to, ..
+            } => {
+                book.resolve_in_place(stake_account)?;
+                book.resolve_option(to)
+            }
+            StakeCommands::Merge {
+                stake_account,
+                source,
+            } => {
+                book.resolve_in_place(stake_account)?;
+                book.resolve_in_place(source)
+            }
+            StakeCommands::List { pubkey, .. } => book.resolve_option(pubkey),
+        }
+    }
+
+    // Everything but `list` is an operation the REST API can prepare as well
+    fn operation(self) -> Option<api::StakeOperation> {
+        Some(match self {
+            StakeCommands::Create { amount, seed } => api::StakeOperation::Create { amount, seed },
+            StakeCommands::Delegate {
+                stake_account,
+                vote_account,
+            } => api::StakeOperation::Delegate {
+                stake_account,
+                vote_account,
+            },
+            StakeCommands::Deactivate { stake_account } => {
+                api::StakeOperation::Deactivate { stake_account }
+            }
+            StakeCommands::Withdraw {
+                stake_account,
+                amount,
+                to,
+            } => api::StakeOperation::Withdraw {
+                stake_account,
+                amount,
+                to,
+            },
+            StakeCommands::Split {
+                stake_account,
+                amount,
+                seed,
+            } => api::StakeOperation::Split {
+                stake_account,
+                amount,
+                seed,
+            },
+            StakeCommands::Merge {
+                stake_account,
+                source,
+            } => api::StakeOperation::Merge {
+                stake_account,
+                source_account: source,
+            },
+            StakeCommands::List { .. } => return None,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Create a new mint, optionally Token-2022 with extensions and Metaplex metadata
+    CreateMint {
+        #[arg(short, long, default_value_t = 9)]
+        decimals: u8,
+        #[arg(long)]
+        mint_authority: Option<String>, // Defaults to the wallet
+        #[arg(long)]
+        freeze_authority: Option<String>,
+        #[arg(long)]
+        token_2022: bool, // Implied by any extension flag
+        #[arg(long)]
+        transfer_fee_bps: Option<u16>,
+        #[arg(long, requires = "transfer_fee_bps", default_value_t = 0.0)]
+        max_fee: f64, // Whole tokens
+        #[arg(long)]
+        interest_rate_bps: Option<i16>,
+        #[arg(long)]
+        non_transferable: bool,
+        #[arg(long)]
+        permanent_delegate: Option<String>,
+        #[arg(long)]
+        close_authority: bool, // Let the wallet close the mint once supply is zero
+        #[arg(long, requires = "freeze_authority")]
+        default_frozen: bool,
+        #[arg(long, requires_all = ["symbol", "uri"])]
+        name: Option<String>,
+        #[arg(long, requires_all = ["name", "uri"])]
+        symbol: Option<String>,
+        #[arg(long, requires_all = ["name", "symbol"])]
+        uri: Option<String>,
+        #[arg(short, long)]
+        seed: Option<String>, // Derive the address from the wallet instead of a new keypair
+    },
+    /// Mint tokens to a wallet's associated token account
+    MintTo {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(short, long)]
+        amount: f64,
+        #[arg(short, long)]
+        to: Option<String>, // Defaults to the wallet
+    },
+    /// Burn tokens from the wallet
+    Burn {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(short, long)]
+        amount: f64,
+    },
+    /// Freeze a wallet's token account
+    Freeze {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(long)]
+        owner: String,
+    },
+    /// Thaw a frozen token account
+    Thaw {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(long)]
+        owner: String,
+    },
+    /// Hand a mint authority to another address, or revoke it
+    SetAuthority {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(long, value_enum)]
+        authority: api::MintAuthority,
+        #[arg(long, required_unless_present = "revoke")]
+        new_authority: Option<String>,
+        #[arg(long, conflicts_with = "new_authority")]
+        revoke: bool, // Irreversible
+    },
+    /// Add Metaplex metadata to a mint the wallet is mint authority of
+    CreateMetadata {
+        #[arg(short, long)]
+        mint: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+}
+
+impl TokenCommands {
+    fn resolve_aliases(&mut self, book: &contacts::AddressBook) -> Result<()> {
+        match self {
+            TokenCommands::CreateMint {
+                mint_authority,
+                freeze_authority,
+                permanent_delegate,
+                ..
+            } => {
+                book.resolve_option(mint_authority)?;
+                book.resolve_option(freeze_authority)?;
+                book.resolve_option(permanent_delegate)
+            }
+            TokenCommands::MintTo { mint, to, .. } => {
+                book.resolve_in_place(mint)?;
+                book.resolve_option(to)
+            }
+            TokenCommands::Freeze { mint, owner } | TokenCommands::Thaw { mint, owner } => {
+                book.resolve_in_place(mint)?;
+                book.resolve_in_place(owner)
+            }
+            TokenCommands::SetAuthority {
+                mint,
+                new_authority,
+                ..
+            } => {
+                book.resolve_in_place(mint)?;
+                book.resolve_option(new_authority)
+            }
+            TokenCommands::Burn { mint, .. } | TokenCommands::CreateMetadata { mint, .. } => {
+                book.resolve_in_place(mint)
+            }
+        }
+    }
+
+    fn operation(self) -> api::MintOperation {
+        match self {
+            TokenCommands::CreateMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                token_2022,
+                transfer_fee_bps,
+                max_fee,
+                interest_rate_bps,
+                non_transferable,
+                permanent_delegate,
+                close_authority,
+                default_frozen,
+                name,
+                symbol,
+                uri,
+                seed,
+            } => {
+                let mut extensions = Vec::new();
+                if let Some(basis_points) = transfer_fee_bps {
+                    extensions.push(api::MintExtension::TransferFee {
+                        basis_points,
+                        maximum_fee: max_fee,
+                    });
+                }
+                if let Some(rate_bps) = interest_rate_bps {
+                    extensions.push(api::MintExtension::InterestBearing { rate_bps });
+                }
+                if non_transferable {
+                    extensions.push(api::MintExtension::NonTransferable);
+                }
+                if let Some(delegate) = permanent_delegate {
+                    extensions.push(api::MintExtension::PermanentDelegate { delegate });
+                }
+                if close_authority {
+                    extensions.push(api::MintExtension::MintCloseAuthority);
+                }
+                if default_frozen {
+                    extensions.push(api::MintExtension::DefaultFrozen);
+                }
+
+                api::MintOperation::CreateMint {
+                    decimals,
+                    mint_authority,
+                    freeze_authority,
+                    token_2022,
+                    extensions,
+                    metadata: match (name, symbol, uri) {
+                        (Some(name), Some(symbol), Some(uri)) => {
+                            Some(api::TokenMetadata { name, symbol, uri })
+                        }
+                        _ => None,
+                    },
+                    seed,
+                }
+            }
+            TokenCommands::MintTo { mint, amount, to } => {
+                api::MintOperation::MintTo { mint, amount, to }
+            }
+            TokenCommands::Burn { mint, amount } => api::MintOperation::Burn { mint, amount },
+            TokenCommands::Freeze { mint, owner } => api::MintOperation::Freeze { mint, owner },
+            TokenCommands::Thaw { mint, owner } => api::MintOperation::Thaw { mint, owner },
+            TokenCommands::SetAuthority {
+                mint,
+                authority,
+                new_authority,
+                ..
+            } => api::MintOperation::SetAuthority {
+                mint,
+                authority,
+                new_authority,
+            },
+            TokenCommands::CreateMetadata {
+                mint,
+                name,
+                symbol,
+                uri,
+            } => api::MintOperation::CreateMetadata {
+                mint,
+                name,
+                symbol,
+                uri,
+            },
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum PayCommands {
+    /// Print a solana: transfer request URL and its QR code
+    Request {
+        #[arg(short, long)]
+        recipient: Option<String>, // Defaults to pay.recipient, then the wallet
+        #[arg(short, long)]
+        amount: Option<f64>,
+        #[arg(short = 't', long)]
+        spl_token: Option<String>, // Symbol or mint; SOL if omitted
+        #[arg(long)]
+        label: Option<String>,
+        #[arg(long)]
+        message: Option<String>,
+        #[arg(long)]
+        memo: Option<String>,
+        #[arg(long, requires = "amount")]
+        pay_with: Option<String>, // Token the payer spends; served as a transaction request
+        #[arg(long)]
+        watch: bool, // Wait until it is paid or expires
+    },
+    /// Look a payment up by its reference
+    Status {
+        #[arg(long)]
+        reference: String,
+        #[arg(short, long)]
+        recipient: String,
+        #[arg(short, long)]
+        amount: Option<f64>,
+        #[arg(short = 't', long)]
+        spl_token: Option<String>,
+        #[arg(long)]
+        expires_at: Option<i64>, // Unix seconds; without it a missing payment stays pending
+        #[arg(long)]
+        watch: bool,
+    },
+}
+
+impl PayCommands {
+    fn resolve_aliases(&mut self, book: &contacts::AddressBook) -> Result<()> {
+        match self {
+            PayCommands::Request {
+                recipient,
+                spl_token,
+                pay_with,
+                ..
+            } => {
+                book.resolve_option(recipient)?;
+                book.resolve_option(spl_token)?;
+                book.resolve_option(pay_with)
+            }
+            PayCommands::Status {
+                recipient,
+                spl_token,
+                ..
+            } => {
+                book.resolve_in_place(recipient)?;
+                book.resolve_option(spl_token)
+            }
+        }
+    }
+
+    fn request(&self) -> Option<api::PayRequest> {
+        match self {
+            PayCommands::Request {
+                recipient,
+                amount,
+                spl_token,
+                label,
+                message,
+                memo,
+                pay_with,
+                ..
+            } => Some(api::PayRequest {
+                recipient: recipient.clone(),
+                amount: *amount,
+                spl_token: spl_token.clone(),
+                label: label.clone(),
+                message: message.clone(),
+                memo: memo.clone(),
+                pay_with: pay_with.clone(),
+            }),
+            PayCommands::Status { .. } => None,
+        }
+    }
+
+    fn status(&self) -> Option<api::PayStatusRequest> {
+        match self {
+            PayCommands::Status {
+                reference,
+                recipient,
+                amount,
+                spl_token,
+                expires_at,
+                ..
+            } => Some(api::PayStatusRequest {
+                reference: reference.clone(),
+                recipient: recipient.clone(),
+                amount: *amount,
+                spl_token: spl_token.clone(),
+                expires_at: *expires_at,
+            }),
+            PayCommands::Request { .. } => None,
+        }
+    }
+
+    fn watch(&self) -> bool {
+        match self {
+            PayCommands::Request { watch, .. } | PayCommands::Status { watch, .. } => *watch,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective configuration, secrets redacted
+    Show {
+        #[arg(long)]
+        resolved: bool, // One line per value, with the layer it came from
+    },
+    /// Check every setting and report problems by key
+    Validate,
+    /// Interactively write a working config for a network
+    Init {
+        #[arg(short, long)]
+        file: Option<String>, // Defaults to the -c path
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// Check that no entry was altered, removed or reordered
+    Verify,
+    /// Search entries for an investigation
+    Query {
+        #[arg(long)]
+        actor: Option<String>, // Substring, e.g. `key:gateway` or `cli:`
+        #[arg(long)]
+        action: Option<String>, // transfer, swap, stake, liquid_stake, liquid_unstake, mint, cleanup, wrap, unwrap or submit
+        #[arg(long)]
+        status: Option<String>,
+        #[arg(long)]
+        signature: Option<String>,
+        #[arg(long)]
+        address: Option<String>, // Any address in the intent
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>, // RFC 3339
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContactsCommands {
+    /// Save an address under a name
+    Add {
+        #[arg(short, long)]
+        name: String, // Letters, digits, '-' and '_'; case-insensitive
+        #[arg(short, long)]
+        address: String,
+        #[arg(long)]
+        note: Option<String>,
+        #[arg(short, long = "tag")]
+        tags: Vec<String>, // Repeat for several
+    },
+    /// List contacts, optionally only those with a tag
+    List {
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Forget a contact
+    Remove {
+        #[arg(short, long)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Sign a challenge with the local wallet and store the session
+    Login {
+        #[arg(short, long)]
+        server: Option<String>, // Defaults to remote.url
+    },
+    /// Exchange the stored refresh token for a new session
+    Refresh {
+        #[arg(short, long)]
+        server: Option<String>,
+    },
+    /// Forget the stored session
+    Logout,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    let format = cli.output;
+
+    match run(cli).await {
+        Ok(report) => {
+            report.print(format);
+            // The transaction went through, but the journal is missing it
+            if audit::write_failed() {
+                eprintln!("Error: failed to write the audit entry, see the log");
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            app_log!(debug, "Command failed: {:?}", e);
+            output::fail(format, &e)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<Report> {
+    // Runs before loading, the existing file may be missing or broken
+    if let Some(Commands::Config {
+        action: ConfigCommands::Init { file },
+    }) = &cli.command
+    {
+        let path = file.as_deref().unwrap_or(&cli.config);
+        let written = cli::config_wizard(path)?;
+        let report = Report::new(json!({ "path": path, "written": written }))?;
+        return Ok(match written {
+            true => report
+                .line(format!("✅ Wrote {}", path))
+                .line("   A random internal secret was generated, keep the file private"),
+            false => report.line("❌ Nothing written"),
+        });
+    }
 
     // Initialize config
-    let config = Config::load(&cli.config)?;
+    let base = Config::load(&cli.config, &config_flags(&cli)?)?;
+
+    // `config` subcommands must work on a broken config, that is what they are for
+    let inspecting = matches!(cli.command, Some(Commands::Config { .. }));
+    if let Err(e) = logging::init(&base.logging) {
+        if !inspecting {
+            return Err(e);
+        }
+        logging::init(&config::LoggingConfig::default())?;
+    }
+
+    app_log!(debug, "Starting Solana CLI client");
+    if std::path::Path::new(&cli.config).exists() {
+        app_log!(debug, "Loaded config from {}", cli.config);
+    } else {
+        app_log!(
+            warn,
+            "Config file {} not found, using defaults and environment",
+            cli.config
+        );
+    }
+
+    let config = match base.profile.as_ref() {
+        Some(profile) if !inspecting => base.with_profile(profile)?,
+        _ => base.clone(),
+    };
+    if !inspecting {
+        check_config(&config)?;
+    }
+
+    // `@name` arguments become addresses before any command sees them
+    let mut command = cli.command;
+    if !inspecting && let Some(command) = &mut command {
+        command.resolve_aliases(&contacts::AddressBook::load(&config)?)?;
+    }
+
+    let result = match cli.remote {
+        Some(server) => {
+            let client = solana_client::connect(&config, server).await?;
+            run_remote(&config, &client, command).await
+        }
+        None => run_local(config, base, command, cli.confirm_mainnet, cli.output).await,
+    };
+
+    // On stderr, so it never mixes with the command's output
+    if cli.stats {
+        eprintln!("📈 Call statistics:");
+        for line in metrics::summary() {
+            eprintln!("   {}", line);
+        }
+    }
+
+    result
+}
 
-    init_logging!("/var/log/solanize.log", "solanize", "cli", &[
-        LogOption::Debug,
-        LogOption::RocketOff
-    ]);
-    app_log!(info, "Starting Solana CLI client");
+// Refuse to start on errors; warnings are only logged
+fn check_config(config: &Config) -> Result<()> {
+    let mut errors = Vec::new();
+    for issue in validation::validate(config) {
+        match issue.severity {
+            validation::Severity::Warning => {
+                app_log!(warn, "Config {}: {}", issue.key, issue.message)
+            }
+            validation::Severity::Error => errors.push(format!("{}: {}", issue.key, issue.message)),
+        }
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(error::SolanaClientError::ConfigError {
+        message: format!(
+            "{} invalid setting(s), run `config validate` for details\n  {}",
+            errors.len(),
+            errors.join("\n  ")
+        ),
+    }
+    .into())
+}
+
+// The command-line layer of the config: `--set`, plus flags that map to a setting
+fn config_flags(cli: &Cli) -> Result<Vec<(String, String)>> {
+    let mut flags = Vec::new();
 
-    match cli.command {
+    for assignment in &cli.overrides {
+        let (key, value) =
+            assignment
+                .split_once('=')
+                .ok_or_else(|| error::SolanaClientError::ConfigError {
+                    message: format!("Expected KEY=VALUE, got '{}'", assignment),
+                })?;
+        flags.push((key.trim().to_string(), value.to_string()));
+    }
+
+    if let Some(profile) = &cli.profile {
+        flags.push(("profile".to_string(), profile.clone()));
+    }
+
+    if let Some(Commands::Server {
+        port: Some(port), ..
+    }) = &cli.command
+    {
+        flags.push(("server.port".to_string(), port.to_string()));
+    }
+
+    Ok(flags)
+}
+
+// Commands that move funds or serve requests need an explicit opt-in on mainnet
+fn ensure_mainnet_confirmed(config: &Config, confirmed: bool, operation: &str) -> Result<()> {
+    if config.is_mainnet() && !confirmed {
+        return Err(error::SolanaClientError::ConfigError {
+            message: format!(
+                "Refusing to {} on {} without --confirm-mainnet",
+                operation, config.solana.network
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+async fn run_local(
+    config: Config,
+    base: Config,
+    command: Option<Commands>,
+    confirm_mainnet: bool,
+    format: OutputFormat,
+) -> Result<Report> {
+    let report = match command {
         Some(Commands::Menu) | None => {
-            let menu = InteractiveMenu::new(config);
+            if format == OutputFormat::Json {
+                return Err(error::SolanaClientError::InvalidRequest {
+                    message: "The interactive menu has no JSON output, run a command instead"
+                        .to_string(),
+                }
+                .into());
+            }
+            let mut menu = InteractiveMenu::new(base, config, confirm_mainnet);
             menu.run().await?;
+            Report::empty()
         }
         Some(Commands::GenerateWallet) => {
-            wallet::generate_wallet(&config).await?;
+            let pubkey = wallet::generate_wallet(&config).await?;
+            Report::new(json!({
+                "pubkey": pubkey.to_string(),
+                "path": config.wallet.keypair_path,
+            }))?
+            .line("✅ Wallet generated successfully!")
+            .line(format!("📍 Public Key: {}", pubkey))
+            .line(format!("💾 Saved to: {}", config.wallet.keypair_path))
         }
         Some(Commands::Balance) => {
-            let balance = wallet::get_balance(&config).await?;
-            app_log!(info, "Balance: {} SOL", balance);
+            let keypair = wallet::load_keypair(&config).await?;
+            let balance = wallet::get_balance_for_pubkey(&config, &keypair.pubkey()).await?;
+            balance_report(api::BalanceResponse {
+                pubkey: keypair.pubkey().to_string(),
+                balance,
+                token: "SOL".to_string(),
+            })?
         }
         Some(Commands::Faucet { amount }) => {
-            wallet::request_airdrop(&config, amount).await?;
+            let (signature, balance) = wallet::request_airdrop(&config, amount).await?;
+            Report::new(json!({
+                "signature": signature.to_string(),
+                "amount": amount,
+                "balance": balance,
+            }))?
+            .line("✅ Airdrop confirmed!")
+            .line(format!("🔗 Signature: {}", signature))
+            .line(format!("💰 New balance: {} SOL", balance))
         }
-        Some(Commands::CreateTx { to, amount }) => {
-            let tx = transaction::create_transaction(&config, &to, amount).await?;
-            app_log!(info, "Transaction created: {}", tx);
+        Some(Commands::CreateTx { to, amount, memo }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "sign a transfer")?;
+            let new_recipient = contacts::is_new_recipient(&config, &to)?;
+            let tx = transaction::create_transaction(
+                &config,
+                &audit::cli_actor(),
+                &to,
+                amount,
+                memo.as_deref(),
+            )
+            .await?;
+            created_report(tx, &to, amount, memo.as_deref(), new_recipient)?
         }
         Some(Commands::SendTx { signature }) => {
-            transaction::send_transaction(&config, &signature).await?;
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "send a transaction")?;
+            let signature =
+                transaction::send_transaction(&config, &audit::cli_actor(), &signature).await?;
+            submitted_report(api::SubmitResponse {
+                signature: signature.to_string(),
+                status: "confirmed".to_string(),
+            })?
         }
         Some(Commands::Swap { from, to, amount }) => {
-            jupiter::swap_tokens(&config, &from, &to, amount).await?;
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "swap")?;
+            let swap =
+                jupiter::swap_tokens(&config, &audit::cli_actor(), &from, &to, amount).await?;
+            swap_report(swap)?
         }
         Some(Commands::Price { token }) => {
             let price = jupiter::get_token_price(&config, &token).await?;
-            app_log!(info, "Price for {}: ${}", token, price);
+            price_report(api::PriceResponse {
+                token,
+                price,
+                currency: "USD".to_string(),
+            })?
         }
         Some(Commands::Search { query }) => {
-            let tokens = token::search_tokens(&config, &query).await?;
-            for token in tokens {
-                app_log!(info, "{}: {} ({})", token.symbol, token.name, token.address);
-            }
+            let tokens: Vec<api::TokenInfo> = token::search_tokens(&config, &query)
+                .await?
+                .into_iter()
+                .map(|t| api::TokenInfo {
+                    symbol: t.symbol,
+                    name: t.name,
+                    address: t.address,
+                    decimals: t.decimals,
+                })
+                .collect();
+            search_report(api::TokenSearchResponse {
+                count: tokens.len(),
+                tokens,
+            })?
         }
         Some(Commands::ListTokens) => {
-            wallet::list_wallet_tokens(&config).await?;
+            let keypair = wallet::load_keypair(&config).await?;
+            let tokens = wallet::get_wallet_holdings(&config, &keypair.pubkey()).await?;
+            wallet_tokens_report(api::WalletTokensResponse {
+                pubkey: keypair.pubkey().to_string(),
+                total_tokens: tokens.len(),
+                tokens,
+            })?
         }
-        Some(Commands::Server) => {
-            let port = std::env::var("ROCKET_PORT")
-                .map_err(|_| anyhow::anyhow!("ROCKET_PORT environment variable is required"))?
-                .parse::<u16>()
-                .map_err(|_| anyhow::anyhow!("ROCKET_PORT must be a valid port number"))?;
-
-            app_log!(info, "Starting web server on port {}", port);
-            web::start_server(config, port).await?;
+        Some(Commands::Openapi { file }) => {
+            let spec = openapi::spec_json()?;
+            match file {
+                Some(path) => {
+                    std::fs::write(&path, spec)?;
+                    Report::new(json!({ "path": path }))?
+                        .line(format!("OpenAPI spec written to {}", path))
+                }
+                None => Report::new(serde_json::from_str::<serde_json::Value>(&spec)?)?.line(spec),
+            }
+        }
+        Some(Commands::Server { .. }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "start the server")?;
+            app_log!(info, "Starting web server on port {}", config.server.port);
+            web::start_server(config).await?;
+            Report::empty()
         }
         Some(Commands::History {
             limit,
             before,
             pubkey,
+            memo,
         }) => {
-            let target_pubkey = if let Some(pk) = pubkey {
-                solana_sdk::pubkey::Pubkey::from_str(&pk)?
-            } else {
-                let keypair = wallet::load_keypair(&config).await?;
-                keypair.pubkey()
-            };
-
-            let history = transaction::fetch_transaction_history(
+            let target_pubkey = resolve_target(&config, pubkey).await?;
+            let (transactions, next_before) = transaction::search_transaction_history(
                 &config,
                 &target_pubkey,
                 Some(limit),
                 before,
+                memo.as_deref(),
             )
             .await?;
 
-            if history.is_empty() {
-                app_log!(info, "No transactions found");
-            } else {
-                app_log!(info, "Transaction History for {}", target_pubkey);
-                app_log!(info, "{}", "=".repeat(80));
-
-                for (i, tx) in history.iter().enumerate() {
-                    app_log!(
-                        info,
-                        "{}. {} | {} | {:?}",
-                        i + 1,
-                        &tx.signature[..8],
-                        format_tx_type(&tx.transaction_type),
-                        tx.status
-                    );
-
-                    if let Some(amount) = tx.amount {
-                        let symbol = tx.token_symbol.as_deref().unwrap_or("Unknown");
-                        app_log!(info, "   Amount: {} {}", amount, symbol);
-                    }
+            // Same pagination fields as the REST endpoint
+            history_report(api::TransactionHistoryResponse {
+                pubkey: target_pubkey.to_string(),
+                total_count: transactions.len(),
+                transactions,
+                has_more: next_before.is_some(),
+                next_before,
+            })?
+        }
 
-                    if let Some(fee) = tx.fee {
-                        app_log!(info, "   Fee: {} SOL", fee);
-                    }
+        Some(Commands::Pending { pubkey }) => {
+            let target_pubkey = resolve_target(&config, pubkey).await?;
+            let pending = transaction::fetch_pending_transactions(&config, &target_pubkey).await?;
+            pending_report(api::PendingTransactionsResponse {
+                pubkey: target_pubkey.to_string(),
+                count: pending.len(),
+                pending_transactions: pending,
+            })?
+        }
 
-                    if let Some(block_time) = tx.block_time {
-                        let dt = chrono::DateTime::from_timestamp(block_time, 0)
-                            .unwrap_or_else(|| chrono::Utc::now());
-                        app_log!(info, "   Time: {}", dt.format("%Y-%m-%d %H:%M:%S UTC"));
-                    }
+        Some(Commands::Auth { action }) => match action {
+            AuthCommands::Login { server } => {
+                let client = solana_client::SolanaApiClient::new(solana_client::remote_url(
+                    &config, server,
+                )?);
+                let keypair = wallet::load_keypair(&config).await?;
+                let tokens = client.login(&keypair).await?;
+                solana_client::save_session(&config.remote.session_path, &tokens)?;
+                Report::new(json!({ "wallet_address": tokens.wallet_address }))?
+                    .line(format!("✅ Logged in as {}", tokens.wallet_address))
+            }
+            AuthCommands::Refresh { server } => {
+                let client = solana_client::SolanaApiClient::new(solana_client::remote_url(
+                    &config, server,
+                )?);
+                let session = solana_client::load_session(&config.remote.session_path)?;
+                let tokens = client.refresh_token(&session.refresh_token).await?;
+                solana_client::save_session(&config.remote.session_path, &tokens)?;
+                Report::new(json!({ "wallet_address": tokens.wallet_address }))?.line(format!(
+                    "✅ Session refreshed for {}",
+                    tokens.wallet_address
+                ))
+            }
+            AuthCommands::Logout => {
+                if std::path::Path::new(&config.remote.session_path).exists() {
+                    std::fs::remove_file(&config.remote.session_path)?;
+                }
+                Report::new(json!({ "logged_out": true }))?.line("Logged out")
+            }
+        },
 
-                    match tx.confirmation_status {
-                        transaction::ConfirmationStatus::Finalized => {
-                            app_log!(info, "   Status: Finalized")
-                        }
-                        transaction::ConfirmationStatus::Confirmed => {
-                            app_log!(info, "   Status: Confirmed")
-                        }
-                        transaction::ConfirmationStatus::Processed => {
-                            app_log!(info, "   Status: Processed")
+        Some(Commands::Config { action }) => match action {
+            ConfigCommands::Show { resolved: false } => {
+                Report::new(config.redacted()?)?.line(config.to_redacted_yaml()?)
+            }
+            ConfigCommands::Show { resolved: true } => {
+                let resolved = config.resolved()?;
+                let data: Vec<_> = resolved
+                    .iter()
+                    .map(|(key, value, source)| {
+                        json!({ "key": key, "value": value, "source": source })
+                    })
+                    .collect();
+                let mut report = Report::new(data)?.table(
+                    &["KEY", "VALUE", "SOURCE"],
+                    resolved
+                        .iter()
+                        .map(|(key, value, source)| {
+                            vec![key.clone(), output::cell(value), source.clone()]
+                        })
+                        .collect(),
+                );
+                for (key, value, source) in &resolved {
+                    report = report.line(format!("{} = {}  # {}", key, value, source));
+                }
+                report
+            }
+            ConfigCommands::Validate => {
+                let issues = validation::validate(&config);
+                let errors: Vec<String> = issues
+                    .iter()
+                    .filter(|i| i.severity == validation::Severity::Error)
+                    .map(|i| format!("{}: {}", i.key, i.message))
+                    .collect();
+
+                // Problems are the result here, so they go to stdout even on failure
+                if !errors.is_empty() {
+                    if format != OutputFormat::Json {
+                        for issue in &issues {
+                            println!("{}", issue_line(issue));
                         }
                     }
+                    return Err(error::SolanaClientError::ConfigError {
+                        message: format!(
+                            "{} error(s) in configuration: {}",
+                            errors.len(),
+                            errors.join("; ")
+                        ),
+                    }
+                    .into());
+                }
+
+                let mut report = Report::new(json!({ "valid": true, "issues": issues }))?.table(
+                    &["KEY", "SEVERITY", "MESSAGE"],
+                    issues
+                        .iter()
+                        .map(|i| vec![i.key.clone(), "warning".to_string(), i.message.clone()])
+                        .collect(),
+                );
+                for issue in &issues {
+                    report = report.line(issue_line(issue));
+                }
+                report.line("✅ Configuration is valid")
+            }
+            ConfigCommands::Init { .. } => unreachable!("handled before the config is loaded"),
+        },
 
-                    if let Some(error) = &tx.error {
-                        app_log!(info, "   Error: {}", error);
+        Some(Commands::Audit { action }) => match action {
+            AuditCommands::Verify => {
+                let count = audit::verify(&config.audit.path)?;
+                Report::new(json!({ "path": config.audit.path, "entries": count }))?.line(format!(
+                    "✅ Audit log intact: {} entries in {}",
+                    count, config.audit.path
+                ))
+            }
+            AuditCommands::Query {
+                actor,
+                action,
+                status,
+                signature,
+                address,
+                since,
+                until,
+                limit,
+            } => {
+                let entries = audit::query(
+                    &config.audit.path,
+                    &audit::AuditQuery {
+                        actor,
+                        action,
+                        status,
+                        signature,
+                        address,
+                        since,
+                        until,
+                        limit,
+                    },
+                )?;
+                audit_report(&entries)?
+            }
+        },
+        Some(Commands::Contacts { action }) => {
+            let mut book = contacts::AddressBook::load(&config)?;
+            match action {
+                ContactsCommands::Add {
+                    name,
+                    address,
+                    note,
+                    tags,
+                } => {
+                    let contact = book.add(&name, &address, note, &tags)?;
+                    let line = format!("✅ Saved @{}: {}", contact.name, contact.address);
+                    Report::new(contact)?.line(line)
+                }
+                ContactsCommands::List { tag } => {
+                    let tag = tag.map(|tag| tag.to_lowercase());
+                    contacts_report(
+                        book.contacts()
+                            .iter()
+                            .filter(|c| tag.as_ref().is_none_or(|tag| c.tags.contains(tag)))
+                            .collect(),
+                    )?
+                }
+                ContactsCommands::Remove { name } => {
+                    let contact = book.remove(&name)?;
+                    let line = format!("🗑️  Removed @{}: {}", contact.name, contact.address);
+                    Report::new(contact)?.line(line)
+                }
+            }
+        }
+
+        Some(Commands::Stake {
+            action: StakeCommands::List { pubkey, epochs },
+        }) => {
+            let target_pubkey = resolve_target(&config, pubkey).await?;
+            stake_accounts_report(stake::list_accounts(&config, &target_pubkey, epochs).await?)?
+        }
+        Some(Commands::Stake { action }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "change stake accounts")?;
+            let Some(operation) = action.operation() else {
+                unreachable!("listing is matched above")
+            };
+            stake_report(stake::execute(&config, &audit::cli_actor(), &operation).await?)?
+        }
+        Some(Commands::Cleanup {
+            dust_usd,
+            dust_action,
+            dry_run,
+        }) => {
+            if !dry_run {
+                ensure_mainnet_confirmed(&config, confirm_mainnet, "close token accounts")?;
+            }
+            cleanup_report(
+                cleanup::execute(&config, &audit::cli_actor(), dust_usd, dust_action, dry_run)
+                    .await?,
+            )?
+        }
+        Some(Commands::Wrap { amount }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "wrap SOL")?;
+            wrap_report(
+                wrap::execute(
+                    &config,
+                    &audit::cli_actor(),
+                    wrap::Direction::Wrap,
+                    Some(amount),
+                )
+                .await?,
+            )?
+        }
+        Some(Commands::Unwrap { amount }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "unwrap SOL")?;
+            wrap_report(
+                wrap::execute(
+                    &config,
+                    &audit::cli_actor(),
+                    wrap::Direction::Unwrap,
+                    amount,
+                )
+                .await?,
+            )?
+        }
+        Some(Commands::Token { action }) => {
+            ensure_mainnet_confirmed(&config, confirm_mainnet, "administer token mints")?;
+            mint_report(mint::execute(&config, &audit::cli_actor(), &action.operation()).await?)?
+        }
+        Some(Commands::Pay { action }) => {
+            let (created, status_request) = match action.request() {
+                Some(request) => {
+                    let created = pay::create_request(&config, &request).await?;
+                    let report = pay_request_report(&created)?;
+                    if !action.watch() {
+                        return Ok(report);
                     }
+                    // The payer needs the link now; stdout waits for the outcome
+                    report.eprint();
+                    let status_request = pay_status_request(&created);
+                    (Some(created), status_request)
+                }
+                None => (
+                    None,
+                    action.status().expect("pay status has a status request"),
+                ),
+            };
+
+            let status = match action.watch() {
+                true => pay::watch(&config, &status_request).await?,
+                false => pay::check(&config, &status_request).await?,
+            };
+            pay_outcome_report(created.as_ref(), status)?
+        }
+        Some(Commands::LiquidStake {
+            token,
+            amount,
+            quote_only,
+        }) => {
+            if !quote_only {
+                ensure_mainnet_confirmed(&config, confirm_mainnet, "liquid stake")?;
+            }
+            liquid_stake_report(
+                liquid_stake::stake(&config, &audit::cli_actor(), &token, amount, quote_only)
+                    .await?,
+            )?
+        }
+        Some(Commands::LiquidUnstake {
+            token,
+            amount,
+            mode,
+            quote_only,
+        }) => {
+            if !quote_only {
+                ensure_mainnet_confirmed(&config, confirm_mainnet, "liquid unstake")?;
+            }
+            liquid_stake_report(
+                liquid_stake::unstake(
+                    &config,
+                    &audit::cli_actor(),
+                    &token,
+                    amount,
+                    mode,
+                    quote_only,
+                )
+                .await?,
+            )?
+        }
 
-                    app_log!(info,);
+        // Streams until interrupted: one line, or one JSON document, per event
+        Some(Commands::Watch { pubkey, signature }) => {
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+            let watcher = if let Some(sig) = signature {
+                let signature = solana_sdk::signature::Signature::from_str(&sig).map_err(|_| {
+                    error::SolanaClientError::InvalidRequest {
+                        message: format!("Invalid signature: {}", sig),
+                    }
+                })?;
+                app_log!(info, "Watching signature {} (Ctrl+C to stop)", signature);
+                let config = config.clone();
+                tokio::spawn(
+                    async move { watch::watch_signature(&config, &signature, sender).await },
+                )
+            } else {
+                let target_pubkey = resolve_target(&config, pubkey).await?;
+                app_log!(info, "Watching {} (Ctrl+C to stop)", target_pubkey);
+                let config = config.clone();
+                tokio::spawn(async move {
+                    watch::watch_address(&config, &target_pubkey, sender, None).await
+                })
+            };
+
+            while let Some(event) = receiver.recv().await {
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string(&event)?),
+                    _ => println!("{}", format_watch_event(&event)),
                 }
             }
+
+            watcher.await??;
+            Report::empty()
+        }
+    };
+
+    Ok(report)
+}
+
+/// Remote mode: same commands, served by a REST server; signing stays local
+async fn run_remote(
+    config: &Config,
+    client: &solana_client::SolanaApiClient,
+    command: Option<Commands>,
+) -> Result<Report> {
+    let report = match command {
+        Some(Commands::Balance) => {
+            let keypair = wallet::load_keypair(config).await?;
+            balance_report(client.balance(&keypair.pubkey().to_string()).await?)?
+        }
+        Some(Commands::CreateTx { to, amount, memo }) => {
+            let keypair = wallet::load_keypair(config).await?;
+            let new_recipient = contacts::is_new_recipient(config, &to)?;
+            let prepared = client
+                .prepare_transaction(&api::PrepareTransactionRequest {
+                    payer_pubkey: keypair.pubkey().to_string(),
+                    to_address: to.clone(),
+                    amount,
+                    memo: memo.clone(),
+                })
+                .await?;
+
+            let tx_bytes = BASE64.decode(&prepared.unsigned_transaction)?;
+            let mut tx: solana_sdk::transaction::Transaction = bincode::deserialize(&tx_bytes)?;
+            tx.try_sign(&[&keypair], tx.message.recent_blockhash)?;
+
+            // Same encoding as the local create-tx, so send-tx accepts either
+            let tx_string = bs58::encode(bincode::serialize(&tx)?).into_string();
+            created_report(tx_string, &to, amount, memo.as_deref(), new_recipient)?
+        }
+        Some(Commands::SendTx { signature }) => {
+            let tx_bytes = bs58::decode(&signature).into_vec()?;
+            submitted_report(client.submit_transaction(&BASE64.encode(tx_bytes)).await?)?
         }
+        Some(Commands::Swap { from, to, amount }) => {
+            let keypair = wallet::load_keypair(config).await?;
+            let prepared = client
+                .prepare_swap(&api::PrepareSwapRequest {
+                    payer_pubkey: keypair.pubkey().to_string(),
+                    from_token: from.clone(),
+                    to_token: to.clone(),
+                    amount,
+                })
+                .await?;
 
+            let tx_bytes = BASE64.decode(&prepared.unsigned_transaction)?;
+            let unsigned: solana_sdk::transaction::VersionedTransaction =
+                bincode::deserialize(&tx_bytes)?;
+            let signed = solana_sdk::transaction::VersionedTransaction::try_new(
+                unsigned.message,
+                &[&keypair],
+            )?;
+
+            let submitted = client
+                .submit_transaction(&BASE64.encode(bincode::serialize(&signed)?))
+                .await?;
+            swap_report(jupiter::SwapResult {
+                signature: submitted.signature,
+                from_token: from.to_uppercase(),
+                to_token: to.to_uppercase(),
+                amount,
+                quote: prepared.quote_info,
+            })?
+        }
+        Some(Commands::Price { token }) => price_report(client.price(&token).await?)?,
+        Some(Commands::Search { query }) => search_report(client.search_tokens(&query).await?)?,
+        Some(Commands::ListTokens) => {
+            let keypair = wallet::load_keypair(config).await?;
+            wallet_tokens_report(client.wallet_tokens(&keypair.pubkey().to_string()).await?)?
+        }
+        Some(Commands::History {
+            limit,
+            before,
+            pubkey,
+            memo,
+        }) => {
+            let target_pubkey = resolve_target(config, pubkey).await?;
+            history_report(
+                client
+                    .transaction_history(&target_pubkey.to_string(), Some(limit), before, memo)
+                    .await?,
+            )?
+        }
         Some(Commands::Pending { pubkey }) => {
-            let target_pubkey = if let Some(pk) = pubkey {
-                solana_sdk::pubkey::Pubkey::from_str(&pk)?
-            } else {
-                let keypair = wallet::load_keypair(&config).await?;
-                keypair.pubkey()
+            let target_pubkey = resolve_target(config, pubkey).await?;
+            pending_report(
+                client
+                    .pending_transactions(&target_pubkey.to_string())
+                    .await?,
+            )?
+        }
+        Some(Commands::Stake {
+            action: StakeCommands::List { pubkey, epochs },
+        }) => {
+            let target_pubkey = resolve_target(config, pubkey).await?;
+            stake_accounts_report(
+                client
+                    .stake_accounts(&target_pubkey.to_string(), Some(epochs))
+                    .await?,
+            )?
+        }
+        Some(Commands::Stake { action }) => {
+            let Some(operation) = action.operation() else {
+                unreachable!("listing is matched above")
             };
+            let keypair = wallet::load_keypair(config).await?;
+            let prepared = client
+                .prepare_stake(&api::PrepareStakeRequest {
+                    payer_pubkey: keypair.pubkey().to_string(),
+                    operation: operation.clone(),
+                })
+                .await?;
 
-            let pending = transaction::fetch_pending_transactions(&config, &target_pubkey).await?;
+            let tx_bytes = BASE64.decode(&prepared.unsigned_transaction)?;
+            let mut tx: solana_sdk::transaction::Transaction = bincode::deserialize(&tx_bytes)?;
+            tx.try_sign(&[&keypair], tx.message.recent_blockhash)?;
 
-            if pending.is_empty() {
-                app_log!(info, "No pending transactions");
-            } else {
-                app_log!(info, "Pending Transactions for {}", target_pubkey);
-                app_log!(info, "{}", "=".repeat(50));
-
-                for (i, tx) in pending.iter().enumerate() {
-                    app_log!(
-                        info,
-                        "{}. {} | {} | {:?}",
-                        i + 1,
-                        &tx.signature[..8],
-                        format_tx_type(&tx.transaction_type),
-                        tx.status
-                    );
-
-                    if let Some(amount) = tx.amount {
-                        let symbol = tx.token_symbol.as_deref().unwrap_or("Unknown");
-                        app_log!(info, "   Amount: {} {}", amount, symbol);
+            let submitted = client
+                .submit_transaction(&BASE64.encode(bincode::serialize(&tx)?))
+                .await?;
+            stake_report(stake::StakeResult {
+                operation: stake::operation_name(&operation),
+                signature: submitted.signature,
+                stake_account: prepared.stake_account,
+            })?
+        }
+        Some(Commands::Cleanup {
+            dust_usd,
+            dust_action,
+            dry_run,
+        }) => {
+            let keypair = wallet::load_keypair(config).await?;
+            let prepared = client
+                .prepare_cleanup(&api::CleanupRequest {
+                    owner_pubkey: keypair.pubkey().to_string(),
+                    dust_usd,
+                    dust_action: Some(dust_action),
+                })
+                .await?;
+
+            let mut result = cleanup::CleanupResult {
+                owner: prepared.owner,
+                dry_run,
+                accounts: prepared.accounts,
+                reclaimable_sol: prepared.reclaimable_sol,
+                reclaimed_sol: 0.0,
+                signatures: Vec::new(),
+                failures: Vec::new(),
+            };
+            if !dry_run {
+                // Swaps come back versioned, closes legacy; both decode as versioned
+                for transaction in prepared.transactions {
+                    let tx_bytes = BASE64.decode(&transaction.unsigned_transaction)?;
+                    let unsigned: solana_sdk::transaction::VersionedTransaction =
+                        bincode::deserialize(&tx_bytes)?;
+                    let signed = solana_sdk::transaction::VersionedTransaction::try_new(
+                        unsigned.message,
+                        &[&keypair],
+                    )?;
+
+                    match client
+                        .submit_transaction(&BASE64.encode(bincode::serialize(&signed)?))
+                        .await
+                    {
+                        Ok(submitted) => {
+                            result.reclaimed_sol += result
+                                .accounts
+                                .iter()
+                                .filter(|a| {
+                                    a.action != api::CleanupAction::Swap
+                                        && transaction.accounts.contains(&a.address)
+                                })
+                                .map(|a| a.rent)
+                                .sum::<f64>();
+                            result.signatures.push(submitted.signature);
+                        }
+                        Err(e) => result.failures.push(format!(
+                            "{} account(s) starting at {}: {}",
+                            transaction.accounts.len(),
+                            transaction.accounts.first().cloned().unwrap_or_default(),
+                            e
+                        )),
                     }
+                }
+            }
+            cleanup_report(result)?
+        }
+        Some(Commands::Wrap { amount }) => {
+            remote_wrap(config, client, wrap::Direction::Wrap, Some(amount)).await?
+        }
+        Some(Commands::Unwrap { amount }) => {
+            remote_wrap(config, client, wrap::Direction::Unwrap, amount).await?
+        }
+        Some(Commands::Pay { action }) => {
+            let (created, status_request) = match action.request() {
+                Some(request) => {
+                    let created = client.payment_request(&request).await?;
+                    let report = pay_request_report(&created)?;
+                    if !action.watch() {
+                        return Ok(report);
+                    }
+                    // The payer needs the link now; stdout waits for the outcome
+                    report.eprint();
+                    let status_request = pay_status_request(&created);
+                    (Some(created), status_request)
+                }
+                None => (
+                    None,
+                    action.status().expect("pay status has a status request"),
+                ),
+            };
 
-                    app_log!(info, "   Status: Pending confirmation");
-                    app_log!(info,);
+            loop {
+                let status = client.payment_status(&status_request).await?;
+                if !action.watch() || status.status != api::PaymentStatus::Pending {
+                    break pay_outcome_report(created.as_ref(), status)?;
                 }
+                tokio::time::sleep(std::time::Duration::from_secs(
+                    config.pay.poll_interval_secs,
+                ))
+                .await;
             }
         }
+        Some(Commands::Token { action }) => {
+            let operation = action.operation();
+            let keypair = wallet::load_keypair(config).await?;
+            let prepared = client
+                .prepare_mint(&api::PrepareMintRequest {
+                    payer_pubkey: keypair.pubkey().to_string(),
+                    operation: operation.clone(),
+                })
+                .await?;
+
+            let tx_bytes = BASE64.decode(&prepared.unsigned_transaction)?;
+            let mut tx: solana_sdk::transaction::Transaction = bincode::deserialize(&tx_bytes)?;
+            tx.try_sign(&[&keypair], tx.message.recent_blockhash)?;
+
+            let submitted = client
+                .submit_transaction(&BASE64.encode(bincode::serialize(&tx)?))
+                .await?;
+            mint_report(mint::MintResult {
+                operation: mint::operation_name(&operation),
+                signature: submitted.signature,
+                mint: prepared.mint,
+            })?
+        }
+        _ => {
+            return Err(error::SolanaClientError::ConfigError {
+                message: "This command is not available in remote mode".to_string(),
+            }
+            .into());
+        }
+    };
+
+    Ok(report)
+}
+
+async fn remote_wrap(
+    config: &Config,
+    client: &solana_client::SolanaApiClient,
+    direction: wrap::Direction,
+    amount: Option<f64>,
+) -> Result<Report> {
+    let keypair = wallet::load_keypair(config).await?;
+    let request = api::PrepareWrapRequest {
+        owner_pubkey: keypair.pubkey().to_string(),
+        amount,
+    };
+    let prepared = match direction {
+        wrap::Direction::Wrap => client.prepare_wrap(&request).await?,
+        wrap::Direction::Unwrap => client.prepare_unwrap(&request).await?,
+    };
+
+    let tx_bytes = BASE64.decode(&prepared.unsigned_transaction)?;
+    let mut tx: solana_sdk::transaction::Transaction = bincode::deserialize(&tx_bytes)?;
+    tx.try_sign(&[&keypair], tx.message.recent_blockhash)?;
+
+    let submitted = client
+        .submit_transaction(&BASE64.encode(bincode::serialize(&tx)?))
+        .await?;
+    wrap_report(wrap::WrapResult {
+        operation: direction.name(),
+        amount: prepared.amount,
+        account: prepared.account,
+        closed: prepared.closed,
+        signature: submitted.signature,
+    })
+}
+
+async fn resolve_target(
+    config: &Config,
+    pubkey: Option<String>,
+) -> Result<solana_sdk::pubkey::Pubkey> {
+    match pubkey {
+        Some(pk) => Ok(solana_sdk::pubkey::Pubkey::from_str(&pk)
+            .map_err(|_| error::SolanaClientError::InvalidAddress { address: pk })?),
+        None => Ok(wallet::load_keypair(config).await?.pubkey()),
     }
+}
 
-    Ok(())
+// ── Reports ───────────────────────────────────────────────────────────────────
+// Local and remote mode build the same documents, shaped like the REST responses,
+// so `--output json` does not depend on where a command ran.
+
+fn balance_report(balance: api::BalanceResponse) -> Result<Report> {
+    let line = format!("Balance: {} {}", balance.balance, balance.token);
+    Ok(Report::new(balance)?.line(line))
+}
+
+fn created_report(
+    transaction: String,
+    to: &str,
+    amount: f64,
+    memo: Option<&str>,
+    new_recipient: bool, // Not a contact and never sent to before
+) -> Result<Report> {
+    let report = Report::new(json!({
+        "transaction": transaction,
+        "to": to,
+        "amount": amount,
+        "memo": memo,
+        "new_recipient": new_recipient,
+    }))?;
+    let report = match new_recipient {
+        true => report.line(format!(
+            "⚠️  First transfer to {}: not a contact and never sent to before",
+            to
+        )),
+        false => report,
+    };
+    Ok(report.line(format!("Transaction created: {}", transaction)))
+}
+
+fn submitted_report(submitted: api::SubmitResponse) -> Result<Report> {
+    let line = format!("✅ Transaction sent: {}", submitted.signature);
+    Ok(Report::new(submitted)?.line(line))
+}
+
+fn swap_report(swap: jupiter::SwapResult) -> Result<Report> {
+    let lines = [
+        format!(
+            "📊 Expected output: {:.6} {} (price impact {:.4}%, {} route steps)",
+            swap.quote.expected_output,
+            swap.to_token,
+            swap.quote.price_impact,
+            swap.quote.route_steps
+        ),
+        format!("✅ Swap completed: {}", swap.signature),
+    ];
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(swap)?, |report, line| report.line(line)))
+}
+
+fn price_report(price: api::PriceResponse) -> Result<Report> {
+    let line = format!("Price for {}: ${}", price.token, price.price);
+    Ok(Report::new(price)?.line(line))
+}
+
+fn search_report(result: api::TokenSearchResponse) -> Result<Report> {
+    let rows = result
+        .tokens
+        .iter()
+        .map(|t| vec![t.symbol.clone(), t.name.clone(), t.address.clone()])
+        .collect::<Vec<_>>();
+    let lines: Vec<String> = result
+        .tokens
+        .iter()
+        .map(|t| format!("{}: {} ({})", t.symbol, t.name, t.address))
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(result)?.table(&["SYMBOL", "NAME", "ADDRESS"], rows),
+        |report, line| report.line(line),
+    ))
+}
+
+fn wallet_tokens_report(result: api::WalletTokensResponse) -> Result<Report> {
+    let mut lines = Vec::new();
+    let mut rows = Vec::new();
+
+    if result.tokens.is_empty() {
+        lines.push("💸 No tokens found in wallet".to_string());
+    }
+    for (i, token) in result.tokens.iter().enumerate() {
+        lines.push(format!(
+            "{}. {} ({}) - {} tokens",
+            i + 1,
+            token.symbol,
+            token.name,
+            wallet::format_balance(token.balance)
+        ));
+        if let Some(usd_value) = token.usd_value {
+            lines.push(format!("   💲 ~${:.2}", usd_value));
+        }
+        lines.push(format!("   📍 {}", token.mint));
+
+        rows.push(vec![
+            token.symbol.clone(),
+            wallet::format_balance(token.balance),
+            token
+                .usd_value
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_else(|| "-".to_string()),
+            token.mint.clone(),
+        ]);
+    }
+
+    Ok(lines.into_iter().fold(
+        Report::new(result)?.table(&["SYMBOL", "BALANCE", "USD", "MINT"], rows),
+        |report, line| report.line(line),
+    ))
+}
+
+fn audit_report(entries: &[audit::AuditEntry]) -> Result<Report> {
+    let mut lines = Vec::new();
+    if entries.is_empty() {
+        lines.push("No audit entries found".to_string());
+    }
+
+    for entry in entries {
+        lines.push(format!(
+            "#{} {} | {} | {} | {} | {}",
+            entry.seq,
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            entry.actor,
+            entry.action,
+            entry.status,
+            entry.signature.as_deref().unwrap_or("-")
+        ));
+        lines.push(format!("   Intent: {}", entry.intent));
+        lines.push(format!("   Policy: {:?}", entry.policy));
+        for violation in &entry.violations {
+            lines.push(format!("   Violation: {}", violation));
+        }
+        if let Some(error) = &entry.error {
+            lines.push(format!("   Error: {}", error));
+        }
+    }
+
+    let rows = entries
+        .iter()
+        .map(|e| {
+            vec![
+                e.seq.to_string(),
+                e.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                e.actor.clone(),
+                e.action.clone(),
+                e.status.clone(),
+                e.signature.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(entries)?.table(
+            &["SEQ", "TIME", "ACTOR", "ACTION", "STATUS", "SIGNATURE"],
+            rows,
+        ),
+        |report, line| report.line(line),
+    ))
+}
+
+fn contacts_report(contacts: Vec<&contacts::Contact>) -> Result<Report> {
+    let mut lines = Vec::new();
+    if contacts.is_empty() {
+        lines.push("No contacts found".to_string());
+    }
+
+    for contact in &contacts {
+        lines.push(format!("@{} | {}", contact.name, contact.address));
+        if !contact.tags.is_empty() {
+            lines.push(format!("   Tags: {}", contact.tags.join(", ")));
+        }
+        if let Some(note) = &contact.note {
+            lines.push(format!("   Note: {}", note));
+        }
+    }
+
+    let rows = contacts
+        .iter()
+        .map(|c| {
+            vec![
+                format!("@{}", c.name),
+                c.address.clone(),
+                c.tags.join(","),
+                c.note.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(&contacts)?.table(&["NAME", "ADDRESS", "TAGS", "NOTE"], rows),
+        |report, line| report.line(line),
+    ))
+}
+
+fn stake_report(result: stake::StakeResult) -> Result<Report> {
+    let lines = [
+        format!(
+            "✅ Stake {} confirmed: {}",
+            result.operation, result.signature
+        ),
+        format!("📍 Stake account: {}", result.stake_account),
+    ];
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(result)?, |report, line| report.line(line)))
+}
+
+fn cleanup_report(result: cleanup::CleanupResult) -> Result<Report> {
+    let mut lines = Vec::new();
+
+    if result.accounts.is_empty() {
+        lines.push(format!(
+            "No token accounts to clean up for {}",
+            result.owner
+        ));
+    } else {
+        lines.push(format!(
+            "{} token account(s) for {}, {} SOL of rent reclaimable",
+            result.accounts.len(),
+            result.owner,
+            result.reclaimable_sol
+        ));
+        lines.push("=".repeat(80));
+    }
+    for account in &result.accounts {
+        lines.push(format!(
+            "{} | {} | {} | {:?}{}",
+            account.address,
+            account.mint,
+            account.balance,
+            account.action,
+            account
+                .note
+                .as_ref()
+                .map(|n| format!(" ({})", n))
+                .unwrap_or_default()
+        ));
+    }
+
+    if result.dry_run {
+        lines.push("Dry run: nothing was sent".to_string());
+    } else if !result.accounts.is_empty() {
+        for signature in &result.signatures {
+            lines.push(format!("✅ Confirmed: {}", signature));
+        }
+        for failure in &result.failures {
+            lines.push(format!("❌ Failed: {}", failure));
+        }
+        lines.push(format!("💰 Reclaimed {} SOL", result.reclaimed_sol));
+    }
+
+    let rows = result
+        .accounts
+        .iter()
+        .map(|a| {
+            vec![
+                a.address.clone(),
+                a.mint.clone(),
+                a.balance.to_string(),
+                a.usd_value
+                    .map(|v| format!("{:.4}", v))
+                    .unwrap_or_else(|| "-".to_string()),
+                format!("{:?}", a.action).to_lowercase(),
+                a.rent.to_string(),
+                a.note.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(result)?.table(
+            &[
+                "ACCOUNT", "MINT", "BALANCE", "USD", "ACTION", "RENT", "NOTE",
+            ],
+            rows,
+        ),
+        |report, line| report.line(line),
+    ))
+}
+
+fn wrap_report(result: wrap::WrapResult) -> Result<Report> {
+    let mut lines = vec![match result.operation {
+        "wrap" => format!("✅ Wrapped {} SOL into {}", result.amount, result.account),
+        _ => format!("✅ Unwrapped {} SOL from {}", result.amount, result.account),
+    }];
+    if result.closed {
+        lines.push("   Wrapped SOL account closed, rent returned".to_string());
+    }
+    lines.push(format!("   Signature: {}", result.signature));
+
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(result)?, |report, line| report.line(line)))
+}
+
+fn pay_status_request(created: &api::PayRequestResponse) -> api::PayStatusRequest {
+    api::PayStatusRequest {
+        reference: created.reference.clone(),
+        recipient: created.recipient.clone(),
+        amount: created.amount,
+        spl_token: created.spl_token.clone(),
+        expires_at: Some(created.expires_at),
+    }
+}
+
+fn pay_request_report(created: &api::PayRequestResponse) -> Result<Report> {
+    let mut lines = vec![
+        format!("🧾 {}", created.url),
+        String::new(),
+        pay::render_qr(&created.url)?,
+        format!("🔑 Reference: {}", created.reference),
+    ];
+    if let Some(expires) = chrono::DateTime::from_timestamp(created.expires_at, 0) {
+        lines.push(format!("⏳ Expires: {}", expires.to_rfc3339()));
+    }
+
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(created)?, |report, line| report.line(line)))
+}
+
+/// A status, or with `pay --watch` the request it followed and the status it
+/// ended in as one document
+fn pay_outcome_report(
+    created: Option<&api::PayRequestResponse>,
+    status: api::PayStatusResponse,
+) -> Result<Report> {
+    let mut lines = vec![match status.status {
+        api::PaymentStatus::Pending => format!("⏳ No payment yet for {}", status.reference),
+        api::PaymentStatus::Paid => format!("✅ Paid: {}", status.reference),
+        api::PaymentStatus::Underpaid => format!(
+            "⚠️  Underpaid: received {} of {}",
+            status.received.unwrap_or(0.0),
+            status.expected.unwrap_or(0.0)
+        ),
+        api::PaymentStatus::Expired => format!("❌ Expired unpaid: {}", status.reference),
+    }];
+    if let Some(signature) = &status.signature {
+        lines.push(format!("   Signature: {}", signature));
+    }
+
+    let data = match created {
+        Some(created) => json!({ "request": created, "status": status }),
+        None => serde_json::to_value(status)?,
+    };
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(data)?, |report, line| report.line(line)))
+}
+
+fn mint_report(result: mint::MintResult) -> Result<Report> {
+    let lines = [
+        format!(
+            "✅ Token {} confirmed: {}",
+            result.operation, result.signature
+        ),
+        format!("🪙 Mint: {}", result.mint),
+    ];
+    Ok(lines
+        .into_iter()
+        .fold(Report::new(result)?, |report, line| report.line(line)))
+}
+
+fn liquid_stake_report(result: liquid_stake::LiquidStakeResult) -> Result<Report> {
+    let (input, output) = match result.direction {
+        "stake" => ("SOL".to_string(), result.token.clone()),
+        _ => (result.token.clone(), "SOL".to_string()),
+    };
+    let mut lines = vec![format!(
+        "Liquid {} of {} {} via pool {}",
+        result.direction, result.amount, input, result.pool
+    )];
+    for route in &result.routes {
+        lines.push(format!(
+            "   {}: {}{}",
+            route.route,
+            route
+                .output
+                .map(|o| format!("{} {}", o, output))
+                .unwrap_or_else(|| "unavailable".to_string()),
+            route
+                .note
+                .as_ref()
+                .map(|n| format!(" ({})", n))
+                .unwrap_or_default()
+        ));
+    }
+
+    match (&result.chosen, &result.signature) {
+        (Some(route), Some(signature)) => {
+            lines.push(format!("✅ Confirmed via {}: {}", route, signature))
+        }
+        (Some(route), None) => lines.push(format!("Best route: {}", route)),
+        (None, _) => lines.push("No route available".to_string()),
+    }
+    if let Some(stake_account) = &result.stake_account {
+        lines.push(format!(
+            "📍 Stake account: {} (deactivating; withdraw with `stake withdraw` once inactive)",
+            stake_account
+        ));
+    }
+
+    let rows = result
+        .routes
+        .iter()
+        .map(|r| {
+            vec![
+                r.route.to_string(),
+                r.output
+                    .map(|o| o.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                if result.chosen == Some(r.route) {
+                    "*"
+                } else {
+                    ""
+                }
+                .to_string(),
+                r.note.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(result)?.table(&["ROUTE", "OUTPUT", "CHOSEN", "NOTE"], rows),
+        |report, line| report.line(line),
+    ))
+}
+
+fn stake_accounts_report(result: api::StakeAccountsResponse) -> Result<Report> {
+    let mut lines = Vec::new();
+
+    if result.accounts.is_empty() {
+        lines.push(format!("No stake accounts found for {}", result.pubkey));
+    } else {
+        lines.push(format!(
+            "Stake accounts for {} (epoch {})",
+            result.pubkey, result.epoch
+        ));
+        lines.push("=".repeat(80));
+    }
+
+    for (i, account) in result.accounts.iter().enumerate() {
+        lines.push(format!(
+            "{}. {} | {} | {} SOL",
+            i + 1,
+            account.address,
+            account.state,
+            account.balance
+        ));
+        if let Some(vote_account) = &account.vote_account {
+            lines.push(format!(
+                "   Delegated to {}: {} of {} SOL active",
+                vote_account,
+                account.active_stake.unwrap_or_default(),
+                account.delegated_stake.unwrap_or_default()
+            ));
+        }
+        for reward in &account.rewards {
+            lines.push(format!(
+                "   Epoch {}: +{} SOL{}",
+                reward.epoch,
+                reward.amount,
+                reward
+                    .commission
+                    .map(|c| format!(" ({}% commission)", c))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    let rows = result
+        .accounts
+        .iter()
+        .map(|a| {
+            vec![
+                a.address.clone(),
+                a.state.clone(),
+                a.balance.to_string(),
+                a.active_stake
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                a.vote_account.clone().unwrap_or_else(|| "-".to_string()),
+                a.rewards
+                    .first()
+                    .map(|r| format!("{} (epoch {})", r.amount, r.epoch))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    Ok(lines.into_iter().fold(
+        Report::new(result)?.table(
+            &[
+                "ADDRESS",
+                "STATE",
+                "BALANCE",
+                "ACTIVE",
+                "VOTE ACCOUNT",
+                "LAST REWARD",
+            ],
+            rows,
+        ),
+        |report, line| report.line(line),
+    ))
+}
+
+fn history_report(history: api::TransactionHistoryResponse) -> Result<Report> {
+    let mut lines = Vec::new();
+
+    if history.transactions.is_empty() {
+        lines.push("No transactions found".to_string());
+    } else {
+        lines.push(format!("Transaction History for {}", history.pubkey));
+        lines.push("=".repeat(80));
+
+        for (i, tx) in history.transactions.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} | {} | {:?}",
+                i + 1,
+                &tx.signature[..8],
+                format_tx_type(&tx.transaction_type),
+                tx.status
+            ));
+
+            if let Some(amount) = tx.amount {
+                let symbol = tx.token_symbol.as_deref().unwrap_or("Unknown");
+                lines.push(format!("   Amount: {} {}", amount, symbol));
+            }
+
+            if let Some(fee) = tx.fee {
+                lines.push(format!("   Fee: {} SOL", fee));
+            }
+
+            if let Some(block_time) = tx.block_time {
+                lines.push(format!("   Time: {}", format_block_time(block_time)));
+            }
+
+            lines.push(format!("   Status: {:?}", tx.confirmation_status));
+
+            if let Some(memo) = &tx.memo {
+                lines.push(format!("   Memo: {}", memo));
+            }
+
+            if let Some(error) = &tx.error {
+                lines.push(format!("   Error: {}", error));
+            }
+
+            lines.push(String::new());
+        }
+    }
+
+    let rows = history.transactions.iter().map(transaction_row).collect();
+    Ok(lines.into_iter().fold(
+        Report::new(history)?.table(TRANSACTION_HEADERS, rows),
+        |report, line| report.line(line),
+    ))
+}
+
+fn pending_report(pending: api::PendingTransactionsResponse) -> Result<Report> {
+    let mut lines = Vec::new();
+
+    if pending.pending_transactions.is_empty() {
+        lines.push("No pending transactions".to_string());
+    } else {
+        lines.push(format!("Pending Transactions for {}", pending.pubkey));
+        lines.push("=".repeat(50));
+
+        for (i, tx) in pending.pending_transactions.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} | {} | {:?}",
+                i + 1,
+                &tx.signature[..8],
+                format_tx_type(&tx.transaction_type),
+                tx.status
+            ));
+
+            if let Some(amount) = tx.amount {
+                let symbol = tx.token_symbol.as_deref().unwrap_or("Unknown");
+                lines.push(format!("   Amount: {} {}", amount, symbol));
+            }
+
+            lines.push("   Status: Pending confirmation".to_string());
+            lines.push(String::new());
+        }
+    }
+
+    let rows = pending
+        .pending_transactions
+        .iter()
+        .map(transaction_row)
+        .collect();
+    Ok(lines.into_iter().fold(
+        Report::new(pending)?.table(TRANSACTION_HEADERS, rows),
+        |report, line| report.line(line),
+    ))
+}
+
+const TRANSACTION_HEADERS: &[&str] = &[
+    "SIGNATURE",
+    "TYPE",
+    "AMOUNT",
+    "FEE",
+    "TIME",
+    "STATUS",
+    "MEMO",
+];
+
+fn transaction_row(tx: &transaction::TransactionHistory) -> Vec<String> {
+    vec![
+        tx.signature.clone(),
+        format_tx_type(&tx.transaction_type).to_string(),
+        tx.amount
+            .map(|amount| {
+                format!(
+                    "{} {}",
+                    amount,
+                    tx.token_symbol.as_deref().unwrap_or("Unknown")
+                )
+            })
+            .unwrap_or_else(|| "-".to_string()),
+        tx.fee
+            .map(|fee| fee.to_string())
+            .unwrap_or_else(|| "-".to_string()),
+        tx.block_time
+            .map(format_block_time)
+            .unwrap_or_else(|| "-".to_string()),
+        match &tx.error {
+            Some(_) => "Failed".to_string(),
+            None => format!("{:?}", tx.confirmation_status),
+        },
+        tx.memo.clone().unwrap_or_else(|| "-".to_string()),
+    ]
+}
+
+fn format_block_time(block_time: i64) -> String {
+    chrono::DateTime::from_timestamp(block_time, 0)
+        .unwrap_or_else(chrono::Utc::now)
+        .format("%Y-%m-%d %H:%M:%S UTC")
+        .to_string()
+}
+
+fn issue_line(issue: &validation::Issue) -> String {
+    let marker = match issue.severity {
+        validation::Severity::Error => "❌",
+        validation::Severity::Warning => "⚠️ ",
+    };
+    format!("{} {}: {}", marker, issue.key, issue.message)
+}
+
+fn format_watch_event(event: &watch::WatchEvent) -> String {
+    match event {
+        watch::WatchEvent::SignatureStatus {
+            signature,
+            commitment,
+            slot,
+            error,
+        } => match error {
+            Some(error) => format!("❌ {} failed at slot {}: {}", signature, slot, error),
+            None => format!("✅ {} {} at slot {}", signature, commitment, slot),
+        },
+        watch::WatchEvent::SignatureExpired { signature, reached } => match reached {
+            Some(commitment) => format!("⌛ {} stopped at {}", signature, commitment),
+            None => format!("⌛ {} was never seen; it has likely expired", signature),
+        },
+        watch::WatchEvent::Transaction {
+            signature,
+            slot,
+            error,
+            ..
+        } => match error {
+            Some(error) => format!("📜 {} (slot {}) failed: {}", signature, slot, error),
+            None => format!("📜 {} (slot {})", signature, slot),
+        },
+        watch::WatchEvent::BalanceChange { balance, slot, .. } => {
+            format!("💰 Balance: {} SOL (slot {})", balance, slot)
+        }
+    }
 }
 
 fn format_tx_type(tx_type: &transaction::TransactionType) -> &'static str {
@@ -0,0 +1,153 @@
+//! The one Metaplex Token Metadata instruction `mint.rs` needs,
+//! CreateMetadataAccountV3, encoded by hand.
+
+use crate::api::TokenMetadata;
+use crate::error::SolanaClientError;
+use anyhow::Result;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+const MAX_NAME_LEN: usize = 32;
+const MAX_SYMBOL_LEN: usize = 10;
+const MAX_URI_LEN: usize = 200;
+
+/// Metadata account of `mint`
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Mutable metadata with no creators, collection or royalties; `authority`
+/// is the mint authority, payer and update authority
+pub fn create_metadata(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    metadata: &TokenMetadata,
+) -> Result<Instruction> {
+    for (field, value, max) in [
+        ("name", &metadata.name, MAX_NAME_LEN),
+        ("symbol", &metadata.symbol, MAX_SYMBOL_LEN),
+        ("uri", &metadata.uri, MAX_URI_LEN),
+    ] {
+        if value.len() > max {
+            return Err(SolanaClientError::InvalidRequest {
+                message: format!("Metadata {} is longer than {} bytes", field, max),
+            }
+            .into());
+        }
+    }
+
+    // Borsh: DataV2, then is_mutable and collection_details
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    for value in [&metadata.name, &metadata.symbol, &metadata.uri] {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+    data.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+    data.extend_from_slice(&[0, 0, 0]); // creators, collection, uses: None
+    data.push(1); // is_mutable
+    data.push(0); // collection_details: None
+
+    Ok(Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(metadata_address(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(name: &str, symbol: &str, uri: &str) -> TokenMetadata {
+        TokenMetadata {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+        }
+    }
+
+    #[test]
+    fn encodes_create_metadata_account_v3() {
+        let (mint, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = create_metadata(
+            &mint,
+            &authority,
+            &metadata("Coin", "CN", "https://x.io/c.json"),
+        )
+        .unwrap();
+
+        let mut expected = vec![33];
+        expected.extend_from_slice(&[4, 0, 0, 0]);
+        expected.extend_from_slice(b"Coin");
+        expected.extend_from_slice(&[2, 0, 0, 0]);
+        expected.extend_from_slice(b"CN");
+        expected.extend_from_slice(&[19, 0, 0, 0]);
+        expected.extend_from_slice(b"https://x.io/c.json");
+        expected.extend_from_slice(&[
+            0, 0, // seller_fee_basis_points
+            0, // creators
+            0, // collection
+            0, // uses
+            1, // is_mutable
+            0, // collection_details
+        ]);
+        assert_eq!(ix.data, expected);
+        assert_eq!(ix.program_id, TOKEN_METADATA_PROGRAM_ID);
+
+        let metas: Vec<(Pubkey, bool, bool)> = ix
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_writable, meta.is_signer))
+            .collect();
+        assert_eq!(
+            metas,
+            vec![
+                (metadata_address(&mint), true, false),
+                (mint, false, false),
+                (authority, false, true), // Mint authority
+                (authority, true, true),  // Payer
+                (authority, false, true), // Update authority
+                (solana_system_interface::program::ID, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_fields_over_the_limits() {
+        let (mint, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for metadata in [
+            metadata(&"n".repeat(MAX_NAME_LEN + 1), "CN", ""),
+            metadata("Coin", &"s".repeat(MAX_SYMBOL_LEN + 1), ""),
+            metadata("Coin", "CN", &"u".repeat(MAX_URI_LEN + 1)),
+        ] {
+            assert!(create_metadata(&mint, &authority, &metadata).is_err());
+        }
+        let longest = metadata(
+            &"n".repeat(MAX_NAME_LEN),
+            &"s".repeat(MAX_SYMBOL_LEN),
+            &"u".repeat(MAX_URI_LEN),
+        );
+        assert!(create_metadata(&mint, &authority, &longest).is_ok());
+    }
+}
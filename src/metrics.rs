@@ -0,0 +1,285 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+pub const HTTP_REQUESTS: &str = "solanize_http_requests_total";
+pub const HTTP_DURATION: &str = "solanize_http_request_duration_seconds";
+pub const RPC_DURATION: &str = "solanize_rpc_request_duration_seconds";
+pub const RPC_FAILURES: &str = "solanize_rpc_failures_total";
+pub const JUPITER_DURATION: &str = "solanize_jupiter_request_duration_seconds";
+pub const JUPITER_FAILURES: &str = "solanize_jupiter_failures_total";
+pub const TRANSACTIONS_SUBMITTED: &str = "solanize_transactions_submitted_total";
+pub const TRANSACTIONS_CONFIRMED: &str = "solanize_transactions_confirmed_total";
+pub const TOKEN_CACHE_HITS: &str = "solanize_token_list_cache_hits_total";
+pub const TOKEN_CACHE_MISSES: &str = "solanize_token_list_cache_misses_total";
+const TOKEN_CACHE_HIT_RATIO: &str = "solanize_token_list_cache_hit_ratio";
+
+// (name, type, help) in exposition order
+const METRICS: &[(&str, &str, &str)] = &[
+    (
+        HTTP_REQUESTS,
+        "counter",
+        "REST requests by route and status",
+    ),
+    (HTTP_DURATION, "histogram", "REST request latency by route"),
+    (
+        RPC_DURATION,
+        "histogram",
+        "Solana RPC call latency by method",
+    ),
+    (RPC_FAILURES, "counter", "Failed Solana RPC calls by method"),
+    (
+        JUPITER_DURATION,
+        "histogram",
+        "Jupiter API latency by endpoint",
+    ),
+    (
+        JUPITER_FAILURES,
+        "counter",
+        "Failed Jupiter API calls by endpoint",
+    ),
+    (
+        TRANSACTIONS_SUBMITTED,
+        "counter",
+        "Transactions accepted by the RPC node",
+    ),
+    (
+        TRANSACTIONS_CONFIRMED,
+        "counter",
+        "Submitted transactions that reached confirmation",
+    ),
+    (
+        TOKEN_CACHE_HITS,
+        "counter",
+        "Token list lookups served from cache",
+    ),
+    (
+        TOKEN_CACHE_MISSES,
+        "counter",
+        "Token list lookups that fetched from Jupiter",
+    ),
+];
+
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+/// Process-wide counters and histograms, keyed by metric name and rendered label set
+struct Registry {
+    counters: BTreeMap<(&'static str, String), u64>,
+    histograms: BTreeMap<(&'static str, String), Histogram>,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(|| {
+    // Unlabelled counters start at zero so they are scraped before the first event
+    let counters = [
+        TRANSACTIONS_SUBMITTED,
+        TRANSACTIONS_CONFIRMED,
+        TOKEN_CACHE_HITS,
+        TOKEN_CACHE_MISSES,
+    ]
+    .into_iter()
+    .map(|name| ((name, String::new()), 0))
+    .collect();
+
+    Mutex::new(Registry {
+        counters,
+        histograms: BTreeMap::new(),
+    })
+});
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}=\"{}\"",
+                key,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn inc(name: &'static str, pairs: &[(&str, &str)]) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry.counters.entry((name, labels(pairs))).or_default() += 1;
+}
+
+pub fn observe(name: &'static str, pairs: &[(&str, &str)], elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    let mut registry = REGISTRY.lock().unwrap();
+    let histogram = registry
+        .histograms
+        .entry((name, labels(pairs)))
+        .or_default();
+
+    histogram.count += 1;
+    histogram.sum += seconds;
+    if let Some(i) = BUCKETS.iter().position(|le| seconds <= *le) {
+        histogram.buckets[i] += 1;
+    }
+}
+
+pub fn record_rpc(method: &str, elapsed: Duration, ok: bool) {
+    observe(RPC_DURATION, &[("method", method)], elapsed);
+    if !ok {
+        inc(RPC_FAILURES, &[("method", method)]);
+    }
+}
+
+/// Time a Jupiter API call; errors count as failures and are passed through
+pub async fn time_jupiter<T>(endpoint: &str, call: impl Future<Output = Result<T>>) -> Result<T> {
+    let started = Instant::now();
+    let result = call.await;
+
+    observe(
+        JUPITER_DURATION,
+        &[("endpoint", endpoint)],
+        started.elapsed(),
+    );
+    if result.is_err() {
+        inc(JUPITER_FAILURES, &[("endpoint", endpoint)]);
+    }
+
+    result
+}
+
+pub fn transaction_confirmed() {
+    inc(TRANSACTIONS_CONFIRMED, &[]);
+}
+
+/// Prometheus text exposition format
+pub fn render() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+
+    for (name, kind, help) in METRICS {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+
+        for ((_, labels), value) in registry.counters.iter().filter(|((n, _), _)| n == name) {
+            let _ = writeln!(out, "{} {}", series(name, labels, None), value);
+        }
+
+        for ((_, labels), histogram) in registry.histograms.iter().filter(|((n, _), _)| n == name) {
+            let bucket = format!("{}_bucket", name);
+            let mut cumulative = 0;
+            for (le, count) in BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{} {}",
+                    series(&bucket, labels, Some(&le.to_string())),
+                    cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{} {}",
+                series(&bucket, labels, Some("+Inf")),
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{} {}",
+                series(&format!("{}_sum", name), labels, None),
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "{} {}",
+                series(&format!("{}_count", name), labels, None),
+                histogram.count
+            );
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP {} Share of token list lookups served from cache",
+        TOKEN_CACHE_HIT_RATIO
+    );
+    let _ = writeln!(out, "# TYPE {} gauge", TOKEN_CACHE_HIT_RATIO);
+    let _ = writeln!(
+        out,
+        "{} {}",
+        TOKEN_CACHE_HIT_RATIO,
+        cache_hit_ratio(&registry)
+    );
+
+    out
+}
+
+/// One line per series, for printing after a CLI command
+pub fn summary() -> Vec<String> {
+    let registry = REGISTRY.lock().unwrap();
+    let mut lines = Vec::new();
+
+    for ((name, labels), histogram) in &registry.histograms {
+        lines.push(format!(
+            "{}: {} calls, avg {:.1}ms",
+            series(name, labels, None),
+            histogram.count,
+            histogram.sum * 1000.0 / histogram.count as f64
+        ));
+    }
+
+    for ((name, labels), value) in registry.counters.iter().filter(|(_, v)| **v > 0) {
+        lines.push(format!("{}: {}", series(name, labels, None), value));
+    }
+
+    if cache_lookups(&registry) > 0 {
+        lines.push(format!(
+            "{}: {:.2}",
+            TOKEN_CACHE_HIT_RATIO,
+            cache_hit_ratio(&registry)
+        ));
+    }
+
+    lines
+}
+
+fn series(name: &str, labels: &str, le: Option<&str>) -> String {
+    let le = le.map(|le| format!("le=\"{}\"", le));
+    let all: Vec<&str> = [Some(labels), le.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    if all.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{{{}}}", name, all.join(","))
+    }
+}
+
+fn counter(registry: &Registry, name: &'static str) -> u64 {
+    registry
+        .counters
+        .get(&(name, String::new()))
+        .copied()
+        .unwrap_or(0)
+}
+
+fn cache_lookups(registry: &Registry) -> u64 {
+    counter(registry, TOKEN_CACHE_HITS) + counter(registry, TOKEN_CACHE_MISSES)
+}
+
+fn cache_hit_ratio(registry: &Registry) -> f64 {
+    match cache_lookups(registry) {
+        0 => 0.0,
+        lookups => counter(registry, TOKEN_CACHE_HITS) as f64 / lookups as f64,
+    }
+}
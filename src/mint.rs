@@ -0,0 +1,512 @@
+use crate::api::{MintAuthority, MintExtension, MintOperation, PrepareMintResponse, TokenMetadata};
+use crate::app_log;
+use crate::audit::Audit;
+use crate::metaplex;
+use crate::policy::{Intent, Spend};
+use crate::{config::Config, error::SolanaClientError, rpc, signing, transaction, wallet};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::{MAX_SEED_LEN, Pubkey},
+    signature::{Keypair, Signer},
+};
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::extension::{
+    ExtensionType, StateWithExtensions, default_account_state, interest_bearing_mint, transfer_fee,
+};
+use spl_token_2022::instruction::{self as token_instruction, AuthorityType};
+use spl_token_2022::state::{AccountState, Mint};
+use std::str::FromStr;
+
+/// A mint operation signed by the local wallet and confirmed
+#[derive(Debug, Serialize)]
+pub struct MintResult {
+    pub operation: &'static str,
+    pub signature: String,
+    pub mint: String,
+}
+
+// The instructions for one operation, and what the policy should see of them
+struct Plan {
+    instructions: Vec<Instruction>,
+    mint: Pubkey,
+    intent: Intent,
+    new_account_signs: bool, // A fresh keypair owns the new mint's address
+}
+
+// What an existing mint's account says about it
+struct MintInfo {
+    program_id: Pubkey,
+    decimals: u8,
+}
+
+pub fn operation_name(operation: &MintOperation) -> &'static str {
+    match operation {
+        MintOperation::CreateMint { .. } => "create_mint",
+        MintOperation::MintTo { .. } => "mint_to",
+        MintOperation::Burn { .. } => "burn",
+        MintOperation::Freeze { .. } => "freeze",
+        MintOperation::Thaw { .. } => "thaw",
+        MintOperation::SetAuthority { .. } => "set_authority",
+        MintOperation::CreateMetadata { .. } => "create_metadata",
+    }
+}
+
+/// Sign `operation` with the wallet and send it. Without a seed, create_mint
+/// puts the mint at a throwaway keypair's address.
+pub async fn execute(
+    config: &Config,
+    actor: &str,
+    operation: &MintOperation,
+) -> Result<MintResult> {
+    let mut audit = Audit::new(actor, "mint", json!(operation));
+    let result: Result<MintResult> = async {
+        let keypair = wallet::load_keypair(config).await?;
+        let new_account = Keypair::new();
+        let client = rpc::client(config);
+
+        let plan = plan(&client, &keypair.pubkey(), operation, &new_account)?;
+        audit.check_policy(config, &plan.intent)?;
+
+        app_log!(info, "Token {}: {}", operation_name(operation), plan.mint);
+
+        let extra_signers: &[&Keypair] = match plan.new_account_signs {
+            true => &[&new_account],
+            false => &[],
+        };
+        let signature = transaction::sign_and_send(
+            config,
+            &mut audit,
+            &keypair,
+            extra_signers,
+            &plan.instructions,
+        )?;
+
+        Ok(MintResult {
+            operation: operation_name(operation),
+            signature: signature.to_string(),
+            mint: plan.mint.to_string(),
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+/// Build `operation` unsigned for `payer`. New mints are always derived from
+/// a seed here, so the payer is the only signer.
+pub async fn prepare(
+    config: &Config,
+    actor: &str,
+    payer: &Pubkey,
+    mut operation: MintOperation,
+) -> Result<PrepareMintResponse> {
+    if let MintOperation::CreateMint { seed, .. } = &mut operation
+        && seed.is_none()
+    {
+        *seed = Some(format!(
+            "mint-{}",
+            signing::to_hex(&rand::random::<[u8; 8]>())
+        ));
+    }
+
+    let mut intent = json!(operation);
+    intent["payer"] = json!(payer.to_string());
+    let mut audit = Audit::new(actor, "mint", intent);
+    let result: Result<PrepareMintResponse> = async {
+        let client = rpc::client(config);
+
+        // Unused: a new mint has a seed by now
+        let plan = plan(&client, payer, &operation, &Keypair::new())?;
+        audit.check_policy(config, &plan.intent)?;
+
+        app_log!(
+            info,
+            "Preparing token {} for {}: {}",
+            operation_name(&operation),
+            payer,
+            plan.mint
+        );
+
+        let prepared = transaction::prepare_unsigned(config, payer, &plan.instructions)?;
+        Ok(PrepareMintResponse {
+            unsigned_transaction: prepared.unsigned_transaction,
+            mint: plan.mint.to_string(),
+            required_signers: prepared.required_signers,
+            recent_blockhash: prepared.recent_blockhash,
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+fn plan(
+    client: &RpcClient,
+    authority: &Pubkey,
+    operation: &MintOperation,
+    new_account: &Keypair,
+) -> Result<Plan> {
+    let plan = |instructions: Vec<Instruction>, mint: Pubkey, new_account_signs| Plan {
+        intent: Intent::instructions(authority, &instructions),
+        instructions,
+        mint,
+        new_account_signs,
+    };
+
+    Ok(match operation {
+        MintOperation::CreateMint {
+            decimals,
+            mint_authority,
+            freeze_authority,
+            token_2022,
+            extensions,
+            metadata,
+            seed,
+        } => {
+            let (instructions, mint, new_account_signs) = create_mint(
+                client,
+                authority,
+                *decimals,
+                mint_authority.as_deref(),
+                freeze_authority.as_deref(),
+                *token_2022,
+                extensions,
+                metadata.as_ref(),
+                seed.as_deref(),
+                new_account,
+            )?;
+            plan(instructions, mint, new_account_signs)
+        }
+        MintOperation::MintTo { mint, amount, to } => {
+            let mint = parse_address(mint)?;
+            let info = mint_info(client, &mint)?;
+            let owner = match to {
+                Some(to) => parse_address(to)?,
+                None => *authority,
+            };
+            let account =
+                get_associated_token_address_with_program_id(&owner, &mint, &info.program_id);
+            let instructions = vec![
+                create_associated_token_account_idempotent(
+                    authority,
+                    &owner,
+                    &mint,
+                    &info.program_id,
+                ),
+                token_instruction::mint_to_checked(
+                    &info.program_id,
+                    &mint,
+                    &account,
+                    authority,
+                    &[],
+                    to_units(*amount, info.decimals)?,
+                    info.decimals,
+                )?,
+            ];
+            plan(instructions, mint, false)
+        }
+        MintOperation::Burn { mint, amount } => {
+            let mint = parse_address(mint)?;
+            let info = mint_info(client, &mint)?;
+            let account =
+                get_associated_token_address_with_program_id(authority, &mint, &info.program_id);
+            let mut plan = plan(
+                vec![token_instruction::burn_checked(
+                    &info.program_id,
+                    &account,
+                    &mint,
+                    authority,
+                    &[],
+                    to_units(*amount, info.decimals)?,
+                    info.decimals,
+                )?],
+                mint,
+                false,
+            );
+            // Burnt tokens leave the wallet for good, so they count like a spend;
+            // limits for them are keyed by mint address
+            plan.intent.spend = Some(Spend {
+                symbol: mint.to_string(),
+                mint: mint.to_string(),
+                amount: *amount,
+            });
+            plan
+        }
+        MintOperation::Freeze { mint, owner } | MintOperation::Thaw { mint, owner } => {
+            let mint = parse_address(mint)?;
+            let info = mint_info(client, &mint)?;
+            let owner = parse_address(owner)?;
+            let account =
+                get_associated_token_address_with_program_id(&owner, &mint, &info.program_id);
+            let build = match operation {
+                MintOperation::Freeze { .. } => token_instruction::freeze_account,
+                _ => token_instruction::thaw_account,
+            };
+            plan(
+                vec![build(&info.program_id, &account, &mint, authority, &[])?],
+                mint,
+                false,
+            )
+        }
+        MintOperation::SetAuthority {
+            mint,
+            authority: kind,
+            new_authority,
+        } => {
+            let mint = parse_address(mint)?;
+            let info = mint_info(client, &mint)?;
+            let new_authority = new_authority.as_deref().map(parse_address).transpose()?;
+            plan(
+                vec![token_instruction::set_authority(
+                    &info.program_id,
+                    &mint,
+                    new_authority.as_ref(),
+                    authority_type(*kind),
+                    authority,
+                    &[],
+                )?],
+                mint,
+                false,
+            )
+        }
+        MintOperation::CreateMetadata {
+            mint,
+            name,
+            symbol,
+            uri,
+        } => {
+            let mint = parse_address(mint)?;
+            let metadata = TokenMetadata {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri: uri.clone(),
+            };
+            plan(
+                vec![metaplex::create_metadata(&mint, authority, &metadata)?],
+                mint,
+                false,
+            )
+        }
+    })
+}
+
+// Account creation, extensions, InitializeMint2, then metadata. The payer
+// stays mint authority until the metadata exists, since Metaplex needs its
+// signature, and hands it over at the end.
+#[allow(clippy::too_many_arguments)]
+fn create_mint(
+    client: &RpcClient,
+    payer: &Pubkey,
+    decimals: u8,
+    mint_authority: Option<&str>,
+    freeze_authority: Option<&str>,
+    token_2022: bool,
+    extensions: &[MintExtension],
+    metadata: Option<&TokenMetadata>,
+    seed: Option<&str>,
+    new_account: &Keypair,
+) -> Result<(Vec<Instruction>, Pubkey, bool)> {
+    let program_id = match token_2022 || !extensions.is_empty() {
+        true => spl_token_2022::id(),
+        false => spl_token::id(),
+    };
+    let mint_authority = mint_authority
+        .map(parse_address)
+        .transpose()?
+        .unwrap_or(*payer);
+    let freeze_authority = freeze_authority.map(parse_address).transpose()?;
+
+    let extension_types: Vec<ExtensionType> = extensions.iter().map(extension_type).collect();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&extension_types)?;
+    let rent = client.get_minimum_balance_for_rent_exemption(space)?;
+
+    let (mint, create, new_account_signs) = match seed {
+        Some(seed) => {
+            if seed.len() > MAX_SEED_LEN {
+                return Err(invalid(format!(
+                    "Seed '{}' is longer than {} bytes",
+                    seed, MAX_SEED_LEN
+                )));
+            }
+            let mint = Pubkey::create_with_seed(payer, seed, &program_id)
+                .map_err(|e| invalid(format!("Invalid seed '{}': {}", seed, e)))?;
+            let create = system_instruction::create_account_with_seed(
+                payer,
+                &mint,
+                payer,
+                seed,
+                rent,
+                space as u64,
+                &program_id,
+            );
+            (mint, create, false)
+        }
+        None => {
+            let mint = new_account.pubkey();
+            let create =
+                system_instruction::create_account(payer, &mint, rent, space as u64, &program_id);
+            (mint, create, true)
+        }
+    };
+
+    let mut instructions = vec![create];
+    for extension in extensions {
+        instructions.push(initialize_extension(
+            &program_id,
+            &mint,
+            payer,
+            decimals,
+            freeze_authority.is_some(),
+            extension,
+        )?);
+    }
+
+    let initial_authority = match metadata {
+        Some(_) => payer,
+        None => &mint_authority,
+    };
+    instructions.push(token_instruction::initialize_mint2(
+        &program_id,
+        &mint,
+        initial_authority,
+        freeze_authority.as_ref(),
+        decimals,
+    )?);
+
+    if let Some(metadata) = metadata {
+        instructions.push(metaplex::create_metadata(&mint, payer, metadata)?);
+        if mint_authority != *payer {
+            instructions.push(token_instruction::set_authority(
+                &program_id,
+                &mint,
+                Some(&mint_authority),
+                AuthorityType::MintTokens,
+                payer,
+                &[],
+            )?);
+        }
+    }
+
+    Ok((instructions, mint, new_account_signs))
+}
+
+// Extensions are initialized before the mint itself
+fn initialize_extension(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    decimals: u8,
+    has_freeze_authority: bool,
+    extension: &MintExtension,
+) -> Result<Instruction> {
+    Ok(match extension {
+        MintExtension::TransferFee {
+            basis_points,
+            maximum_fee,
+        } => transfer_fee::instruction::initialize_transfer_fee_config(
+            program_id,
+            mint,
+            Some(payer),
+            Some(payer),
+            *basis_points,
+            (*maximum_fee * 10_f64.powi(decimals as i32)).round() as u64,
+        )?,
+        MintExtension::InterestBearing { rate_bps } => {
+            interest_bearing_mint::instruction::initialize(
+                program_id,
+                mint,
+                Some(*payer),
+                *rate_bps,
+            )?
+        }
+        MintExtension::NonTransferable => {
+            token_instruction::initialize_non_transferable_mint(program_id, mint)?
+        }
+        MintExtension::PermanentDelegate { delegate } => {
+            token_instruction::initialize_permanent_delegate(
+                program_id,
+                mint,
+                &parse_address(delegate)?,
+            )?
+        }
+        MintExtension::MintCloseAuthority => {
+            token_instruction::initialize_mint_close_authority(program_id, mint, Some(payer))?
+        }
+        MintExtension::DefaultFrozen => {
+            if !has_freeze_authority {
+                return Err(invalid(
+                    "default_frozen needs a freeze authority to thaw accounts".to_string(),
+                ));
+            }
+            default_account_state::instruction::initialize_default_account_state(
+                program_id,
+                mint,
+                &AccountState::Frozen,
+            )?
+        }
+    })
+}
+
+fn extension_type(extension: &MintExtension) -> ExtensionType {
+    match extension {
+        MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+        MintExtension::InterestBearing { .. } => ExtensionType::InterestBearingConfig,
+        MintExtension::NonTransferable => ExtensionType::NonTransferable,
+        MintExtension::PermanentDelegate { .. } => ExtensionType::PermanentDelegate,
+        MintExtension::MintCloseAuthority => ExtensionType::MintCloseAuthority,
+        MintExtension::DefaultFrozen => ExtensionType::DefaultAccountState,
+    }
+}
+
+fn authority_type(authority: MintAuthority) -> AuthorityType {
+    match authority {
+        MintAuthority::Mint => AuthorityType::MintTokens,
+        MintAuthority::Freeze => AuthorityType::FreezeAccount,
+        MintAuthority::CloseMint => AuthorityType::CloseMint,
+        MintAuthority::TransferFeeConfig => AuthorityType::TransferFeeConfig,
+        MintAuthority::WithheldWithdraw => AuthorityType::WithheldWithdraw,
+        MintAuthority::InterestRate => AuthorityType::InterestRate,
+        MintAuthority::PermanentDelegate => AuthorityType::PermanentDelegate,
+    }
+}
+
+// The owning program tells legacy and Token-2022 mints apart
+fn mint_info(client: &RpcClient, mint: &Pubkey) -> Result<MintInfo> {
+    let account = client.get_account(mint)?;
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return Err(invalid(format!("{} is not a token mint", mint)));
+    }
+    let state = StateWithExtensions::<Mint>::unpack(&account.data)
+        .map_err(|e| invalid(format!("{} is not a token mint: {}", mint, e)))?;
+    Ok(MintInfo {
+        program_id: account.owner,
+        decimals: state.base.decimals,
+    })
+}
+
+fn parse_address(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| {
+        SolanaClientError::InvalidAddress {
+            address: address.to_string(),
+        }
+        .into()
+    })
+}
+
+fn to_units(amount: f64, decimals: u8) -> Result<u64> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(invalid(format!("Amount must be positive, got {}", amount)));
+    }
+    // Round: 2.01 * 10^6 is 2009999.9999999998 in floating point
+    Ok((amount * 10_f64.powi(decimals as i32)).round() as u64)
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    SolanaClientError::InvalidRequest { message }.into()
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::{signing, web};
+
+const SIGNATURE_DESCRIPTION: &str = "Hex HMAC-SHA256, keyed with the secret of X-Solanize-Key-Id, over \
+    `METHOD\\nPATH\\nX-Solanize-Timestamp\\nX-Solanize-Nonce\\nX-Solanize-Content-SHA256` \
+    (content hash is the hex SHA-256 of the body, empty body if omitted)";
+
+/// OpenAPI 3 document for the REST server, generated from the route handlers
+/// and the DTOs in `api.rs`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Solanize API",
+        description = "Wallet, swap and transaction endpoints. All paths are relative to the `/solana` mount point."
+    ),
+    servers((url = "/solana")),
+    paths(
+        web::health,
+        web::get_balance,
+        web::prepare_swap,
+        web::prepare_transaction,
+        web::prepare_wrap,
+        web::prepare_unwrap,
+        web::submit_signed_transaction,
+        web::get_token_price,
+        web::search_tokens,
+        web::get_wallet_tokens,
+        web::prepare_cleanup,
+        web::get_transaction_history_web,
+        web::get_pending_transactions_web,
+        web::prepare_stake,
+        web::get_stake_accounts,
+        web::prepare_mint,
+        web::create_payment_request,
+        web::get_payment_status,
+        web::pay_link_metadata,
+        web::pay_link_transaction,
+        web::watch_address_web,
+        web::watch_signature_web,
+        web::register_webhook,
+        web::list_webhooks,
+        web::delete_webhook,
+        web::list_dead_letters,
+        web::auth_challenge,
+        web::auth_verify,
+        web::auth_refresh,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "system"),
+        (name = "wallet", description = "Balances, holdings and history"),
+        (name = "transactions", description = "Prepare unsigned transactions and submit signed ones"),
+        (name = "tokens", description = "Prices and token search"),
+        (name = "staking", description = "Native stake accounts"),
+        (name = "mints", description = "SPL and Token-2022 mint administration"),
+        (name = "pay", description = "Solana Pay transfer requests and payment detection"),
+        (name = "watch", description = "Server-Sent Event streams"),
+        (name = "webhooks", description = "Wallet activity callbacks"),
+        (name = "auth", description = "Sign-In-With-Solana"),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            // An access token from /auth/verify, or the internal secret while no keys are configured
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+            components.add_security_scheme(
+                "signature",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                    signing::SIGNATURE_HEADER,
+                    SIGNATURE_DESCRIPTION,
+                ))),
+            );
+        }
+    }
+}
+
+pub fn spec_json() -> Result<String> {
+    Ok(ApiDoc::openapi().to_pretty_json()?)
+}
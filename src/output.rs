@@ -0,0 +1,166 @@
+use crate::api::{ApiError, ApiResponse, ErrorCode};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::process::ExitCode;
+
+/// How command results are printed on stdout; logs never go there
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Plain,
+    /// Aligned columns
+    Table,
+    /// One `{success, data, error}` document, the same envelope as the REST API
+    Json,
+}
+
+/// What a command produced: `data` for `--output json`, plus how to show it to a person
+#[derive(Default)]
+pub struct Report {
+    data: Value,
+    text: Vec<String>,
+    table: Option<(Vec<&'static str>, Vec<Vec<String>>)>,
+}
+
+impl Report {
+    pub fn new(data: impl Serialize) -> Result<Self> {
+        Ok(Self {
+            data: serde_json::to_value(data)?,
+            ..Default::default()
+        })
+    }
+
+    /// For commands that print nothing on success (server, menu)
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// A line of the plain rendering
+    pub fn line(mut self, line: impl Into<String>) -> Self {
+        self.text.push(line.into());
+        self
+    }
+
+    /// Rows for `--output table`; without them a table shows `data`'s top-level fields
+    pub fn table(mut self, headers: &[&'static str], rows: Vec<Vec<String>>) -> Self {
+        self.table = Some((headers.to_vec(), rows));
+        self
+    }
+
+    /// The plain rendering on stderr, for interim output that must not end up
+    /// in the stdout document
+    pub fn eprint(&self) {
+        for line in &self.text {
+            eprintln!("{}", line);
+        }
+    }
+
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Json => print_json(&ApiResponse {
+                success: true,
+                data: Some(&self.data),
+                error: None,
+            }),
+            OutputFormat::Table => match &self.table {
+                Some((headers, rows)) => print_table(headers, rows),
+                None => print_fields(&self.data),
+            },
+            OutputFormat::Plain => {
+                for line in &self.text {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+}
+
+/// Report a failed command and pick the process exit code from its error class
+pub fn fail(format: OutputFormat, error: &anyhow::Error) -> ExitCode {
+    let error = ApiError::from(error);
+    let code = exit_code(error.code);
+
+    match format {
+        OutputFormat::Json => print_json(&ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(error),
+        }),
+        OutputFormat::Plain | OutputFormat::Table => eprintln!("Error: {}", error.message),
+    }
+    ExitCode::from(code)
+}
+
+/// Exit codes scripts can branch on; 2 matches clap's own usage errors
+pub fn exit_code(code: ErrorCode) -> u8 {
+    match code {
+        ErrorCode::InternalError => 1,
+        ErrorCode::InvalidAddress | ErrorCode::InvalidWalletFormat | ErrorCode::InvalidRequest => 2,
+        ErrorCode::ConfigError => 3,
+        ErrorCode::AuthFailed | ErrorCode::Unauthorized | ErrorCode::Forbidden => 4,
+        ErrorCode::PolicyViolation => 5,
+        ErrorCode::NotFound | ErrorCode::WalletNotFound => 6,
+        ErrorCode::InsufficientBalance | ErrorCode::TransactionFailed => 7,
+        ErrorCode::NetworkError => 8,
+        ErrorCode::Conflict => 9,
+        ErrorCode::RateLimited => 10,
+    }
+}
+
+fn print_json(document: &impl Serialize) {
+    match serde_json::to_string_pretty(document) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error: failed to encode output: {}", e),
+    }
+}
+
+// Objects as field/value rows, anything else as a single cell
+fn print_fields(data: &Value) {
+    match data {
+        Value::Object(fields) => {
+            let rows = fields
+                .iter()
+                .map(|(key, value)| vec![key.clone(), cell(value)])
+                .collect::<Vec<_>>();
+            print_table(&["FIELD", "VALUE"], &rows);
+        }
+        Value::Null => {}
+        other => println!("{}", cell(other)),
+    }
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let render = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", render(headers.to_vec()));
+    for row in rows {
+        println!("{}", render(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// A JSON value as table text: strings unquoted, null as `-`
+pub fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
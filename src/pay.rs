@@ -0,0 +1,846 @@
+//! Solana Pay: transfer request URLs with a unique reference key, transaction
+//! requests this server answers with the transaction to sign, and finding the
+//! payment by its reference once the payer's wallet sends it.
+
+use crate::api::{
+    PayLinkTransaction, PayRequest, PayRequestResponse, PayStatusRequest, PayStatusResponse,
+    PaymentStatus,
+};
+use crate::app_log;
+use crate::audit::Audit;
+use crate::policy::{Intent, Spend};
+use crate::{config::Config, error::SolanaClientError, jupiter, rpc, signing, transaction, wallet};
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use qrcode::QrCode;
+use qrcode::render::unicode::Dense1x2;
+use reqwest::Url;
+use serde_json::json;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedTransaction, UiMessage, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::instruction as token_instruction;
+use spl_token_2022::state::Mint;
+use std::str::FromStr;
+use std::time::Duration;
+
+const SOL_DECIMALS: u32 = 9;
+
+// Payments are looked for among this many of the reference's latest transactions
+const SIGNATURE_LIMIT: usize = 25;
+
+/// What a transaction request link asks for, carried in its query string
+pub struct Checkout {
+    pub recipient: Pubkey,
+    pub amount: f64,
+    pub spl_token: Option<Pubkey>, // What the recipient receives, none for SOL
+    pub pay_with: Option<Pubkey>,  // What the payer spends, none for SOL
+    pub reference: Option<Pubkey>,
+    pub message: Option<String>,
+    pub memo: Option<String>, // Recorded with the transfer; swapped payments cannot carry one
+}
+
+impl Checkout {
+    /// Without `pay_with` the payer spends what the recipient receives
+    #[allow(clippy::too_many_arguments)]
+    pub fn parse(
+        config: &Config,
+        recipient: &str,
+        amount: f64,
+        spl_token: Option<&str>,
+        pay_with: Option<&str>,
+        reference: Option<&str>,
+        message: Option<String>,
+        memo: Option<String>,
+    ) -> Result<Self> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(invalid(format!("Amount must be positive, got {}", amount)));
+        }
+        let spl_token = resolve_token(config, spl_token)?;
+
+        Ok(Self {
+            recipient: parse_address(recipient)?,
+            amount,
+            spl_token,
+            pay_with: match pay_with {
+                Some(token) => resolve_token(config, Some(token))?,
+                None => spl_token,
+            },
+            reference: reference.map(parse_address).transpose()?,
+            message,
+            memo,
+        })
+    }
+
+    // The recipient receives a token other than the one the payer spends
+    fn swaps(&self) -> bool {
+        self.spl_token.is_some() && self.pay_with != self.spl_token
+    }
+}
+
+/// Build a transfer request with a fresh reference key
+pub async fn create_request(config: &Config, request: &PayRequest) -> Result<PayRequestResponse> {
+    let recipient = match request.recipient.as_ref().or(config.pay.recipient.as_ref()) {
+        Some(recipient) => parse_address(recipient)?,
+        None => wallet::load_keypair(config).await?.pubkey(),
+    };
+    if let Some(amount) = request.amount
+        && (!amount.is_finite() || amount <= 0.0)
+    {
+        return Err(invalid(format!("Amount must be positive, got {}", amount)));
+    }
+    let spl_token = resolve_token(config, request.spl_token.as_deref())?;
+
+    // Only its public key matters: the payer's wallet adds it to the transfer
+    let reference = Keypair::new().pubkey();
+    let label = request.label.as_ref().or(config.pay.label.as_ref());
+
+    let url = match &request.pay_with {
+        Some(pay_with) => {
+            let amount = request
+                .amount
+                .ok_or_else(|| invalid("A transaction request needs an amount".to_string()))?;
+            transaction_request_url(
+                config,
+                &Checkout {
+                    recipient,
+                    amount,
+                    spl_token,
+                    pay_with: resolve_token(config, Some(pay_with))?,
+                    reference: Some(reference),
+                    message: request.message.clone(),
+                    memo: request.memo.clone(),
+                },
+            )?
+        }
+        None => transfer_url(
+            &recipient,
+            request.amount,
+            spl_token.as_ref(),
+            &reference,
+            label.map(String::as_str),
+            request.message.as_deref(),
+            request.memo.as_deref(),
+        )?,
+    };
+
+    app_log!(info, "Payment request {} for {}", reference, recipient);
+
+    Ok(PayRequestResponse {
+        url,
+        recipient: recipient.to_string(),
+        amount: request.amount,
+        spl_token: spl_token.map(|mint| mint.to_string()),
+        reference: reference.to_string(),
+        expires_at: chrono::Utc::now().timestamp() + config.pay.expires_in_secs as i64,
+    })
+}
+
+/// `solana:<recipient>?amount=..&spl-token=..&reference=..&label=..&message=..&memo=..`
+pub fn transfer_url(
+    recipient: &Pubkey,
+    amount: Option<f64>,
+    spl_token: Option<&Pubkey>,
+    reference: &Pubkey,
+    label: Option<&str>,
+    message: Option<&str>,
+    memo: Option<&str>,
+) -> Result<String> {
+    let mut url = Url::parse(&format!("solana:{}", recipient))?;
+
+    let mut params = Vec::new();
+    if let Some(amount) = amount {
+        // Display never uses exponent notation, which the spec forbids
+        params.push(("amount", amount.to_string()));
+    }
+    if let Some(mint) = spl_token {
+        params.push(("spl-token", mint.to_string()));
+    }
+    params.push(("reference", reference.to_string()));
+    for (key, value) in [("label", label), ("message", message), ("memo", memo)] {
+        if let Some(value) = value {
+            params.push((key, value.to_string()));
+        }
+    }
+    url.query_pairs_mut().extend_pairs(params);
+
+    Ok(url.to_string())
+}
+
+/// `solana:<link>`, where the link is this server's `/pay/tx` with the
+/// checkout in its query string, URL-encoded as a whole. The query ends with an
+/// expiry and an HMAC over the rest, so only links made here are answered.
+pub fn transaction_request_url(config: &Config, checkout: &Checkout) -> Result<String> {
+    if checkout.memo.is_some() && checkout.swaps() {
+        return Err(invalid("Swapped payments cannot carry a memo".to_string()));
+    }
+    let base = config
+        .pay
+        .public_url
+        .as_ref()
+        .ok_or_else(|| SolanaClientError::ConfigError {
+            message: "Set pay.public_url to create transaction requests".to_string(),
+        })?;
+    let mut link = Url::parse(&format!("{}/pay/tx", base.trim_end_matches('/')))?;
+
+    let expires = chrono::Utc::now().timestamp() + config.pay.expires_in_secs as i64;
+    link.query_pairs_mut()
+        .extend_pairs(link_params(checkout, expires))
+        .append_pair("sig", &link_signature(config, checkout, expires)?);
+
+    let encoded: String = url::form_urlencoded::byte_serialize(link.as_str().as_bytes()).collect();
+    Ok(format!("solana:{}", encoded))
+}
+
+/// Check that a `/pay/tx` query was signed by `transaction_request_url` and
+/// has not expired
+pub fn verify_link(config: &Config, checkout: &Checkout, expires: i64, sig: &str) -> Result<()> {
+    let expected = link_signature(config, checkout, expires)?;
+    if !signing::constant_time_eq(&expected, sig) {
+        return Err(SolanaClientError::AuthFailed {
+            reason: "Payment link signature does not match".to_string(),
+        }
+        .into());
+    }
+    if expires < chrono::Utc::now().timestamp() {
+        return Err(invalid("Payment link has expired".to_string()));
+    }
+    Ok(())
+}
+
+// The signed part of the query, in link order
+fn link_params(checkout: &Checkout, expires: i64) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        ("recipient", checkout.recipient.to_string()),
+        ("amount", checkout.amount.to_string()),
+    ];
+    if let Some(mint) = checkout.spl_token {
+        params.push(("spl-token", mint.to_string()));
+    }
+    if checkout.pay_with != checkout.spl_token {
+        let token = checkout.pay_with.map(|mint| mint.to_string());
+        params.push(("pay-with", token.unwrap_or_else(|| "SOL".to_string())));
+    }
+    if let Some(reference) = checkout.reference {
+        params.push(("reference", reference.to_string()));
+    }
+    if let Some(message) = &checkout.message {
+        params.push(("message", message.clone()));
+    }
+    if let Some(memo) = &checkout.memo {
+        params.push(("memo", memo.clone()));
+    }
+    params.push(("expires", expires.to_string()));
+    params
+}
+
+fn link_signature(config: &Config, checkout: &Checkout, expires: i64) -> Result<String> {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(link_params(checkout, expires))
+        .finish();
+    signing::sign(&config.internal.secret, &query)
+}
+
+/// The transaction a transaction request's `account` signs: a SOL or token
+/// transfer, or a Jupiter swap into the recipient's token account when the
+/// payer spends something else. Audited with the payer as `pay:<account>`.
+pub async fn transaction(
+    config: &Config,
+    account: &Pubkey,
+    checkout: &Checkout,
+) -> Result<PayLinkTransaction> {
+    let actor = format!("pay:{}", account);
+    let recipient = &checkout.recipient;
+
+    let unsigned = match (checkout.spl_token, checkout.pay_with) {
+        (None, None) => {
+            transaction::prepare_sol_transfer(
+                config,
+                &actor,
+                account,
+                &recipient.to_string(),
+                checkout.amount,
+                checkout.memo.as_deref(),
+            )
+            .await?
+            .0
+        }
+        (Some(mint), Some(paid)) if mint == paid => {
+            token_transfer(
+                config,
+                &actor,
+                account,
+                recipient,
+                &mint,
+                checkout.amount,
+                checkout.memo.as_deref(),
+            )
+            .await?
+        }
+        (Some(_), _) if checkout.memo.is_some() => {
+            return Err(invalid("Swapped payments cannot carry a memo".to_string()));
+        }
+        (Some(mint), paid) => {
+            let input = paid.unwrap_or_else(spl_token::native_mint::id);
+            payment_swap(
+                config,
+                &actor,
+                account,
+                recipient,
+                &input,
+                &mint,
+                checkout.amount,
+            )
+            .await?
+        }
+        (None, Some(_)) => {
+            return Err(invalid(
+                "Payments received in SOL have to be paid in SOL".to_string(),
+            ));
+        }
+    };
+
+    let unsigned = match &checkout.reference {
+        Some(reference) => with_reference(&unsigned, reference)?,
+        None => unsigned,
+    };
+    Ok(PayLinkTransaction {
+        transaction: unsigned,
+        message: checkout.message.clone(),
+    })
+}
+
+async fn token_transfer(
+    config: &Config,
+    actor: &str,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    amount: f64,
+    memo: Option<&str>,
+) -> Result<String> {
+    let mut audit = Audit::new(
+        actor,
+        "transfer",
+        json!({ "token": mint.to_string(), "from": payer.to_string(), "to": recipient.to_string(), "amount": amount, "memo": memo }),
+    );
+    let result: Result<String> = async {
+        let client = rpc::client(config);
+        let (program_id, decimals) = mint_info(&client, mint)?;
+
+        let source = get_associated_token_address_with_program_id(payer, mint, &program_id);
+        let destination =
+            get_associated_token_address_with_program_id(recipient, mint, &program_id);
+        // The transfer goes last: the reference is added to it
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            payer,
+            recipient,
+            mint,
+            &program_id,
+        )];
+        instructions.extend(transaction::with_memo(
+            memo,
+            payer,
+            token_instruction::transfer_checked(
+                &program_id,
+                &source,
+                mint,
+                &destination,
+                payer,
+                &[],
+                (amount * 10f64.powi(decimals as i32)).round() as u64,
+                decimals,
+            )?,
+        )?);
+
+        audit.check_policy(
+            config,
+            &Intent {
+                spend: Some(Spend {
+                    symbol: mint.to_string(),
+                    mint: mint.to_string(),
+                    amount,
+                }),
+                recipients: vec![recipient.to_string()],
+                ..Intent::instructions(payer, &instructions)
+            },
+        )?;
+
+        app_log!(
+            info,
+            "Preparing payment of {} {} from {} to {}",
+            amount,
+            mint,
+            payer,
+            recipient
+        );
+        Ok(transaction::prepare_unsigned(config, payer, &instructions)?.unsigned_transaction)
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+// Exactly `amount` of `output` for the recipient, paid in whatever `input` that takes
+async fn payment_swap(
+    config: &Config,
+    actor: &str,
+    payer: &Pubkey,
+    recipient: &Pubkey,
+    input: &Pubkey,
+    output: &Pubkey,
+    amount: f64,
+) -> Result<String> {
+    let mut audit = Audit::new(
+        actor,
+        "swap",
+        json!({ "from_token": input.to_string(), "to_token": output.to_string(), "amount_out": amount, "payer": payer.to_string(), "recipient": recipient.to_string() }),
+    );
+    let result: Result<String> = async {
+        let client = rpc::client(config);
+        let (output_program, output_decimals) = mint_info(&client, output)?;
+        let (_, input_decimals) = mint_info(&client, input)?;
+
+        // Jupiter delivers into the account but does not create it
+        let destination =
+            get_associated_token_address_with_program_id(recipient, output, &output_program);
+        if client
+            .get_account_with_commitment(&destination, client.commitment())?
+            .value
+            .is_none()
+        {
+            return Err(invalid(format!(
+                "{} has no token account for {}",
+                recipient, output
+            )));
+        }
+
+        let quote = jupiter::get_exact_out_quote(
+            config,
+            &input.to_string(),
+            &output.to_string(),
+            (amount * 10f64.powi(output_decimals as i32)).round() as u64,
+        )
+        .await?;
+        let spent = quote.in_amount.parse::<u64>()? as f64 / 10f64.powi(input_decimals as i32);
+        let price_impact = quote.price_impact_pct.parse::<f64>()?;
+        let slippage_bps = quote.slippage_bps;
+
+        app_log!(
+            info,
+            "Preparing payment of {} {} to {}, paid with ~{} {}",
+            amount,
+            output,
+            recipient,
+            spent,
+            input
+        );
+
+        let swap =
+            jupiter::get_swap_transaction_to(config, quote, payer, Some(&destination)).await?;
+        let versioned: VersionedTransaction =
+            bincode::deserialize(&BASE64.decode(&swap.swap_transaction)?)?;
+
+        audit.check_policy(
+            config,
+            &Intent {
+                recipients: vec![recipient.to_string()],
+                ..Intent::swap(
+                    payer,
+                    Spend {
+                        symbol: input.to_string(),
+                        mint: input.to_string(),
+                        amount: spent,
+                    },
+                    price_impact,
+                    slippage_bps,
+                    &versioned.message,
+                )
+            },
+        )?;
+
+        Ok(swap.swap_transaction)
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+/// Add `reference` to an unsigned transaction as a read-only account, so the
+/// payment can be found with getSignaturesForAddress
+pub fn with_reference(unsigned: &str, reference: &Pubkey) -> Result<String> {
+    let mut tx: VersionedTransaction = bincode::deserialize(&BASE64.decode(unsigned)?)?;
+
+    match &mut tx.message {
+        VersionedMessage::Legacy(message) => {
+            let index = message.account_keys.len() as u8;
+            message.account_keys.push(*reference);
+            message.header.num_readonly_unsigned_accounts += 1;
+            // As Solana Pay wallets do: on the transfer, which ignores extra accounts
+            if let Some(transfer) = message.instructions.last_mut() {
+                transfer.accounts.push(index);
+            }
+        }
+        VersionedMessage::V0(message) => {
+            // Jupiter's instructions take no extra accounts, so the reference
+            // is only listed. Lookup table accounts are numbered after the
+            // static keys and move up by one.
+            let index = message.account_keys.len() as u8;
+            for instruction in &mut message.instructions {
+                if instruction.program_id_index >= index {
+                    instruction.program_id_index += 1;
+                }
+                for account in &mut instruction.accounts {
+                    if *account >= index {
+                        *account += 1;
+                    }
+                }
+            }
+            message.account_keys.push(*reference);
+            message.header.num_readonly_unsigned_accounts += 1;
+        }
+    }
+
+    Ok(BASE64.encode(bincode::serialize(&tx)?))
+}
+
+/// `url` as a QR code drawn with half-block characters, light on dark
+pub fn render_qr(url: &str) -> Result<String> {
+    let code = QrCode::new(url.as_bytes())?;
+
+    // Terminals draw glyphs in the light foreground colour, so swap the
+    // colours to keep dark modules dark
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+/// Look the reference up once and judge the payment it finds, if any
+pub async fn check(config: &Config, request: &PayStatusRequest) -> Result<PayStatusResponse> {
+    let reference = parse_address(&request.reference)?;
+    let recipient = parse_address(&request.recipient)?;
+    let spl_token = resolve_token(config, request.spl_token.as_deref())?;
+    let client = rpc::client(config);
+
+    let signatures = client.get_signatures_for_address_with_config(
+        &reference,
+        GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until: None,
+            limit: Some(SIGNATURE_LIMIT),
+            commitment: Some(CommitmentConfig::confirmed()),
+        },
+    )?;
+
+    let mut response = PayStatusResponse {
+        reference: request.reference.clone(),
+        status: PaymentStatus::Pending,
+        signature: None,
+        received: None,
+        expected: request.amount,
+    };
+
+    // Oldest first: the first transfer made with the reference is the payment
+    for info in signatures.iter().rev().filter(|info| info.err.is_none()) {
+        let signature = Signature::from_str(&info.signature)?;
+        let Some((received, decimals)) =
+            received_by(&client, &signature, &recipient, spl_token.as_ref())?
+        else {
+            continue;
+        };
+        if received <= 0 {
+            continue;
+        }
+
+        let scale = 10f64.powi(decimals as i32);
+        let paid = match request.amount {
+            Some(amount) => received >= (amount * scale).round() as i128,
+            None => true,
+        };
+
+        response.status = match paid {
+            true => PaymentStatus::Paid,
+            false => PaymentStatus::Underpaid,
+        };
+        response.signature = Some(info.signature.clone());
+        response.received = Some(received as f64 / scale);
+        app_log!(
+            info,
+            "Payment {} for reference {}: {:?}",
+            info.signature,
+            reference,
+            response.status
+        );
+        return Ok(response);
+    }
+
+    if request
+        .expires_at
+        .is_some_and(|expires_at| chrono::Utc::now().timestamp() > expires_at)
+    {
+        response.status = PaymentStatus::Expired;
+    }
+    Ok(response)
+}
+
+/// Poll until the payment is found or the request expires
+pub async fn watch(config: &Config, request: &PayStatusRequest) -> Result<PayStatusResponse> {
+    loop {
+        let status = check(config, request).await?;
+        if status.status != PaymentStatus::Pending {
+            return Ok(status);
+        }
+        tokio::time::sleep(Duration::from_secs(config.pay.poll_interval_secs)).await;
+    }
+}
+
+// What `signature` moved to `recipient`, in base units of SOL or the token,
+// with the decimals; None if the transaction is missing or did not touch it
+fn received_by(
+    client: &RpcClient,
+    signature: &Signature,
+    recipient: &Pubkey,
+    spl_token: Option<&Pubkey>,
+) -> Result<Option<(i128, u32)>> {
+    let tx = client.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let Some(meta) = &tx.transaction.meta else {
+        return Ok(None);
+    };
+
+    if let Some(mint) = spl_token {
+        let pre: Vec<UiTransactionTokenBalance> =
+            Option::from(meta.pre_token_balances.clone()).unwrap_or_default();
+        let post: Vec<UiTransactionTokenBalance> =
+            Option::from(meta.post_token_balances.clone()).unwrap_or_default();
+
+        let mut received = 0i128;
+        let mut decimals = None;
+        for (balances, sign) in [(&pre, -1), (&post, 1)] {
+            for balance in balances {
+                let owner = match &balance.owner {
+                    OptionSerializer::Some(owner) => owner.as_str(),
+                    _ => continue,
+                };
+                if owner == recipient.to_string() && balance.mint == mint.to_string() {
+                    let amount = balance.ui_token_amount.amount.parse::<i128>()?;
+                    received += sign * amount;
+                    decimals = Some(balance.ui_token_amount.decimals as u32);
+                }
+            }
+        }
+        return Ok(decimals.map(|decimals| (received, decimals)));
+    }
+
+    let account_keys = match &tx.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(message) => message
+                .account_keys
+                .iter()
+                .map(|a| a.pubkey.clone())
+                .collect(),
+        },
+        _ => return Ok(None),
+    };
+    let Some(index) = account_keys
+        .iter()
+        .position(|key| *key == recipient.to_string())
+    else {
+        return Ok(None);
+    };
+
+    let pre = meta.pre_balances.get(index).copied().unwrap_or(0) as i128;
+    let post = meta.post_balances.get(index).copied().unwrap_or(0) as i128;
+    Ok(Some((post - pre, SOL_DECIMALS)))
+}
+
+// Token program that owns `mint`, and its decimals
+fn mint_info(client: &RpcClient, mint: &Pubkey) -> Result<(Pubkey, u8)> {
+    let account = client.get_account(mint)?;
+    let decimals = StateWithExtensions::<Mint>::unpack(&account.data)?
+        .base
+        .decimals;
+    Ok((account.owner, decimals))
+}
+
+// A mint for the symbol or address, none for SOL
+fn resolve_token(config: &Config, token: Option<&str>) -> Result<Option<Pubkey>> {
+    let Some(token) = token else {
+        return Ok(None);
+    };
+    if token.eq_ignore_ascii_case("SOL") {
+        return Ok(None);
+    }
+
+    let mint = config
+        .tokens
+        .mint_for(token)
+        .map(String::as_str)
+        .unwrap_or(token);
+    if *mint == config.tokens.sol {
+        return Ok(None);
+    }
+    Ok(Some(parse_address(mint)?))
+}
+
+fn parse_address(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| {
+        SolanaClientError::InvalidAddress {
+            address: address.to_string(),
+        }
+        .into()
+    })
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    SolanaClientError::InvalidRequest { message }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{Message, v0};
+
+    fn encode(message: VersionedMessage) -> String {
+        let signatures =
+            vec![Signature::default(); message.header().num_required_signatures as usize];
+        BASE64.encode(
+            bincode::serialize(&VersionedTransaction {
+                signatures,
+                message,
+            })
+            .unwrap(),
+        )
+    }
+
+    fn decode(transaction: &str) -> VersionedMessage {
+        bincode::deserialize::<VersionedTransaction>(&BASE64.decode(transaction).unwrap())
+            .unwrap()
+            .message
+    }
+
+    // Each instruction's program and accounts, resolved through `tables`
+    fn resolved(
+        message: &VersionedMessage,
+        tables: &[AddressLookupTableAccount],
+    ) -> Vec<(Pubkey, Vec<Pubkey>)> {
+        let mut keys = message.static_account_keys().to_vec();
+        if let Some(lookups) = message.address_table_lookups() {
+            let loaded = |indexes: fn(&v0::MessageAddressTableLookup) -> &Vec<u8>| {
+                lookups
+                    .iter()
+                    .flat_map(|lookup| {
+                        let table = tables.iter().find(|t| t.key == lookup.account_key).unwrap();
+                        indexes(lookup).iter().map(|&i| table.addresses[i as usize])
+                    })
+                    .collect::<Vec<_>>()
+            };
+            keys.extend(loaded(|lookup| &lookup.writable_indexes));
+            keys.extend(loaded(|lookup| &lookup.readonly_indexes));
+        }
+        message
+            .instructions()
+            .iter()
+            .map(|ix| {
+                (
+                    keys[ix.program_id_index as usize],
+                    ix.accounts.iter().map(|&i| keys[i as usize]).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adds_reference_to_legacy_transfer() {
+        let (payer, to, reference) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let transfer = solana_system_interface::instruction::transfer(&payer, &to, 1);
+        let message = VersionedMessage::Legacy(Message::new(&[transfer], Some(&payer)));
+
+        let message = decode(&with_reference(&encode(message), &reference).unwrap());
+        message.sanitize().unwrap();
+        let index = message.static_account_keys().len() - 1;
+        assert_eq!(message.static_account_keys()[index], reference);
+        assert!(!message.is_maybe_writable(index, None));
+        assert!(!message.is_signer(index));
+        assert_eq!(
+            resolved(&message, &[]),
+            vec![(
+                solana_system_interface::program::ID,
+                vec![payer, to, reference]
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_v0_lookups_valid() {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let reference = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: (0..4).map(|_| Pubkey::new_unique()).collect(),
+        };
+        // Writable and readonly accounts from the table, as Jupiter routes have
+        let swap = Instruction::new_with_bytes(
+            program,
+            &[7],
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(table.addresses[2], false),
+                AccountMeta::new_readonly(table.addresses[0], false),
+                AccountMeta::new_readonly(table.addresses[3], false),
+            ],
+        );
+        let message = VersionedMessage::V0(
+            v0::Message::try_compile(
+                &payer,
+                &[swap],
+                std::slice::from_ref(&table),
+                Hash::new_unique(),
+            )
+            .unwrap(),
+        );
+        assert!(
+            message
+                .address_table_lookups()
+                .is_some_and(|l| !l.is_empty())
+        );
+        let before = resolved(&message, std::slice::from_ref(&table));
+
+        let message = decode(&with_reference(&encode(message), &reference).unwrap());
+        message.sanitize().unwrap();
+        let index = message.static_account_keys().len() - 1;
+        assert_eq!(message.static_account_keys()[index], reference);
+        assert!(!message.is_maybe_writable(index, None));
+        assert!(!message.is_signer(index));
+        assert_eq!(resolved(&message, &[table]), before);
+    }
+}
@@ -0,0 +1,626 @@
+use crate::app_log;
+use crate::config::{Config, PolicyConfig, TokenLimit};
+use crate::error::SolanaClientError;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_address_lookup_table_interface::state::AddressLookupTable;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::message::compiled_instruction::CompiledInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_system_interface::instruction::SystemInstruction;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::extension::transfer_fee::instruction::TransferFeeInstruction;
+use spl_token_2022::state::{Account as TokenAccount, Mint};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+const DAY_SECS: i64 = 24 * 3600;
+// Token-2022 instruction tag that prefixes the transfer fee extension's own
+const TRANSFER_FEE_EXTENSION: u8 = 26;
+
+/// Tokens leaving the wallet, in whole units
+#[derive(Clone)]
+pub struct Spend {
+    pub symbol: String,
+    pub mint: String,
+    pub amount: f64,
+}
+
+/// What a transaction is about to do, as far as the policy cares
+#[derive(Default)]
+pub struct Intent {
+    pub wallet: String, // Payer; daily limits are tracked per wallet
+    pub spend: Option<Spend>,
+    pub recipients: Vec<String>,
+    pub price_impact_pct: Option<f64>,
+    pub slippage_bps: Option<u16>,
+    pub programs: Vec<Pubkey>,
+}
+
+impl Intent {
+    pub fn sol_transfer(config: &Config, payer: &Pubkey, to_address: &str, amount: f64) -> Self {
+        Self {
+            wallet: payer.to_string(),
+            spend: Some(Spend {
+                symbol: "SOL".to_string(),
+                mint: config.tokens.sol.clone(),
+                amount,
+            }),
+            recipients: vec![to_address.to_string()],
+            programs: vec![solana_system_interface::program::ID],
+            ..Default::default()
+        }
+    }
+
+    /// Instructions that keep funds under the wallet's control; only the programs are checked
+    pub fn instructions(payer: &Pubkey, instructions: &[Instruction]) -> Self {
+        Self {
+            wallet: payer.to_string(),
+            programs: instructions.iter().map(|ix| ix.program_id).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn swap(
+        payer: &Pubkey,
+        spend: Spend,
+        price_impact_pct: f64,
+        slippage_bps: u16,
+        message: &VersionedMessage,
+    ) -> Self {
+        Self {
+            wallet: payer.to_string(),
+            spend: Some(spend),
+            price_impact_pct: Some(price_impact_pct),
+            slippage_bps: Some(slippage_bps),
+            programs: program_ids(message),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SpendRecord {
+    wallet: String,
+    mint: String,
+    amount: f64,
+    timestamp: i64,
+    #[serde(default)]
+    signature: Option<String>, // Transaction it was counted for, to take it back
+}
+
+// Serializes read-modify-write of the ledger file within this process
+static LEDGER: Mutex<()> = Mutex::new(());
+
+/// Check a transaction before it is signed or returned. Its spend only counts
+/// towards the daily limit once the transaction is sent (see `count`).
+pub fn check(config: &Config, intent: &Intent) -> Result<()> {
+    let policy = &config.policy;
+    check_amount(intent)?;
+
+    let mut violations = evaluate(policy, intent);
+
+    if let Some(spend) = &intent.spend {
+        let totals = daily_totals(policy, &[(intent.wallet.clone(), spend.clone())]);
+        if !totals.is_empty() {
+            let _guard = LEDGER.lock().unwrap();
+            violations.extend(over_daily_limit(
+                &load_ledger(&policy.ledger_path)?,
+                &totals,
+            ));
+        }
+    }
+
+    reject(violations)
+}
+
+/// Count `(wallet, spend)` pairs towards the daily limits as their transaction
+/// is sent. The limits are checked again, so two transactions prepared within
+/// the same headroom cannot both be sent.
+pub fn count(config: &Config, spends: &[(String, Spend)], signature: Option<&str>) -> Result<()> {
+    let policy = &config.policy;
+    let totals = daily_totals(policy, spends);
+    if totals.is_empty() {
+        return Ok(());
+    }
+
+    let _guard = LEDGER.lock().unwrap();
+    let mut ledger = load_ledger(&policy.ledger_path)?;
+    reject(over_daily_limit(&ledger, &totals))?;
+
+    let timestamp = chrono::Utc::now().timestamp();
+    ledger.extend(totals.into_iter().map(|(wallet, spend, _)| SpendRecord {
+        wallet,
+        mint: spend.mint,
+        amount: spend.amount,
+        timestamp,
+        signature: signature.map(str::to_string),
+    }));
+    save_ledger(&policy.ledger_path, &ledger)
+}
+
+/// Take back what `count` recorded for a transaction that failed to send
+pub fn uncount(config: &Config, signature: &str) -> Result<()> {
+    let policy = &config.policy;
+    let _guard = LEDGER.lock().unwrap();
+    let mut ledger = load_ledger(&policy.ledger_path)?;
+
+    let before = ledger.len();
+    ledger.retain(|r| r.signature.as_deref() != Some(signature));
+    if ledger.len() == before {
+        return Ok(());
+    }
+    save_ledger(&policy.ledger_path, &ledger)
+}
+
+/// Check a transaction paid by someone else's wallet, such as a Solana Pay
+/// payer: every rule except the daily limit, and nothing is recorded
+pub fn check_external(config: &Config, intent: &Intent) -> Result<()> {
+    check_amount(intent)?;
+    reject(evaluate(&config.policy, intent))
+}
+
+// A negative amount would free up daily headroom, and NaN breaks the ledger
+fn check_amount(intent: &Intent) -> Result<()> {
+    match &intent.spend {
+        Some(spend) if !spend.amount.is_finite() || spend.amount <= 0.0 => {
+            Err(SolanaClientError::InvalidRequest {
+                message: format!("Amount must be positive, got {}", spend.amount),
+            }
+            .into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check an externally signed transaction and count its spend: programs, and
+/// the recipients and limits of the `transfers` decoded from it
+pub fn check_submission(
+    config: &Config,
+    message: &VersionedMessage,
+    transfers: &[Transfer],
+    signature: Option<&str>,
+) -> Result<()> {
+    let policy = &config.policy;
+    let spends: Vec<(String, Spend)> = transfers
+        .iter()
+        .map(|transfer| (transfer.from.clone(), transfer.spend(config)))
+        .collect();
+
+    let mut violations = evaluate(
+        policy,
+        &Intent {
+            recipients: transfers.iter().map(|t| t.to.clone()).collect(),
+            programs: program_ids(message),
+            ..Default::default()
+        },
+    );
+    // The per-transaction limit applies to everything a wallet sends of a token
+    for (wallet, spend) in totals(&spends) {
+        violations.extend(evaluate(
+            policy,
+            &Intent {
+                wallet,
+                spend: Some(spend),
+                ..Default::default()
+            },
+        ));
+    }
+    reject(violations)?;
+
+    count(config, &spends, signature)
+}
+
+/// Program ids invoked by the message's top-level instructions
+pub fn program_ids(message: &VersionedMessage) -> Vec<Pubkey> {
+    let keys = message.static_account_keys();
+    message
+        .instructions()
+        .iter()
+        .filter_map(|ix| keys.get(ix.program_id_index as usize).copied())
+        .collect()
+}
+
+/// Tokens an instruction moves out of a wallet
+#[derive(Debug, Serialize)]
+pub struct Transfer {
+    pub from: String, // Wallet that authorizes it
+    pub to: String,   // Owner of the receiving token account, else the account itself
+    pub mint: String,
+    pub amount: u64, // Base units
+    pub decimals: u8,
+}
+
+impl Transfer {
+    fn spend(&self, config: &Config) -> Spend {
+        Spend {
+            symbol: config
+                .tokens
+                .symbol_for(&self.mint)
+                .unwrap_or_else(|| self.mint.clone()),
+            mint: self.mint.clone(),
+            amount: self.amount as f64 / 10_f64.powi(self.decimals as i32),
+        }
+    }
+}
+
+// A transfer as its instruction states it, before any account is looked up
+struct Stated {
+    authority: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+    mint: Option<Pubkey>, // None for SOL, and for a token Transfer that leaves it to the source
+    amount: u64,
+    decimals: Option<u8>,
+}
+
+/// Transfers among the message's top-level instructions: System Transfer and
+/// TransferWithSeed, and SPL Token or Token-2022 Transfer, TransferChecked and
+/// TransferCheckedWithFee. Token accounts are looked up to find the wallet
+/// that receives, and moves between a wallet's own accounts are left out.
+pub fn transfers(
+    config: &Config,
+    client: &RpcClient,
+    message: &VersionedMessage,
+) -> Result<Vec<Transfer>> {
+    let keys = account_keys(client, message)?;
+    let account = |ix: &CompiledInstruction, index: usize| {
+        ix.accounts
+            .get(index)
+            .and_then(|&key| keys.get(key as usize))
+            .copied()
+    };
+
+    let mut stated = Vec::new();
+    // Associated token accounts this message creates, and their wallets
+    let mut created = HashMap::new();
+    for ix in message.instructions() {
+        let Some(program) = keys.get(ix.program_id_index as usize) else {
+            continue;
+        };
+        if *program == spl_associated_token_account::id() {
+            if let (Some(address), Some(wallet)) = (account(ix, 1), account(ix, 2)) {
+                created.insert(address, wallet);
+            }
+        } else if let Some(transfer) = decode(program, &ix.data, |index| account(ix, index)) {
+            stated.push(transfer);
+        }
+    }
+    if stated.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let accounts = fetch(
+        client,
+        stated.iter().flat_map(|t| [t.source, t.destination]),
+    )?;
+    for transfer in stated.iter_mut().filter(|t| t.decimals.is_none()) {
+        let (_, mint) = accounts
+            .get(&transfer.source)
+            .and_then(token_account)
+            .ok_or_else(|| invalid(format!("{} is not a token account", transfer.source)))?;
+        transfer.mint = Some(mint);
+    }
+    let mints = fetch(
+        client,
+        stated
+            .iter()
+            .filter(|t| t.decimals.is_none())
+            .filter_map(|t| t.mint),
+    )?;
+
+    let mut transfers = Vec::new();
+    for transfer in stated {
+        let to = accounts
+            .get(&transfer.destination)
+            .and_then(token_account)
+            .map(|(owner, _)| owner)
+            .or_else(|| created.get(&transfer.destination).copied())
+            .unwrap_or(transfer.destination);
+        if to == transfer.authority {
+            continue;
+        }
+
+        let (mint, decimals) = match (transfer.mint, transfer.decimals) {
+            (None, _) => (config.tokens.sol.clone(), 9),
+            (Some(mint), Some(decimals)) => (mint.to_string(), decimals),
+            (Some(mint), None) => {
+                let decimals = mints
+                    .get(&mint)
+                    .and_then(|a| StateWithExtensions::<Mint>::unpack(&a.data).ok())
+                    .ok_or_else(|| invalid(format!("{} is not a token mint", mint)))?
+                    .base
+                    .decimals;
+                (mint.to_string(), decimals)
+            }
+        };
+        transfers.push(Transfer {
+            from: transfer.authority.to_string(),
+            to: to.to_string(),
+            mint,
+            amount: transfer.amount,
+            decimals,
+        });
+    }
+    Ok(transfers)
+}
+
+fn decode(
+    program: &Pubkey,
+    data: &[u8],
+    account: impl Fn(usize) -> Option<Pubkey>,
+) -> Option<Stated> {
+    if *program == solana_system_interface::program::ID {
+        // TransferWithSeed takes from an address derived from the signing base
+        let (amount, source, authority, destination) = match bincode::deserialize(data).ok()? {
+            SystemInstruction::Transfer { lamports } => {
+                (lamports, account(0)?, account(0)?, account(1)?)
+            }
+            SystemInstruction::TransferWithSeed { lamports, .. } => {
+                (lamports, account(0)?, account(1)?, account(2)?)
+            }
+            _ => return None,
+        };
+        return Some(Stated {
+            authority,
+            source,
+            destination,
+            mint: None,
+            amount,
+            decimals: Some(9),
+        });
+    }
+    if *program != spl_token::id() && *program != spl_token_2022::id() {
+        return None;
+    }
+
+    // Both programs share these instructions; Token-2022 adds the one with a fee
+    let (amount, decimals) = match TokenInstruction::unpack(data) {
+        Ok(TokenInstruction::Transfer { amount }) => {
+            return Some(Stated {
+                authority: account(2)?,
+                source: account(0)?,
+                destination: account(1)?,
+                mint: None,
+                amount,
+                decimals: None,
+            });
+        }
+        Ok(TokenInstruction::TransferChecked { amount, decimals }) => (amount, decimals),
+        Ok(_) => return None,
+        Err(_) if data.first() == Some(&TRANSFER_FEE_EXTENSION) => {
+            match TransferFeeInstruction::unpack(&data[1..]).ok()? {
+                TransferFeeInstruction::TransferCheckedWithFee {
+                    amount, decimals, ..
+                } => (amount, decimals),
+                _ => return None,
+            }
+        }
+        Err(_) => return None,
+    };
+    Some(Stated {
+        authority: account(3)?,
+        source: account(0)?,
+        destination: account(2)?,
+        mint: Some(account(1)?),
+        amount,
+        decimals: Some(decimals),
+    })
+}
+
+// Every account the message can reference by index: its static keys, then the
+// writable and the readonly addresses it loads from lookup tables
+fn account_keys(client: &RpcClient, message: &VersionedMessage) -> Result<Vec<Pubkey>> {
+    let mut keys = message.static_account_keys().to_vec();
+    let Some(lookups) = message.address_table_lookups() else {
+        return Ok(keys);
+    };
+
+    let tables = fetch(client, lookups.iter().map(|l| l.account_key))?;
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let table = tables
+            .get(&lookup.account_key)
+            .and_then(|a| AddressLookupTable::deserialize(&a.data).ok())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "{} is not an address lookup table",
+                    lookup.account_key
+                ))
+            })?;
+        for (indexes, loaded) in [
+            (&lookup.writable_indexes, &mut writable),
+            (&lookup.readonly_indexes, &mut readonly),
+        ] {
+            for &index in indexes {
+                let address = table.addresses.get(index as usize).ok_or_else(|| {
+                    invalid(format!(
+                        "Lookup table {} has no index {}",
+                        lookup.account_key, index
+                    ))
+                })?;
+                loaded.push(*address);
+            }
+        }
+    }
+    keys.extend(writable);
+    keys.extend(readonly);
+    Ok(keys)
+}
+
+// The accounts that exist among `keys`, 100 per request as the RPC allows
+fn fetch(
+    client: &RpcClient,
+    keys: impl IntoIterator<Item = Pubkey>,
+) -> Result<HashMap<Pubkey, Account>> {
+    let mut keys: Vec<Pubkey> = keys.into_iter().collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut found = HashMap::new();
+    for chunk in keys.chunks(100) {
+        for (key, account) in chunk.iter().zip(client.get_multiple_accounts(chunk)?) {
+            if let Some(account) = account {
+                found.insert(*key, account);
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Owner and mint of a token account under either token program
+fn token_account(account: &Account) -> Option<(Pubkey, Pubkey)> {
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return None;
+    }
+    let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).ok()?;
+    Some((state.base.owner, state.base.mint))
+}
+
+// Every rule except the daily limit, which needs the ledger
+fn evaluate(policy: &PolicyConfig, intent: &Intent) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    if let Some(spend) = &intent.spend
+        && let Some(max) = limit_for(policy, spend).and_then(|l| l.per_transaction)
+        && spend.amount > max
+    {
+        violations.push(format!(
+            "{} {} exceeds the per-transaction limit of {}",
+            spend.amount, spend.symbol, max
+        ));
+    }
+
+    for recipient in &intent.recipients {
+        if policy.denied_recipients.contains(recipient) {
+            violations.push(format!("Recipient {} is denylisted", recipient));
+        } else if !policy.allowed_recipients.is_empty()
+            && !policy.allowed_recipients.contains(recipient)
+        {
+            violations.push(format!("Recipient {} is not on the allowlist", recipient));
+        }
+    }
+
+    if let (Some(impact), Some(max)) = (intent.price_impact_pct, policy.max_price_impact_pct)
+        && impact > max
+    {
+        violations.push(format!(
+            "Price impact {:.4}% exceeds the maximum of {}%",
+            impact, max
+        ));
+    }
+
+    if let (Some(slippage), Some(max)) = (intent.slippage_bps, policy.max_slippage_bps)
+        && slippage > max
+    {
+        violations.push(format!(
+            "Slippage of {} bps exceeds the maximum of {} bps",
+            slippage, max
+        ));
+    }
+
+    if !policy.allowed_programs.is_empty() {
+        for program in &intent.programs {
+            let violation = format!("Program {} is not allowed", program);
+            if !policy.allowed_programs.contains(&program.to_string())
+                && !violations.contains(&violation)
+            {
+                violations.push(violation);
+            }
+        }
+    }
+
+    violations
+}
+
+fn reject(violations: Vec<String>) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    for violation in &violations {
+        app_log!(warn, "Policy violation: {}", violation);
+    }
+    Err(SolanaClientError::PolicyViolation { violations }.into())
+}
+
+// Spends summed per wallet and mint
+fn totals(spends: &[(String, Spend)]) -> Vec<(String, Spend)> {
+    let mut totals: Vec<(String, Spend)> = Vec::new();
+    for (wallet, spend) in spends {
+        match totals
+            .iter_mut()
+            .find(|(w, total)| w == wallet && total.mint == spend.mint)
+        {
+            Some((_, total)) => total.amount += spend.amount,
+            None => totals.push((wallet.clone(), spend.clone())),
+        }
+    }
+    totals
+}
+
+// Totals of the spends that have a daily limit, with that limit
+fn daily_totals(policy: &PolicyConfig, spends: &[(String, Spend)]) -> Vec<(String, Spend, f64)> {
+    totals(spends)
+        .into_iter()
+        .filter_map(|(wallet, spend)| {
+            let limit = limit_for(policy, &spend)?.daily?;
+            Some((wallet, spend, limit))
+        })
+        .collect()
+}
+
+fn over_daily_limit(ledger: &[SpendRecord], totals: &[(String, Spend, f64)]) -> Vec<String> {
+    totals
+        .iter()
+        .filter_map(|(wallet, spend, limit)| {
+            let spent: f64 = ledger
+                .iter()
+                .filter(|r| r.wallet == *wallet && r.mint == spend.mint)
+                .map(|r| r.amount)
+                .sum();
+            (spent + spend.amount > *limit).then(|| {
+                format!(
+                    "{} {} would exceed the daily limit of {} ({} already spent in the last 24h)",
+                    spend.amount, spend.symbol, limit, spent
+                )
+            })
+        })
+        .collect()
+}
+
+fn limit_for<'a>(policy: &'a PolicyConfig, spend: &Spend) -> Option<&'a TokenLimit> {
+    policy
+        .limits
+        .iter()
+        .find(|(token, _)| token.eq_ignore_ascii_case(&spend.symbol) || **token == spend.mint)
+        .map(|(_, limit)| limit)
+}
+
+// Only the last 24h are kept; older spends no longer count against any limit
+fn load_ledger(path: &str) -> Result<Vec<SpendRecord>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut ledger: Vec<SpendRecord> = serde_json::from_str(&content)?;
+    let cutoff = chrono::Utc::now().timestamp() - DAY_SECS;
+    ledger.retain(|r| r.timestamp > cutoff);
+    Ok(ledger)
+}
+
+fn save_ledger(path: &str, ledger: &[SpendRecord]) -> Result<()> {
+    fs::write(path, serde_json::to_string(ledger)?)?;
+    Ok(())
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    SolanaClientError::InvalidRequest { message }.into()
+}
@@ -0,0 +1,53 @@
+use crate::config::Config;
+use crate::metrics;
+use async_trait::async_trait;
+use solana_client::client_error::Result as ClientResult;
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::time::Instant;
+
+/// HTTP transport that records latency and failures for every RPC method
+struct InstrumentedSender {
+    inner: HttpSender,
+}
+
+#[async_trait]
+impl RpcSender for InstrumentedSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        let started = Instant::now();
+        let result = self.inner.send(request, params).await;
+
+        let method = request.to_string();
+        metrics::record_rpc(&method, started.elapsed(), result.is_ok());
+        if matches!(request, RpcRequest::SendTransaction) && result.is_ok() {
+            metrics::inc(metrics::TRANSACTIONS_SUBMITTED, &[]);
+        }
+
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// RPC client for `solana.rpc_url`; use this instead of `RpcClient::new` so calls are measured
+pub fn client(config: &Config) -> RpcClient {
+    RpcClient::new_sender(
+        InstrumentedSender {
+            inner: HttpSender::new(&config.solana.rpc_url),
+        },
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    )
+}
@@ -0,0 +1,152 @@
+use crate::app_log;
+use crate::config::{Config, KeyScope};
+use crate::error::SolanaClientError;
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use subtle::ConstantTimeEq;
+
+pub const KEY_ID_HEADER: &str = "X-Solanize-Key-Id";
+pub const TIMESTAMP_HEADER: &str = "X-Solanize-Timestamp";
+pub const NONCE_HEADER: &str = "X-Solanize-Nonce";
+pub const CONTENT_HASH_HEADER: &str = "X-Solanize-Content-SHA256";
+pub const SIGNATURE_HEADER: &str = "X-Solanize-Signature";
+
+/// Everything a signed request commits to, as received in its headers
+pub struct SignedRequest<'a> {
+    pub key_id: &'a str,
+    pub method: &'a str,
+    pub path: &'a str, // Origin form, including the mount point and query
+    pub timestamp: &'a str,
+    pub nonce: &'a str,
+    pub body_sha256: &'a str,
+    pub signature: &'a str,
+}
+
+/// The string both sides feed to HMAC-SHA256
+pub fn canonical_request(
+    method: &str,
+    path: &str,
+    timestamp: &str,
+    nonce: &str,
+    body_sha256: &str,
+) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        path,
+        timestamp,
+        nonce,
+        body_sha256
+    )
+}
+
+pub fn sign(secret: &str, canonical: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(canonical.as_bytes());
+    Ok(to_hex(&mac.finalize().into_bytes()))
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+/// Server side of request authentication: configured keys plus a nonce cache
+/// covering the skew window, so a captured request cannot be replayed.
+pub struct RequestVerifier {
+    config: Config,
+    nonces: Mutex<HashMap<String, i64>>,
+}
+
+impl RequestVerifier {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Legacy `Bearer <secret>`; only honoured while no signing keys are configured
+    pub fn verify_bearer(&self, token: &str) -> Option<KeyScope> {
+        let internal = &self.config.internal;
+        if internal.keys.is_empty() && constant_time_eq(token, &internal.secret) {
+            Some(KeyScope::Submit)
+        } else {
+            None
+        }
+    }
+
+    /// Check a signed request and return the scope its key grants
+    pub fn verify(&self, request: &SignedRequest) -> Result<KeyScope> {
+        let now = chrono::Utc::now();
+        let internal = &self.config.internal;
+
+        let key = internal
+            .keys
+            .iter()
+            .find(|k| k.id == request.key_id)
+            .ok_or_else(|| auth_error(format!("Unknown key id {}", request.key_id)))?;
+
+        if key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(auth_error(format!("Key {} has expired", key.id)));
+        }
+
+        let timestamp: i64 = request
+            .timestamp
+            .parse()
+            .map_err(|_| auth_error("Malformed timestamp".to_string()))?;
+        if (now.timestamp() - timestamp).abs() > internal.max_clock_skew_secs {
+            return Err(auth_error("Timestamp outside the allowed skew".to_string()));
+        }
+
+        let canonical = canonical_request(
+            request.method,
+            request.path,
+            request.timestamp,
+            request.nonce,
+            request.body_sha256,
+        );
+        let signature = from_hex(request.signature)
+            .ok_or_else(|| auth_error("Malformed signature".to_string()))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.secret.as_bytes())?;
+        mac.update(canonical.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| auth_error("Signature mismatch".to_string()))?;
+
+        // Only remember nonces of genuine requests, otherwise anyone could fill the cache
+        let mut nonces = self.nonces.lock().unwrap();
+        nonces.retain(|_, expires_at| *expires_at > now.timestamp());
+        let nonce_key = format!("{}:{}", key.id, request.nonce);
+        if request.nonce.is_empty() || nonces.contains_key(&nonce_key) {
+            app_log!(warn, "Rejected replayed request for key {}", key.id);
+            return Err(auth_error("Nonce already used".to_string()));
+        }
+        nonces.insert(nonce_key, timestamp + internal.max_clock_skew_secs);
+
+        Ok(key.scopes.iter().copied().max().unwrap_or(KeyScope::Read))
+    }
+}
+
+fn auth_error(reason: String) -> anyhow::Error {
+    SolanaClientError::AuthFailed { reason }.into()
+}
@@ -1,53 +1,377 @@
+use crate::api::{
+    ApiResponse, BalanceRequest, BalanceResponse, CleanupRequest, CleanupResponse, PayRequest,
+    PayRequestResponse, PayStatusRequest, PayStatusResponse, PendingTransactionsRequest,
+    PendingTransactionsResponse, PrepareMintRequest, PrepareMintResponse, PrepareStakeRequest,
+    PrepareStakeResponse, PrepareSwapRequest, PrepareSwapResponse, PrepareTransactionRequest,
+    PrepareTransactionResponse, PrepareWrapRequest, PrepareWrapResponse, PriceRequest,
+    PriceResponse, SearchRequest, StakeAccountsRequest, StakeAccountsResponse, SubmitResponse,
+    SubmitSignedRequest, TokenSearchResponse, TransactionHistoryRequest,
+    TransactionHistoryResponse, WalletTokensRequest, WalletTokensResponse,
+};
+use crate::app_log;
+use crate::{config::Config, error::SolanaClientError, signing};
 use anyhow::Result;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{Serialize, de::DeserializeOwned};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AuthChallenge {
-    pub wallet_address: String,
-    pub challenge: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AuthVerification {
-    pub wallet_address: String,
-    pub signature: String,
-    pub challenge: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TransactionRequest {
-    pub from: String,
-    pub to: String,
-    pub amount: f64,
-}
+pub use crate::auth::{AuthChallenge, AuthTokens, AuthVerification, RefreshRequest};
 
 pub struct SolanaApiClient {
     client: Client,
     base_url: String,
+    access_token: Option<String>,
+    signing_key: Option<(String, String)>, // (key id, secret)
 }
 
 impl SolanaApiClient {
+    /// `base_url` includes the mount point, e.g. http://127.0.0.1:9876/solana
     pub fn new(base_url: String) -> Self {
         Self {
             client: Client::new(),
-            base_url,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            access_token: None,
+            signing_key: None,
+        }
+    }
+
+    /// Bearer token sent on every call: a wallet access token or the internal secret
+    pub fn with_access_token(mut self, token: String) -> Self {
+        self.access_token = Some(token);
+        self
+    }
+
+    /// HMAC-sign every call with one of the server's `internal.keys`
+    pub fn with_signing_key(mut self, key_id: String, secret: String) -> Self {
+        self.signing_key = Some((key_id, secret));
+        self
+    }
+
+    /// POST a JSON body and unwrap the `ApiResponse` envelope
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.post_with_key(path, body, None).await
+    }
+
+    async fn post_with_key<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+        idempotency_key: Option<String>,
+    ) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        let body = serde_json::to_vec(body)?;
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+
+        if let Some((key_id, secret)) = &self.signing_key {
+            let parsed = reqwest::Url::parse(&url)?;
+            let path = match parsed.query() {
+                Some(query) => format!("{}?{}", parsed.path(), query),
+                None => parsed.path().to_string(),
+            };
+            let timestamp = chrono::Utc::now().timestamp().to_string();
+            let nonce = signing::to_hex(&rand::random::<[u8; 16]>());
+            let body_sha256 = signing::sha256_hex(&body);
+            let canonical =
+                signing::canonical_request("POST", &path, &timestamp, &nonce, &body_sha256);
+
+            request = request
+                .header(signing::KEY_ID_HEADER, key_id)
+                .header(signing::TIMESTAMP_HEADER, timestamp)
+                .header(signing::NONCE_HEADER, nonce)
+                .header(signing::CONTENT_HASH_HEADER, body_sha256)
+                .header(
+                    signing::SIGNATURE_HEADER,
+                    signing::sign(secret, &canonical)?,
+                );
+        }
+
+        self.send(request.body(body), &url).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+        url: &str,
+    ) -> Result<T> {
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| SolanaClientError::NetworkError {
+                source: Box::new(e),
+            })?;
+
+        // Error responses still carry the envelope; only fall back to the
+        // status line when the body is not ours (proxy page, crash, ...)
+        let status = response.status();
+        let envelope: ApiResponse<T> = match response.json().await {
+            Ok(envelope) => envelope,
+            Err(e) if status.is_success() => return Err(e.into()),
+            Err(_) => {
+                return Err(SolanaClientError::NetworkError {
+                    source: format!("{} returned {}", url, status).into(),
+                }
+                .into());
+            }
+        };
+
+        match (envelope.data, envelope.error) {
+            (Some(data), _) if envelope.success => Ok(data),
+            (_, Some(error)) => Err(SolanaClientError::Remote {
+                code: error.code,
+                message: error.message,
+            }
+            .into()),
+            _ => Err(SolanaClientError::NetworkError {
+                source: format!("Empty response from {}", url).into(),
+            }
+            .into()),
         }
     }
 
-    // Future API implementations
-    pub async fn request_challenge(&self, _wallet_address: &str) -> Result<AuthChallenge> {
-        // TODO: Implement POST /api/v1/auth/challenge/{wallet_address}
-        unimplemented!("API endpoints not implemented yet")
+    pub async fn balance(&self, pubkey: &str) -> Result<BalanceResponse> {
+        self.post(
+            "/balance",
+            &BalanceRequest {
+                pubkey: pubkey.to_string(),
+            },
+        )
+        .await
     }
 
-    pub async fn verify_auth(&self, _verification: AuthVerification) -> Result<String> {
-        // TODO: Implement POST /api/v1/auth/verify
-        unimplemented!("API endpoints not implemented yet")
+    pub async fn prepare_swap(&self, request: &PrepareSwapRequest) -> Result<PrepareSwapResponse> {
+        self.post("/swap/prepare", request).await
     }
 
-    pub async fn refresh_token(&self, _token: &str) -> Result<String> {
-        // TODO: Implement POST /api/v1/auth/refresh
-        unimplemented!("API endpoints not implemented yet")
+    pub async fn prepare_transaction(
+        &self,
+        request: &PrepareTransactionRequest,
+    ) -> Result<PrepareTransactionResponse> {
+        self.post("/transaction/prepare", request).await
     }
+
+    pub async fn prepare_stake(
+        &self,
+        request: &PrepareStakeRequest,
+    ) -> Result<PrepareStakeResponse> {
+        self.post("/stake/prepare", request).await
+    }
+
+    pub async fn prepare_cleanup(&self, request: &CleanupRequest) -> Result<CleanupResponse> {
+        self.post("/cleanup/prepare", request).await
+    }
+
+    pub async fn prepare_wrap(&self, request: &PrepareWrapRequest) -> Result<PrepareWrapResponse> {
+        self.post("/wrap/prepare", request).await
+    }
+
+    pub async fn prepare_unwrap(
+        &self,
+        request: &PrepareWrapRequest,
+    ) -> Result<PrepareWrapResponse> {
+        self.post("/unwrap/prepare", request).await
+    }
+
+    pub async fn payment_request(&self, request: &PayRequest) -> Result<PayRequestResponse> {
+        self.post("/pay/request", request).await
+    }
+
+    pub async fn payment_status(&self, request: &PayStatusRequest) -> Result<PayStatusResponse> {
+        self.post("/pay/status", request).await
+    }
+
+    pub async fn prepare_mint(&self, request: &PrepareMintRequest) -> Result<PrepareMintResponse> {
+        self.post("/token/prepare", request).await
+    }
+
+    pub async fn stake_accounts(
+        &self,
+        pubkey: &str,
+        epochs: Option<usize>,
+    ) -> Result<StakeAccountsResponse> {
+        self.post(
+            "/stake/accounts",
+            &StakeAccountsRequest {
+                pubkey: pubkey.to_string(),
+                epochs,
+            },
+        )
+        .await
+    }
+
+    /// Submit a base64 bincode transaction that is already fully signed.
+    /// Keyed by the transaction itself, so resubmitting after a timeout is safe.
+    pub async fn submit_transaction(&self, signed_transaction: &str) -> Result<SubmitResponse> {
+        self.post_with_key(
+            "/transaction/submit",
+            &SubmitSignedRequest {
+                signed_transaction: signed_transaction.to_string(),
+            },
+            Some(signing::sha256_hex(signed_transaction.as_bytes())),
+        )
+        .await
+    }
+
+    pub async fn price(&self, token: &str) -> Result<PriceResponse> {
+        self.post(
+            "/price",
+            &PriceRequest {
+                token: token.to_string(),
+            },
+        )
+        .await
+    }
+
+    pub async fn search_tokens(&self, query: &str) -> Result<TokenSearchResponse> {
+        self.post(
+            "/tokens/search",
+            &SearchRequest {
+                query: query.to_string(),
+            },
+        )
+        .await
+    }
+
+    pub async fn wallet_tokens(&self, pubkey: &str) -> Result<WalletTokensResponse> {
+        self.post(
+            "/wallet/tokens",
+            &WalletTokensRequest {
+                pubkey: pubkey.to_string(),
+            },
+        )
+        .await
+    }
+
+    pub async fn transaction_history(
+        &self,
+        pubkey: &str,
+        limit: Option<usize>,
+        before: Option<String>,
+        memo: Option<String>,
+    ) -> Result<TransactionHistoryResponse> {
+        self.post(
+            "/transactions/history",
+            &TransactionHistoryRequest {
+                pubkey: pubkey.to_string(),
+                limit,
+                before,
+                memo,
+            },
+        )
+        .await
+    }
+
+    pub async fn pending_transactions(&self, pubkey: &str) -> Result<PendingTransactionsResponse> {
+        self.post(
+            "/transactions/pending",
+            &PendingTransactionsRequest {
+                pubkey: pubkey.to_string(),
+            },
+        )
+        .await
+    }
+
+    pub async fn request_challenge(&self, wallet_address: &str) -> Result<AuthChallenge> {
+        self.post(&format!("/auth/challenge/{}", wallet_address), &())
+            .await
+    }
+
+    pub async fn verify_auth(&self, verification: AuthVerification) -> Result<AuthTokens> {
+        self.post("/auth/verify", &verification).await
+    }
+
+    pub async fn refresh_token(&self, token: &str) -> Result<AuthTokens> {
+        self.post(
+            "/auth/refresh",
+            &RefreshRequest {
+                refresh_token: token.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// Full Sign-In-With-Solana round trip with a local keypair
+    pub async fn login(&self, keypair: &Keypair) -> Result<AuthTokens> {
+        let wallet_address = keypair.pubkey().to_string();
+        let challenge = self.request_challenge(&wallet_address).await?;
+
+        let signature = keypair.sign_message(challenge.challenge.as_bytes());
+
+        self.verify_auth(AuthVerification {
+            wallet_address,
+            signature: signature.to_string(),
+            challenge: challenge.challenge,
+        })
+        .await
+    }
+}
+
+pub fn remote_url(config: &Config, server: Option<String>) -> Result<String> {
+    server.or_else(|| config.remote.url.clone()).ok_or_else(|| {
+        SolanaClientError::ConfigError {
+            message: "No server given; pass --server or set remote.url".to_string(),
+        }
+        .into()
+    })
+}
+
+/// Build a client for remote mode: signed with `remote.key_id` when configured,
+/// otherwise from the stored session, refreshing it first if it expired
+pub async fn connect(config: &Config, server: Option<String>) -> Result<SolanaApiClient> {
+    let client = SolanaApiClient::new(remote_url(config, server)?);
+
+    if let (Some(key_id), Some(secret)) = (&config.remote.key_id, &config.remote.key_secret) {
+        return Ok(client.with_signing_key(key_id.clone(), secret.clone()));
+    }
+
+    let mut session = load_session(&config.remote.session_path)?;
+
+    if session.expires_at <= chrono::Utc::now().timestamp() {
+        app_log!(info, "Access token expired, refreshing session");
+        session = client.refresh_token(&session.refresh_token).await?;
+        save_session(&config.remote.session_path, &session)?;
+    }
+
+    Ok(client.with_access_token(session.access_token))
+}
+
+/// The tokens act for the wallet, so only the owner may read the file
+pub fn save_session(path: &str, tokens: &AuthTokens) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // `mode` only applies to new files; tighten one left by an older version
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(serde_json::to_string_pretty(tokens)?.as_bytes())?;
+    app_log!(info, "Session saved to {}", path);
+    Ok(())
+}
+
+pub fn load_session(path: &str) -> Result<AuthTokens> {
+    if !std::path::Path::new(path).exists() {
+        return Err(SolanaClientError::AuthFailed {
+            reason: format!("No session at {}, run `auth login` first", path),
+        }
+        .into());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
 }
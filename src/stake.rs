@@ -0,0 +1,498 @@
+use crate::api::{
+    PrepareStakeResponse, StakeAccountInfo, StakeAccountsResponse, StakeOperation, StakeReward,
+};
+use crate::app_log;
+use crate::audit::Audit;
+use crate::policy::{Intent, Spend};
+use crate::{config::Config, error::SolanaClientError, rpc, signing, transaction, wallet};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::{MAX_SEED_LEN, Pubkey},
+    signature::{Keypair, Signer},
+};
+use solana_stake_interface::instruction as stake_instruction;
+use solana_stake_interface::program::ID as STAKE_PROGRAM_ID;
+use solana_stake_interface::stake_history::StakeHistory;
+use solana_stake_interface::state::{Authorized, Lockup, StakeStateV2};
+use solana_system_interface::instruction as system_instruction;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+// Meta starts after the u32 state tag and the u64 rent-exempt reserve
+const STAKER_OFFSET: usize = 12;
+const WITHDRAWER_OFFSET: usize = 44;
+
+pub const DEFAULT_REWARD_EPOCHS: usize = 5;
+
+/// A stake operation signed by the local wallet and confirmed
+#[derive(Debug, Serialize)]
+pub struct StakeResult {
+    pub operation: &'static str,
+    pub signature: String,
+    pub stake_account: String,
+}
+
+// The instructions for one operation, and what the policy should see of them
+struct Plan {
+    instructions: Vec<Instruction>,
+    stake_account: Pubkey,
+    intent: Intent,
+    new_account_signs: bool, // A fresh keypair owns the new account's address
+}
+
+pub fn operation_name(operation: &StakeOperation) -> &'static str {
+    match operation {
+        StakeOperation::Create { .. } => "create",
+        StakeOperation::Delegate { .. } => "delegate",
+        StakeOperation::Deactivate { .. } => "deactivate",
+        StakeOperation::Withdraw { .. } => "withdraw",
+        StakeOperation::Split { .. } => "split",
+        StakeOperation::Merge { .. } => "merge",
+    }
+}
+
+/// Sign `operation` with the wallet and send it. Without a seed, create and
+/// split put the new account at a throwaway keypair's address.
+pub async fn execute(
+    config: &Config,
+    actor: &str,
+    operation: &StakeOperation,
+) -> Result<StakeResult> {
+    let mut audit = Audit::new(actor, "stake", json!(operation));
+    let result: Result<StakeResult> = async {
+        let keypair = wallet::load_keypair(config).await?;
+        let new_account = Keypair::new();
+        let client = rpc::client(config);
+
+        let plan = plan(config, &client, &keypair.pubkey(), operation, &new_account)?;
+        audit.check_policy(config, &plan.intent)?;
+
+        app_log!(
+            info,
+            "Stake {}: {}",
+            operation_name(operation),
+            plan.stake_account
+        );
+
+        let extra_signers: &[&Keypair] = match plan.new_account_signs {
+            true => &[&new_account],
+            false => &[],
+        };
+        let signature = transaction::sign_and_send(
+            config,
+            &mut audit,
+            &keypair,
+            extra_signers,
+            &plan.instructions,
+        )?;
+
+        Ok(StakeResult {
+            operation: operation_name(operation),
+            signature: signature.to_string(),
+            stake_account: plan.stake_account.to_string(),
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+/// Build `operation` unsigned for `payer`. New accounts are always derived
+/// from a seed here, so the payer is the only signer.
+pub async fn prepare(
+    config: &Config,
+    actor: &str,
+    payer: &Pubkey,
+    mut operation: StakeOperation,
+) -> Result<PrepareStakeResponse> {
+    if let StakeOperation::Create { seed, .. } | StakeOperation::Split { seed, .. } = &mut operation
+        && seed.is_none()
+    {
+        *seed = Some(format!(
+            "stake-{}",
+            signing::to_hex(&rand::random::<[u8; 8]>())
+        ));
+    }
+
+    let mut intent = json!(operation);
+    intent["payer"] = json!(payer.to_string());
+    let mut audit = Audit::new(actor, "stake", intent);
+    let result: Result<PrepareStakeResponse> = async {
+        let client = rpc::client(config);
+
+        // Unused: every new account has a seed by now
+        let plan = plan(config, &client, payer, &operation, &Keypair::new())?;
+        audit.check_policy(config, &plan.intent)?;
+
+        app_log!(
+            info,
+            "Preparing stake {} for {}: {}",
+            operation_name(&operation),
+            payer,
+            plan.stake_account
+        );
+
+        let prepared = transaction::prepare_unsigned(config, payer, &plan.instructions)?;
+        Ok(PrepareStakeResponse {
+            unsigned_transaction: prepared.unsigned_transaction,
+            stake_account: plan.stake_account.to_string(),
+            required_signers: prepared.required_signers,
+            recent_blockhash: prepared.recent_blockhash,
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+fn plan(
+    config: &Config,
+    client: &RpcClient,
+    authority: &Pubkey,
+    operation: &StakeOperation,
+    new_account: &Keypair,
+) -> Result<Plan> {
+    let plan = |instructions: Vec<Instruction>, stake_account: Pubkey, new_account_signs| Plan {
+        intent: Intent::instructions(authority, &instructions),
+        instructions,
+        stake_account,
+        new_account_signs,
+    };
+
+    Ok(match operation {
+        StakeOperation::Create { amount, seed } => {
+            let lamports = to_lamports(*amount)?;
+            let reserve = client.get_minimum_balance_for_rent_exemption(StakeStateV2::size_of())?;
+            if lamports <= reserve {
+                return Err(invalid(format!(
+                    "A stake account needs more than the rent-exempt reserve of {} SOL",
+                    to_sol(reserve)
+                )));
+            }
+
+            let balance = client.get_balance(authority)?;
+            if balance < lamports {
+                return Err(SolanaClientError::InsufficientBalance {
+                    current: to_sol(balance),
+                    required: *amount,
+                }
+                .into());
+            }
+
+            let authorized = Authorized::auto(authority);
+            let lockup = Lockup::default();
+            match seed {
+                Some(seed) => {
+                    let address = seeded_address(authority, seed)?;
+                    let instructions = stake_instruction::create_account_with_seed(
+                        authority,
+                        &address,
+                        authority,
+                        seed,
+                        &authorized,
+                        &lockup,
+                        lamports,
+                    );
+                    plan(instructions, address, false)
+                }
+                None => {
+                    let address = new_account.pubkey();
+                    let instructions = stake_instruction::create_account(
+                        authority,
+                        &address,
+                        &authorized,
+                        &lockup,
+                        lamports,
+                    );
+                    plan(instructions, address, true)
+                }
+            }
+        }
+        StakeOperation::Delegate {
+            stake_account,
+            vote_account,
+        } => {
+            let stake = parse_address(stake_account)?;
+            let vote = parse_address(vote_account)?;
+            plan(
+                vec![stake_instruction::delegate_stake(&stake, authority, &vote)],
+                stake,
+                false,
+            )
+        }
+        StakeOperation::Deactivate { stake_account } => {
+            let stake = parse_address(stake_account)?;
+            plan(
+                vec![stake_instruction::deactivate_stake(&stake, authority)],
+                stake,
+                false,
+            )
+        }
+        StakeOperation::Withdraw {
+            stake_account,
+            amount,
+            to,
+        } => {
+            let stake = parse_address(stake_account)?;
+            let to = match to {
+                Some(to) => parse_address(to)?,
+                None => *authority,
+            };
+            let lamports = match amount {
+                Some(amount) => to_lamports(*amount)?,
+                None => client.get_balance(&stake)?,
+            };
+
+            let mut plan = plan(
+                vec![stake_instruction::withdraw(
+                    &stake, authority, &to, lamports, None,
+                )],
+                stake,
+                false,
+            );
+            // Back into the wallet is not a spend; anywhere else is a transfer
+            if to != *authority {
+                plan.intent.spend = Some(Spend {
+                    symbol: "SOL".to_string(),
+                    mint: config.tokens.sol.clone(),
+                    amount: to_sol(lamports),
+                });
+                plan.intent.recipients = vec![to.to_string()];
+            }
+            plan
+        }
+        StakeOperation::Split {
+            stake_account,
+            amount,
+            seed,
+        } => {
+            let stake = parse_address(stake_account)?;
+            let lamports = to_lamports(*amount)?;
+            let space = StakeStateV2::size_of();
+            let reserve = client.get_minimum_balance_for_rent_exemption(space)?;
+
+            // The split destination must already be rent exempt, so create it
+            // funded, sized and owned by the stake program in one instruction
+            let (address, create, new_account_signs) = match seed {
+                Some(seed) => {
+                    let address = seeded_address(authority, seed)?;
+                    let create = system_instruction::create_account_with_seed(
+                        authority,
+                        &address,
+                        authority,
+                        seed,
+                        reserve,
+                        space as u64,
+                        &STAKE_PROGRAM_ID,
+                    );
+                    (address, create, false)
+                }
+                None => {
+                    let address = new_account.pubkey();
+                    let create = system_instruction::create_account(
+                        authority,
+                        &address,
+                        reserve,
+                        space as u64,
+                        &STAKE_PROGRAM_ID,
+                    );
+                    (address, create, true)
+                }
+            };
+
+            // The library's split also allocates and assigns; only the last instruction splits
+            let split = stake_instruction::split(&stake, authority, lamports, &address)
+                .pop()
+                .expect("split builds at least one instruction");
+            plan(vec![create, split], address, new_account_signs)
+        }
+        StakeOperation::Merge {
+            stake_account,
+            source_account,
+        } => {
+            let destination = parse_address(stake_account)?;
+            let source = parse_address(source_account)?;
+            plan(
+                stake_instruction::merge(&destination, &source, authority),
+                destination,
+                false,
+            )
+        }
+    })
+}
+
+/// Stake accounts where `owner` is staker or withdrawer, with their
+/// activation state and the last `epochs` epochs of rewards
+pub async fn list_accounts(
+    config: &Config,
+    owner: &Pubkey,
+    epochs: usize,
+) -> Result<StakeAccountsResponse> {
+    let client = rpc::client(config);
+
+    app_log!(info, "Fetching stake accounts for {}", owner);
+
+    let mut accounts = BTreeMap::new();
+    for offset in [STAKER_OFFSET, WITHDRAWER_OFFSET] {
+        let found = client.get_program_accounts_with_config(
+            &STAKE_PROGRAM_ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    offset,
+                    owner.as_ref(),
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        accounts.extend(found);
+    }
+
+    let epoch = client.get_epoch_info()?.epoch;
+    let history: StakeHistory =
+        bincode::deserialize(&client.get_account_data(&solana_sdk::sysvar::stake_history::ID)?)?;
+    let addresses: Vec<Pubkey> = accounts.keys().copied().collect();
+    let mut rewards = fetch_rewards(&client, &addresses, epoch, epochs);
+
+    let mut infos = Vec::new();
+    for (address, account) in accounts {
+        let state: StakeStateV2 = match bincode::deserialize(&account.data) {
+            Ok(state) => state,
+            Err(e) => {
+                app_log!(warn, "Skipping unreadable stake account {}: {}", address, e);
+                continue;
+            }
+        };
+        let Some(meta) = state.meta() else {
+            continue;
+        };
+
+        let mut info = StakeAccountInfo {
+            address: address.to_string(),
+            balance: to_sol(account.lamports),
+            state: "initialized".to_string(),
+            vote_account: None,
+            delegated_stake: None,
+            active_stake: None,
+            activation_epoch: None,
+            deactivation_epoch: None,
+            staker: meta.authorized.staker.to_string(),
+            withdrawer: meta.authorized.withdrawer.to_string(),
+            rewards: rewards.remove(&address).unwrap_or_default(),
+        };
+
+        if let Some(delegation) = state.delegation() {
+            let status = delegation.stake_activating_and_deactivating(epoch, &history, None);
+            info.state = if status.deactivating > 0 {
+                "deactivating"
+            } else if status.activating > 0 {
+                "activating"
+            } else if status.effective > 0 {
+                "active"
+            } else {
+                "inactive"
+            }
+            .to_string();
+            info.vote_account = Some(delegation.voter_pubkey.to_string());
+            info.delegated_stake = Some(to_sol(delegation.stake));
+            info.active_stake = Some(to_sol(status.effective));
+            info.activation_epoch = Some(delegation.activation_epoch);
+            info.deactivation_epoch = (delegation.deactivation_epoch != u64::MAX)
+                .then_some(delegation.deactivation_epoch);
+        }
+
+        infos.push(info);
+    }
+
+    app_log!(info, "Found {} stake accounts", infos.len());
+    Ok(StakeAccountsResponse {
+        pubkey: owner.to_string(),
+        epoch,
+        count: infos.len(),
+        accounts: infos,
+    })
+}
+
+// One getInflationReward call per epoch, newest first. Nodes without the
+// older blocks fail the call, which just ends the history there.
+fn fetch_rewards(
+    client: &RpcClient,
+    addresses: &[Pubkey],
+    current_epoch: u64,
+    epochs: usize,
+) -> HashMap<Pubkey, Vec<StakeReward>> {
+    let mut rewards: HashMap<Pubkey, Vec<StakeReward>> = HashMap::new();
+    if addresses.is_empty() {
+        return rewards;
+    }
+
+    let oldest = current_epoch.saturating_sub(epochs as u64);
+    for epoch in (oldest..current_epoch).rev() {
+        let found = match client.get_inflation_reward(addresses, Some(epoch)) {
+            Ok(found) => found,
+            Err(e) => {
+                app_log!(debug, "No inflation rewards for epoch {}: {}", epoch, e);
+                break;
+            }
+        };
+
+        for (address, reward) in addresses.iter().zip(found) {
+            if let Some(reward) = reward {
+                rewards.entry(*address).or_default().push(StakeReward {
+                    epoch: reward.epoch,
+                    amount: to_sol(reward.amount),
+                    post_balance: to_sol(reward.post_balance),
+                    commission: reward.commission,
+                });
+            }
+        }
+    }
+    rewards
+}
+
+fn seeded_address(base: &Pubkey, seed: &str) -> Result<Pubkey> {
+    if seed.len() > MAX_SEED_LEN {
+        return Err(invalid(format!(
+            "Seed '{}' is longer than {} bytes",
+            seed, MAX_SEED_LEN
+        )));
+    }
+    Pubkey::create_with_seed(base, seed, &STAKE_PROGRAM_ID)
+        .map_err(|e| invalid(format!("Invalid seed '{}': {}", seed, e)))
+}
+
+fn parse_address(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| {
+        SolanaClientError::InvalidAddress {
+            address: address.to_string(),
+        }
+        .into()
+    })
+}
+
+pub fn to_lamports(amount: f64) -> Result<u64> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(invalid(format!("Amount must be positive, got {}", amount)));
+    }
+    // Round: 2.01 * 10^9 is 2009999999.9999998 in floating point
+    Ok((amount * LAMPORTS_PER_SOL as f64).round() as u64)
+}
+
+pub fn to_sol(lamports: u64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    SolanaClientError::InvalidRequest { message }.into()
+}
@@ -0,0 +1,699 @@
+//! Client side of the SPL stake-pool program: account layouts, addresses and
+//! the instructions `liquid_stake.rs` needs. Pools are read from whichever
+//! program owns them, so forks of the program with the same layout work too.
+
+use crate::error::SolanaClientError;
+use anyhow::Result;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use solana_stake_interface::program::ID as STAKE_PROGRAM_ID;
+
+// StakePoolInstruction discriminants (Borsh, one byte)
+const WITHDRAW_STAKE: u8 = 10;
+const DEPOSIT_SOL: u8 = 14;
+const WITHDRAW_SOL: u8 = 16;
+
+const ACCOUNT_TYPE_STAKE_POOL: u8 = 1;
+const ACCOUNT_TYPE_VALIDATOR_LIST: u8 = 2;
+const VALIDATOR_STAKE_INFO_LEN: usize = 73;
+
+/// `numerator / denominator` of the amount
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fee {
+    pub denominator: u64,
+    pub numerator: u64,
+}
+
+impl Fee {
+    /// Fee on `amount`, rounded up like the program does
+    pub fn apply(&self, amount: u64) -> u64 {
+        if self.denominator == 0 {
+            return 0;
+        }
+        let denominator = self.denominator as u128;
+        (amount as u128 * self.numerator as u128).div_ceil(denominator) as u64
+    }
+}
+
+/// The parts of a pool's state needed to quote and build deposits and withdrawals
+#[derive(Debug, Clone)]
+pub struct StakePool {
+    pub validator_list: Pubkey,
+    pub reserve_stake: Pubkey,
+    pub pool_mint: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub token_program_id: Pubkey,
+    pub total_lamports: u64,
+    pub pool_token_supply: u64,
+    pub last_update_epoch: u64,
+    pub preferred_withdraw_validator: Option<Pubkey>,
+    pub stake_withdrawal_fee: Fee,
+    pub sol_deposit_authority: Option<Pubkey>,
+    pub sol_deposit_fee: Fee,
+    pub sol_referral_fee: u8, // Percent of the deposit fee paid to the referrer
+    pub sol_withdraw_authority: Option<Pubkey>,
+    pub sol_withdrawal_fee: Fee,
+}
+
+impl StakePool {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        if r.u8()? != ACCOUNT_TYPE_STAKE_POOL {
+            return Err(layout_error("not a stake pool account"));
+        }
+        r.skip(32 * 3)?; // manager, staker, stake_deposit_authority
+        r.skip(1)?; // stake_withdraw_bump_seed
+        let validator_list = r.pubkey()?;
+        let reserve_stake = r.pubkey()?;
+        let pool_mint = r.pubkey()?;
+        let manager_fee_account = r.pubkey()?;
+        let token_program_id = r.pubkey()?;
+        let total_lamports = r.u64()?;
+        let pool_token_supply = r.u64()?;
+        let last_update_epoch = r.u64()?;
+        r.skip(8 + 8 + 32)?; // lockup
+        r.fee()?; // epoch_fee
+        r.future_fee()?; // next_epoch_fee
+        r.option_pubkey()?; // preferred_deposit_validator_vote_address
+        let preferred_withdraw_validator = r.option_pubkey()?;
+        r.fee()?; // stake_deposit_fee
+        let stake_withdrawal_fee = r.fee()?;
+        r.future_fee()?; // next_stake_withdrawal_fee
+        r.u8()?; // stake_referral_fee
+        let sol_deposit_authority = r.option_pubkey()?;
+        let sol_deposit_fee = r.fee()?;
+        let sol_referral_fee = r.u8()?;
+        let sol_withdraw_authority = r.option_pubkey()?;
+        let sol_withdrawal_fee = r.fee()?;
+
+        Ok(Self {
+            validator_list,
+            reserve_stake,
+            pool_mint,
+            manager_fee_account,
+            token_program_id,
+            total_lamports,
+            pool_token_supply,
+            last_update_epoch,
+            preferred_withdraw_validator,
+            stake_withdrawal_fee,
+            sol_deposit_authority,
+            sol_deposit_fee,
+            sol_referral_fee,
+            sol_withdraw_authority,
+            sol_withdrawal_fee,
+        })
+    }
+
+    /// Pool tokens minted for a deposit of `lamports`, before fees
+    pub fn pool_tokens_for_deposit(&self, lamports: u64) -> u64 {
+        if self.pool_token_supply == 0 || self.total_lamports == 0 {
+            return lamports;
+        }
+        (lamports as u128 * self.pool_token_supply as u128 / self.total_lamports as u128) as u64
+    }
+
+    /// Lamports paid out for burning `pool_tokens`, after fees
+    pub fn lamports_for_withdrawal(&self, pool_tokens: u64) -> u64 {
+        if self.pool_token_supply == 0 {
+            return 0;
+        }
+        (pool_tokens as u128 * self.total_lamports as u128 / self.pool_token_supply as u128) as u64
+    }
+
+    /// Pool tokens a SOL deposit credits to the depositor, who is also the referrer
+    pub fn sol_deposit_output(&self, lamports: u64) -> u64 {
+        let minted = self.pool_tokens_for_deposit(lamports);
+        let fee = self.sol_deposit_fee.apply(minted);
+        let referral = fee * self.sol_referral_fee as u64 / 100;
+        minted - fee.min(minted) + referral
+    }
+}
+
+/// One validator's entry in the pool's validator list
+#[derive(Debug, Clone)]
+pub struct ValidatorStake {
+    pub active_stake_lamports: u64,
+    pub validator_seed_suffix: u32,
+    pub active: bool,
+    pub vote_account: Pubkey,
+}
+
+pub fn decode_validator_list(data: &[u8]) -> Result<Vec<ValidatorStake>> {
+    let mut r = Reader::new(data);
+    if r.u8()? != ACCOUNT_TYPE_VALIDATOR_LIST {
+        return Err(layout_error("not a validator list account"));
+    }
+    r.skip(4)?; // max_validators
+    let count = r.u32()? as usize;
+
+    let mut validators = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut entry = Reader::new(r.take(VALIDATOR_STAKE_INFO_LEN)?);
+        let active_stake_lamports = entry.u64()?;
+        entry.skip(8 + 8 + 8 + 4)?; // transient lamports, last update, transient seed, unused
+        let validator_seed_suffix = entry.u32()?;
+        let active = entry.u8()? == 0;
+        let vote_account = entry.pubkey()?;
+        validators.push(ValidatorStake {
+            active_stake_lamports,
+            validator_seed_suffix,
+            active,
+            vote_account,
+        });
+    }
+    Ok(validators)
+}
+
+/// Authority that signs for the pool's stake and mint
+pub fn withdraw_authority(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pool.as_ref(), b"withdraw"], program_id).0
+}
+
+/// The pool's stake account delegated to `validator`
+pub fn validator_stake_address(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    validator: &ValidatorStake,
+) -> Pubkey {
+    let suffix = validator.validator_seed_suffix.to_le_bytes();
+    let seed: &[u8] = match validator.validator_seed_suffix {
+        0 => &[],
+        _ => &suffix,
+    };
+    Pubkey::find_program_address(
+        &[validator.vote_account.as_ref(), pool.as_ref(), seed],
+        program_id,
+    )
+    .0
+}
+
+/// Deposit SOL from `from` and mint pool tokens to `pool_tokens_to`, which also takes the referral fee
+pub fn deposit_sol(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    pool: &StakePool,
+    from: &Pubkey,
+    pool_tokens_to: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pool_address, false),
+            AccountMeta::new_readonly(withdraw_authority(program_id, pool_address), false),
+            AccountMeta::new(pool.reserve_stake, false),
+            AccountMeta::new(*from, true),
+            AccountMeta::new(*pool_tokens_to, false),
+            AccountMeta::new(pool.manager_fee_account, false),
+            AccountMeta::new(*pool_tokens_to, false),
+            AccountMeta::new(pool.pool_mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new_readonly(pool.token_program_id, false),
+        ],
+        data: amount_data(DEPOSIT_SOL, lamports),
+    }
+}
+
+/// Burn `pool_tokens` owned by `owner` for SOL from the reserve
+pub fn withdraw_sol(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    pool: &StakePool,
+    owner: &Pubkey,
+    pool_tokens_from: &Pubkey,
+    pool_tokens: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pool_address, false),
+            AccountMeta::new_readonly(withdraw_authority(program_id, pool_address), false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*pool_tokens_from, false),
+            AccountMeta::new(pool.reserve_stake, false),
+            AccountMeta::new(*owner, false),
+            AccountMeta::new(pool.manager_fee_account, false),
+            AccountMeta::new(pool.pool_mint, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(sysvar::stake_history::ID, false),
+            AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+            AccountMeta::new_readonly(pool.token_program_id, false),
+        ],
+        data: amount_data(WITHDRAW_SOL, pool_tokens),
+    }
+}
+
+/// Burn `pool_tokens` for active stake split off `stake_to_split` into the
+/// uninitialized `stake_to_receive`, with `owner` as its new authority
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_stake(
+    program_id: &Pubkey,
+    pool_address: &Pubkey,
+    pool: &StakePool,
+    stake_to_split: &Pubkey,
+    stake_to_receive: &Pubkey,
+    owner: &Pubkey,
+    pool_tokens_from: &Pubkey,
+    pool_tokens: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pool_address, false),
+            AccountMeta::new(pool.validator_list, false),
+            AccountMeta::new_readonly(withdraw_authority(program_id, pool_address), false),
+            AccountMeta::new(*stake_to_split, false),
+            AccountMeta::new(*stake_to_receive, false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*pool_tokens_from, false),
+            AccountMeta::new(pool.manager_fee_account, false),
+            AccountMeta::new(pool.pool_mint, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(pool.token_program_id, false),
+            AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+        ],
+        data: amount_data(WITHDRAW_STAKE, pool_tokens),
+    }
+}
+
+fn amount_data(discriminant: u8, amount: u64) -> Vec<u8> {
+    let mut data = vec![discriminant];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+fn layout_error(message: &str) -> anyhow::Error {
+    SolanaClientError::InvalidRequest {
+        message: format!("Unexpected stake pool data: {}", message),
+    }
+    .into()
+}
+
+// Borsh reader for the few types the pool layout uses
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(layout_error("account data too short"));
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::try_from(self.take(32)?)?)
+    }
+
+    fn option_pubkey(&mut self) -> Result<Option<Pubkey>> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.pubkey()?)),
+        }
+    }
+
+    fn fee(&mut self) -> Result<Fee> {
+        Ok(Fee {
+            denominator: self.u64()?,
+            numerator: self.u64()?,
+        })
+    }
+
+    // FutureEpoch<Fee>: None, or the fee for the next one or two epochs
+    fn future_fee(&mut self) -> Result<Option<Fee>> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.fee()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Borsh writer for fixtures, field by field as the program lays them out
+    #[derive(Default)]
+    struct Writer(Vec<u8>);
+
+    impl Writer {
+        fn u8(&mut self, value: u8) -> &mut Self {
+            self.0.push(value);
+            self
+        }
+
+        fn u32(&mut self, value: u32) -> &mut Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn u64(&mut self, value: u64) -> &mut Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn pubkey(&mut self, value: &Pubkey) -> &mut Self {
+            self.0.extend_from_slice(value.as_ref());
+            self
+        }
+
+        fn option_pubkey(&mut self, value: Option<&Pubkey>) -> &mut Self {
+            match value {
+                None => self.u8(0),
+                Some(value) => self.u8(1).pubkey(value),
+            }
+        }
+
+        fn fee(&mut self, denominator: u64, numerator: u64) -> &mut Self {
+            self.u64(denominator).u64(numerator)
+        }
+    }
+
+    struct Keys {
+        validator_list: Pubkey,
+        reserve_stake: Pubkey,
+        pool_mint: Pubkey,
+        manager_fee_account: Pubkey,
+        preferred_withdraw: Pubkey,
+        sol_deposit_authority: Pubkey,
+    }
+
+    fn keys() -> Keys {
+        Keys {
+            validator_list: Pubkey::new_unique(),
+            reserve_stake: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            manager_fee_account: Pubkey::new_unique(),
+            preferred_withdraw: Pubkey::new_unique(),
+            sol_deposit_authority: Pubkey::new_unique(),
+        }
+    }
+
+    // Every optional field set, so a wrong skip shifts the fields after it
+    fn pool_data(keys: &Keys) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.u8(ACCOUNT_TYPE_STAKE_POOL)
+            .pubkey(&Pubkey::new_unique()) // manager
+            .pubkey(&Pubkey::new_unique()) // staker
+            .pubkey(&Pubkey::new_unique()) // stake_deposit_authority
+            .u8(255) // stake_withdraw_bump_seed
+            .pubkey(&keys.validator_list)
+            .pubkey(&keys.reserve_stake)
+            .pubkey(&keys.pool_mint)
+            .pubkey(&keys.manager_fee_account)
+            .pubkey(&spl_token::id())
+            .u64(1_100_000_000_000) // total_lamports
+            .u64(1_000_000_000_000) // pool_token_supply
+            .u64(612) // last_update_epoch
+            .u64(0)
+            .u64(0)
+            .pubkey(&Pubkey::default()) // lockup
+            .fee(100, 5) // epoch_fee
+            .u8(2)
+            .fee(100, 6) // next_epoch_fee: Two
+            .option_pubkey(Some(&Pubkey::new_unique())) // preferred_deposit_validator
+            .option_pubkey(Some(&keys.preferred_withdraw))
+            .fee(1000, 1) // stake_deposit_fee
+            .fee(1000, 3) // stake_withdrawal_fee
+            .u8(1)
+            .fee(1000, 4) // next_stake_withdrawal_fee: One
+            .u8(50) // stake_referral_fee
+            .option_pubkey(Some(&keys.sol_deposit_authority))
+            .fee(10_000, 10) // sol_deposit_fee
+            .u8(40) // sol_referral_fee
+            .option_pubkey(None) // sol_withdraw_authority
+            .fee(10_000, 30) // sol_withdrawal_fee
+            .u8(0) // next_sol_withdrawal_fee: None
+            .u64(1_000_000_000_000) // last_epoch_pool_token_supply
+            .u64(1_100_000_000_000); // last_epoch_total_lamports
+        w.0
+    }
+
+    fn pool(keys: &Keys) -> StakePool {
+        StakePool::decode(&pool_data(keys)).unwrap()
+    }
+
+    // (address, writable, signer) of each account, in order
+    fn metas(instruction: &Instruction) -> Vec<(Pubkey, bool, bool)> {
+        instruction
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_writable, meta.is_signer))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_stake_pool() {
+        let keys = keys();
+        let pool = pool(&keys);
+
+        assert_eq!(pool.validator_list, keys.validator_list);
+        assert_eq!(pool.reserve_stake, keys.reserve_stake);
+        assert_eq!(pool.pool_mint, keys.pool_mint);
+        assert_eq!(pool.manager_fee_account, keys.manager_fee_account);
+        assert_eq!(pool.token_program_id, spl_token::id());
+        assert_eq!(pool.total_lamports, 1_100_000_000_000);
+        assert_eq!(pool.pool_token_supply, 1_000_000_000_000);
+        assert_eq!(pool.last_update_epoch, 612);
+        assert_eq!(
+            pool.preferred_withdraw_validator,
+            Some(keys.preferred_withdraw)
+        );
+        assert_eq!(
+            (
+                pool.stake_withdrawal_fee.denominator,
+                pool.stake_withdrawal_fee.numerator
+            ),
+            (1000, 3)
+        );
+        assert_eq!(pool.sol_deposit_authority, Some(keys.sol_deposit_authority));
+        assert_eq!(
+            (
+                pool.sol_deposit_fee.denominator,
+                pool.sol_deposit_fee.numerator
+            ),
+            (10_000, 10)
+        );
+        assert_eq!(pool.sol_referral_fee, 40);
+        assert_eq!(pool.sol_withdraw_authority, None);
+        assert_eq!(
+            (
+                pool.sol_withdrawal_fee.denominator,
+                pool.sol_withdrawal_fee.numerator
+            ),
+            (10_000, 30)
+        );
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let keys = keys();
+        let mut data = pool_data(&keys);
+        data[0] = ACCOUNT_TYPE_VALIDATOR_LIST;
+        assert!(StakePool::decode(&data).is_err());
+
+        let data = pool_data(&keys);
+        assert!(StakePool::decode(&data[..200]).is_err());
+    }
+
+    #[test]
+    fn quotes_sol_deposit() {
+        let pool = pool(&keys());
+
+        // 1 SOL mints 1/1.1 pool tokens; 0.1% fee, 40% of it back as referral
+        let minted = pool.pool_tokens_for_deposit(1_000_000_000);
+        assert_eq!(minted, 909_090_909);
+        let fee = 909_091; // Rounded up
+        assert_eq!(
+            pool.sol_deposit_output(1_000_000_000),
+            minted - fee + fee * 40 / 100
+        );
+        assert_eq!(pool.lamports_for_withdrawal(1_000_000_000), 1_100_000_000);
+    }
+
+    #[test]
+    fn decodes_validator_list() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let mut w = Writer::default();
+        w.u8(ACCOUNT_TYPE_VALIDATOR_LIST).u32(10).u32(2);
+        for (vote, lamports, suffix, status) in [(&first, 5_000, 0, 0), (&second, 7_000, 3, 1)] {
+            w.u64(lamports) // active_stake_lamports
+                .u64(1) // transient_stake_lamports
+                .u64(612) // last_update_epoch
+                .u64(9) // transient_seed_suffix
+                .u32(0) // unused
+                .u32(suffix) // validator_seed_suffix
+                .u8(status)
+                .pubkey(vote);
+        }
+
+        let validators = decode_validator_list(&w.0).unwrap();
+        assert_eq!(validators.len(), 2);
+        assert_eq!(validators[0].vote_account, first);
+        assert_eq!(validators[0].active_stake_lamports, 5_000);
+        assert_eq!(validators[0].validator_seed_suffix, 0);
+        assert!(validators[0].active);
+        assert_eq!(validators[1].vote_account, second);
+        assert_eq!(validators[1].active_stake_lamports, 7_000);
+        assert_eq!(validators[1].validator_seed_suffix, 3);
+        assert!(!validators[1].active);
+
+        assert!(decode_validator_list(&w.0[..w.0.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn builds_deposit_sol() {
+        let keys = keys();
+        let pool = pool(&keys);
+        let (program, address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = deposit_sol(&program, &address, &pool, &from, &to, 42);
+        assert_eq!(ix.program_id, program);
+        assert_eq!(ix.data, [&[DEPOSIT_SOL][..], &42u64.to_le_bytes()].concat());
+        assert_eq!(
+            metas(&ix),
+            vec![
+                (address, true, false),
+                (withdraw_authority(&program, &address), false, false),
+                (keys.reserve_stake, true, false),
+                (from, true, true),
+                (to, true, false),
+                (keys.manager_fee_account, true, false),
+                (to, true, false), // Referrer
+                (keys.pool_mint, true, false),
+                (solana_system_interface::program::ID, false, false),
+                (spl_token::id(), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_withdraw_sol() {
+        let keys = keys();
+        let pool = pool(&keys);
+        let (program, address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (owner, from) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = withdraw_sol(&program, &address, &pool, &owner, &from, 42);
+        assert_eq!(
+            ix.data,
+            [&[WITHDRAW_SOL][..], &42u64.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            metas(&ix),
+            vec![
+                (address, true, false),
+                (withdraw_authority(&program, &address), false, false),
+                (owner, false, true), // Transfer authority
+                (from, true, false),
+                (keys.reserve_stake, true, false),
+                (owner, true, false), // Lamports destination
+                (keys.manager_fee_account, true, false),
+                (keys.pool_mint, true, false),
+                (sysvar::clock::ID, false, false),
+                (sysvar::stake_history::ID, false, false),
+                (STAKE_PROGRAM_ID, false, false),
+                (spl_token::id(), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_withdraw_stake() {
+        let keys = keys();
+        let pool = pool(&keys);
+        let (program, address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (split, receive) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (owner, from) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = withdraw_stake(
+            &program, &address, &pool, &split, &receive, &owner, &from, 42,
+        );
+        assert_eq!(
+            ix.data,
+            [&[WITHDRAW_STAKE][..], &42u64.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            metas(&ix),
+            vec![
+                (address, true, false),
+                (keys.validator_list, true, false),
+                (withdraw_authority(&program, &address), false, false),
+                (split, true, false),
+                (receive, true, false),
+                (owner, false, false), // New stake authority
+                (owner, false, true),  // Transfer authority
+                (from, true, false),
+                (keys.manager_fee_account, true, false),
+                (keys.pool_mint, true, false),
+                (sysvar::clock::ID, false, false),
+                (spl_token::id(), false, false),
+                (STAKE_PROGRAM_ID, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn derives_validator_stake_address() {
+        let (program, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut validator = ValidatorStake {
+            active_stake_lamports: 0,
+            validator_seed_suffix: 0,
+            active: true,
+            vote_account: Pubkey::new_unique(),
+        };
+
+        let unsuffixed = Pubkey::find_program_address(
+            &[validator.vote_account.as_ref(), pool.as_ref()],
+            &program,
+        )
+        .0;
+        assert_eq!(
+            validator_stake_address(&program, &pool, &validator),
+            unsuffixed
+        );
+
+        validator.validator_seed_suffix = 7;
+        let suffixed = Pubkey::find_program_address(
+            &[
+                validator.vote_account.as_ref(),
+                pool.as_ref(),
+                &7u32.to_le_bytes(),
+            ],
+            &program,
+        )
+        .0;
+        assert_eq!(
+            validator_stake_address(&program, &pool, &validator),
+            suffixed
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use crate::app_log;
-use crate::{config::Config, error::SolanaClientError};
+use crate::{config::Config, error::SolanaClientError, metrics};
 use anyhow::Result;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TokenInfo {
@@ -17,6 +19,7 @@ pub struct TokenInfo {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct TokenListResponse {
     pub name: String,
     #[serde(rename = "logoURI")]
@@ -29,28 +32,46 @@ pub struct TokenListResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 pub struct Version {
     pub major: u32,
     pub minor: u32,
     pub patch: u32,
 }
 
-pub async fn get_all_tokens(_config: &Config) -> Result<Vec<TokenInfo>> {
+// Jupiter's list is several MB; keep it for `jupiter.token_list_ttl_secs`
+static TOKEN_LIST: Mutex<Option<(Instant, Vec<TokenInfo>)>> = Mutex::new(None);
+
+pub async fn get_all_tokens(config: &Config) -> Result<Vec<TokenInfo>> {
+    let ttl = Duration::from_secs(config.jupiter.token_list_ttl_secs);
+    if let Some((fetched_at, tokens)) = TOKEN_LIST.lock().unwrap().as_ref()
+        && fetched_at.elapsed() < ttl
+    {
+        metrics::inc(metrics::TOKEN_CACHE_HITS, &[]);
+        return Ok(tokens.clone());
+    }
+    metrics::inc(metrics::TOKEN_CACHE_MISSES, &[]);
+
     let client = Client::new();
     let url = "https://token.jup.ag/all";
 
     app_log!(info, "Fetching all tokens from Jupiter");
 
-    let response = client.get(url).send().await?;
+    let tokens: Vec<TokenInfo> = metrics::time_jupiter("tokens", async {
+        let response = client.get(url).send().await?;
 
-    if !response.status().is_success() {
-        return Err(SolanaClientError::NetworkError {
-            source: "Failed to fetch token list".into(),
+        if !response.status().is_success() {
+            return Err(SolanaClientError::NetworkError {
+                source: "Failed to fetch token list".into(),
+            }
+            .into());
         }
-        .into());
-    }
 
-    let tokens: Vec<TokenInfo> = response.json().await?;
+        Ok(response.json().await?)
+    })
+    .await?;
+
+    *TOKEN_LIST.lock().unwrap() = Some((Instant::now(), tokens.clone()));
     Ok(tokens)
 }
 
@@ -130,6 +151,7 @@ pub async fn get_token_info(config: &Config, query: &str) -> Result<Option<Token
     Ok(tokens.into_iter().next())
 }
 
+#[allow(dead_code)]
 pub async fn get_popular_tokens(config: &Config) -> Result<Vec<TokenInfo>> {
     let all_tokens = get_all_tokens(config).await?;
 
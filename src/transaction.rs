@@ -1,22 +1,34 @@
 use crate::app_log;
-use crate::{config::Config, error::SolanaClientError, wallet::load_keypair};
+use crate::audit::Audit;
+use crate::policy::Intent;
+use crate::{config::Config, error::SolanaClientError, metrics, rpc, wallet::load_keypair};
 use anyhow::Result;
-use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
 use solana_sdk::{
-    message::Message,
-    signature::Keypair,
+    instruction::Instruction,
+    message::{Message, VersionedMessage},
+    signature::{Keypair, Signature},
     signer::Signer,
-    system_instruction,
     transaction::{Transaction, VersionedTransaction},
 };
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
+use serde_json::{Value, json};
 use solana_sdk::pubkey::Pubkey;
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_system_interface::instruction as system_instruction;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransactionEncoding,
+};
+use utoipa::ToSchema;
+
+// Longest memo that still fits in a single transfer transaction
+const MAX_MEMO_BYTES: usize = 566;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct TransactionHistory {
     pub signature: String,
     pub status: TransactionStatus,
@@ -27,24 +39,34 @@ pub struct TransactionHistory {
     pub amount: Option<f64>,
     pub token_symbol: Option<String>,
     pub transaction_type: TransactionType,
+    pub memo: Option<String>, // SPL Memo text; several memos are joined with "; "
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl TransactionHistory {
+    /// Whether the memo contains `query`, ignoring case
+    pub fn memo_contains(&self, query: &str) -> bool {
+        self.memo
+            .as_ref()
+            .is_some_and(|memo| memo.to_lowercase().contains(&query.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum TransactionStatus {
     Success,
     Failed,
     Pending,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum ConfirmationStatus {
     Processed,
     Confirmed,
     Finalized,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum TransactionType {
     Transfer,
     TokenTransfer,
@@ -52,237 +74,448 @@ pub enum TransactionType {
     Unknown,
 }
 
-pub async fn create_transaction(config: &Config, to_address: &str, amount: f64) -> Result<String> {
-    let from_keypair = load_keypair(config).await?;
-    let client = RpcClient::new(&config.solana.rpc_url);
-
-    // Parse recipient address
-    let to_pubkey =
-        Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
-            address: to_address.to_string(),
-        })?;
-
-    // Check balance
-    let current_balance = crate::wallet::get_balance(config).await?;
-    if current_balance < amount {
-        return Err(SolanaClientError::InsufficientBalance {
-            current: current_balance,
-            required: amount,
+pub async fn create_transaction(
+    config: &Config,
+    actor: &str,
+    to_address: &str,
+    amount: f64,
+    memo: Option<&str>,
+) -> Result<String> {
+    let mut audit = Audit::new(
+        actor,
+        "transfer",
+        json!({ "token": "SOL", "to": to_address, "amount": amount, "memo": memo }),
+    );
+    let result: Result<String> = async {
+        let from_keypair = load_keypair(config).await?;
+        let client = rpc::client(config);
+
+        // Parse recipient address
+        let to_pubkey =
+            Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
+                address: to_address.to_string(),
+            })?;
+        let lamports = crate::stake::to_lamports(amount)?;
+
+        // Check balance
+        let current_balance = crate::wallet::get_balance(config).await?;
+        if current_balance < amount {
+            return Err(SolanaClientError::InsufficientBalance {
+                current: current_balance,
+                required: amount,
+            }
+            .into());
         }
-        .into());
-    }
 
-    let lamports = (amount * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+        // Create transfer instruction
+        let instructions = with_memo(
+            memo,
+            &from_keypair.pubkey(),
+            system_instruction::transfer(&from_keypair.pubkey(), &to_pubkey, lamports),
+        )?;
+
+        audit.check_policy(
+            config,
+            &Intent {
+                programs: instructions.iter().map(|ix| ix.program_id).collect(),
+                ..Intent::sol_transfer(config, &from_keypair.pubkey(), to_address, amount)
+            },
+        )?;
 
-    app_log!(
-        info,
-        "Creating transaction: {} SOL to {}",
-        amount,
-        to_address
-    );
+        app_log!(
+            info,
+            "Creating transaction: {} SOL to {}",
+            amount,
+            to_address
+        );
 
-    // Create transfer instruction
-    let instruction = system_instruction::transfer(&from_keypair.pubkey(), &to_pubkey, lamports);
+        // Get recent blockhash
+        let recent_blockhash = client.get_latest_blockhash()?;
 
-    // Get recent blockhash
-    let recent_blockhash = client.get_latest_blockhash()?;
+        // Create transaction
+        let message = Message::new(&instructions, Some(&from_keypair.pubkey()));
+        let transaction = Transaction::new(&[&from_keypair], message, recent_blockhash);
+        audit.signature = Some(transaction.signatures[0].to_string());
 
-    // Create transaction
-    let message = Message::new(&[instruction], Some(&from_keypair.pubkey()));
-    let transaction = Transaction::new(&[&from_keypair], message, recent_blockhash);
+        // Serialize transaction for later use
+        let serialized_tx = bincode::serialize(&transaction)?;
+        let tx_string = bs58::encode(serialized_tx).into_string();
 
-    // Serialize transaction for later use
-    let serialized_tx = bincode::serialize(&transaction)?;
-    let tx_string = bs58::encode(serialized_tx).into_string();
+        app_log!(info, "Transaction signed: {}", transaction.signatures[0]);
 
-    app_log!(info, "✅ Transaction created successfully!");
-    app_log!(info, "📦 Transaction data: {}", tx_string);
-    app_log!(info, "💸 Amount: {} SOL", amount);
-    app_log!(info, "📍 To: {}", to_address);
+        Ok(tx_string)
+    }
+    .await;
 
-    Ok(tx_string)
+    audit.finish(config, result, "signed")
 }
 
 pub async fn prepare_sol_transfer(
     config: &Config,
+    actor: &str,
     payer_pubkey: &Pubkey,
     to_address: &str,
     amount: f64,
+    memo: Option<&str>,
 ) -> Result<(String, Vec<String>, String)> {
-    let client = RpcClient::new(&config.solana.rpc_url);
-
-    // Parse recipient address
-    let to_pubkey =
-        Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
-            address: to_address.to_string(),
-        })?;
-
-    // Check balance
-    let current_balance = crate::wallet::get_balance_for_pubkey(config, payer_pubkey).await?;
-    if current_balance < amount {
-        return Err(SolanaClientError::InsufficientBalance {
-            current: current_balance,
-            required: amount,
+    let mut audit = Audit::new(
+        actor,
+        "transfer",
+        json!({ "token": "SOL", "from": payer_pubkey.to_string(), "to": to_address, "amount": amount, "memo": memo }),
+    );
+    let result: Result<(String, Vec<String>, String)> = async {
+        let client = rpc::client(config);
+
+        // Parse recipient address
+        let to_pubkey =
+            Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
+                address: to_address.to_string(),
+            })?;
+        let lamports = crate::stake::to_lamports(amount)?;
+
+        // Check balance
+        let current_balance = crate::wallet::get_balance_for_pubkey(config, payer_pubkey).await?;
+        if current_balance < amount {
+            return Err(SolanaClientError::InsufficientBalance {
+                current: current_balance,
+                required: amount,
+            }
+            .into());
         }
-        .into());
-    }
 
-    let lamports = (amount * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+        // Create transfer instruction
+        let instructions = with_memo(
+            memo,
+            payer_pubkey,
+            system_instruction::transfer(payer_pubkey, &to_pubkey, lamports),
+        )?;
+
+        audit.check_policy(
+            config,
+            &Intent {
+                programs: instructions.iter().map(|ix| ix.program_id).collect(),
+                ..Intent::sol_transfer(config, payer_pubkey, to_address, amount)
+            },
+        )?;
 
-    app_log!(
-        info,
-        "Preparing SOL transfer: {} SOL from {} to {}",
-        amount,
-        payer_pubkey,
-        to_address
-    );
+        app_log!(
+            info,
+            "Preparing SOL transfer: {} SOL from {} to {}",
+            amount,
+            payer_pubkey,
+            to_address
+        );
 
-    // Create transfer instruction
-    let instruction = system_instruction::transfer(payer_pubkey, &to_pubkey, lamports);
+        // Get recent blockhash
+        let recent_blockhash = client.get_latest_blockhash()?;
 
-    // Get recent blockhash
-    let recent_blockhash = client.get_latest_blockhash()?;
+        // Create unsigned transaction message
+        let message = Message::new(&instructions, Some(payer_pubkey));
 
-    // Create unsigned transaction message
-    let message = Message::new(&[instruction], Some(payer_pubkey));
+        // Create unsigned transaction (with empty signatures)
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.message.recent_blockhash = recent_blockhash;
 
-    // Create unsigned transaction (with empty signatures)
-    let mut transaction = Transaction::new_unsigned(message);
-    transaction.message.recent_blockhash = recent_blockhash;
+        // Serialize unsigned transaction
+        let serialized_tx = bincode::serialize(&transaction)?;
+        let unsigned_tx_b64 = BASE64.encode(serialized_tx);
 
-    // Serialize unsigned transaction
-    let serialized_tx = bincode::serialize(&transaction)?;
-    let unsigned_tx_b64 = base64::encode(serialized_tx);
+        // Required signers (just the payer)
+        let required_signers = vec![payer_pubkey.to_string()];
 
-    // Required signers (just the payer)
-    let required_signers = vec![payer_pubkey.to_string()];
+        app_log!(info, "Unsigned transaction prepared");
 
-    app_log!(info, "Unsigned transaction prepared");
+        Ok((
+            unsigned_tx_b64,
+            required_signers,
+            recent_blockhash.to_string(),
+        ))
+    }
+    .await;
 
-    Ok((
-        unsigned_tx_b64,
-        required_signers,
-        recent_blockhash.to_string(),
-    ))
+    audit.finish(config, result, "prepared")
 }
 
+/// `payer`, when set, is the only fee payer accepted (wallet callers of the REST API)
 pub async fn submit_signed_transaction(
     config: &Config,
+    actor: &str,
     signed_transaction_b64: &str,
+    payer: Option<&Pubkey>,
 ) -> Result<String> {
-    let client = RpcClient::new(&config.solana.rpc_url);
-
-    app_log!(info, "Submitting signed transaction");
-
-    // Decode the signed transaction
-    let tx_bytes = base64::decode(signed_transaction_b64)?;
-
-    // Try to deserialize as both legacy and versioned transaction
-    let signature = if let Ok(transaction) = bincode::deserialize::<Transaction>(&tx_bytes) {
-        // Legacy transaction
-        client.send_and_confirm_transaction(&transaction)?
-    } else if let Ok(versioned_tx) = bincode::deserialize::<VersionedTransaction>(&tx_bytes) {
-        // Versioned transaction
-        client.send_and_confirm_transaction(&versioned_tx)?
-    } else {
-        return Err(SolanaClientError::TransactionFailed {
-            reason: "Invalid transaction format".to_string(),
+    let mut audit = Audit::new(actor, "submit", Value::Null);
+    let result: Result<String> = async {
+        let client = rpc::client(config);
+
+        app_log!(info, "Submitting signed transaction");
+
+        // Decode the signed transaction
+        let tx_bytes = BASE64.decode(signed_transaction_b64).map_err(|e| {
+            SolanaClientError::InvalidRequest {
+                message: format!("Transaction is not valid base64: {}", e),
+            }
+        })?;
+
+        // Try to deserialize as both legacy and versioned transaction
+        let signature = if let Ok(transaction) = bincode::deserialize::<Transaction>(&tx_bytes) {
+            // Legacy transaction
+            audit.signature = transaction.signatures.first().map(ToString::to_string);
+            let message = VersionedMessage::Legacy(transaction.message.clone());
+            check_fee_payer(&message, payer)?;
+            audit.check_submission(config, &client, &message)?;
+            client.send_and_confirm_transaction(&transaction)?
+        } else if let Ok(versioned_tx) = bincode::deserialize::<VersionedTransaction>(&tx_bytes) {
+            // Versioned transaction
+            audit.signature = versioned_tx.signatures.first().map(ToString::to_string);
+            check_fee_payer(&versioned_tx.message, payer)?;
+            audit.check_submission(config, &client, &versioned_tx.message)?;
+            client.send_and_confirm_transaction(&versioned_tx)?
+        } else {
+            return Err(SolanaClientError::InvalidRequest {
+                message: "Invalid transaction format".to_string(),
+            }
+            .into());
+        };
+
+        metrics::transaction_confirmed();
+        app_log!(info, "Transaction submitted: {}", signature);
+        Ok(signature.to_string())
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+fn check_fee_payer(message: &VersionedMessage, payer: Option<&Pubkey>) -> Result<()> {
+    match payer {
+        Some(payer) if message.static_account_keys().first() != Some(payer) => {
+            Err(SolanaClientError::PolicyViolation {
+                violations: vec![format!(
+                    "Only transactions paid by {} may be submitted",
+                    payer
+                )],
+            }
+            .into())
         }
-        .into());
-    };
+        _ => Ok(()),
+    }
+}
+
+/// Broadcast a transaction from `create_transaction` and wait for confirmation
+pub async fn send_transaction(config: &Config, actor: &str, tx_data: &str) -> Result<Signature> {
+    let mut audit = Audit::new(actor, "submit", Value::Null);
+    let result: Result<Signature> = async {
+        let client = rpc::client(config);
+
+        app_log!(info, "Sending transaction");
+
+        // Deserialize transaction
+        let transaction: Transaction = bs58::decode(tx_data)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bincode::deserialize(&bytes).ok())
+            .ok_or_else(|| SolanaClientError::InvalidRequest {
+                message: "Invalid transaction format".to_string(),
+            })?;
+        audit.signature = transaction.signatures.first().map(ToString::to_string);
+
+        audit.check_submission(
+            config,
+            &client,
+            &VersionedMessage::Legacy(transaction.message.clone()),
+        )?;
+
+        // Send transaction
+        match client.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => {
+                metrics::transaction_confirmed();
+                app_log!(info, "Transaction confirmed: {}", signature);
+                Ok(signature)
+            }
+            Err(e) => {
+                app_log!(error, "Transaction failed: {}", e);
+                Err(SolanaClientError::TransactionFailed {
+                    reason: format!("Send failed: {}", e),
+                }
+                .into())
+            }
+        }
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+/// Unsigned transaction for a wallet elsewhere to sign, as the prepare endpoints return it
+pub struct PreparedTransaction {
+    pub unsigned_transaction: String, // Base64 encoded
+    pub required_signers: Vec<String>,
+    pub recent_blockhash: String,
+}
+
+/// Build an unsigned legacy transaction paid by `payer`
+pub fn prepare_unsigned(
+    config: &Config,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<PreparedTransaction> {
+    let client = rpc::client(config);
+    let recent_blockhash = client.get_latest_blockhash()?;
+
+    let message = Message::new(instructions, Some(payer));
+    let required_signers = message.account_keys[..message.header.num_required_signatures as usize]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
 
-    app_log!(info, "Transaction submitted: {}", signature);
-    Ok(signature.to_string())
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction.message.recent_blockhash = recent_blockhash;
+
+    Ok(PreparedTransaction {
+        unsigned_transaction: BASE64.encode(bincode::serialize(&transaction)?),
+        required_signers,
+        recent_blockhash: recent_blockhash.to_string(),
+    })
 }
 
-pub async fn send_transaction(config: &Config, tx_data: &str) -> Result<()> {
-    let client = RpcClient::new(&config.solana.rpc_url);
+/// Sign with the wallet (plus any new accounts) and wait for confirmation.
+/// The caller has already checked the policy on `audit`.
+pub fn sign_and_send(
+    config: &Config,
+    audit: &mut Audit,
+    payer: &Keypair,
+    extra_signers: &[&Keypair],
+    instructions: &[Instruction],
+) -> Result<Signature> {
+    let client = rpc::client(config);
+    let recent_blockhash = client.get_latest_blockhash()?;
 
-    app_log!(info, "Sending transaction");
+    let mut signers = vec![payer];
+    signers.extend_from_slice(extra_signers);
 
-    // Deserialize transaction
-    let tx_bytes = bs58::decode(tx_data).into_vec()?;
-    let transaction: Transaction = bincode::deserialize(&tx_bytes)?;
+    let message = Message::new(instructions, Some(&payer.pubkey()));
+    let transaction = Transaction::new(&signers, message, recent_blockhash);
+    audit.signature = Some(transaction.signatures[0].to_string());
+    audit.count_spend(config)?;
 
-    // Send transaction
     match client.send_and_confirm_transaction(&transaction) {
         Ok(signature) => {
-            app_log!(info, "✅ Transaction sent successfully!");
-            app_log!(info, "🔗 Signature: {}", signature);
-
-            // Update balance
-            let new_balance = crate::wallet::get_balance(config).await?;
-            app_log!(info, "💰 New balance: {} SOL", new_balance);
+            metrics::transaction_confirmed();
+            app_log!(info, "Transaction confirmed: {}", signature);
+            Ok(signature)
         }
         Err(e) => {
             app_log!(error, "Transaction failed: {}", e);
-            return Err(SolanaClientError::TransactionFailed {
+            Err(SolanaClientError::TransactionFailed {
                 reason: format!("Send failed: {}", e),
             }
-            .into());
+            .into())
         }
     }
+}
 
-    Ok(())
+/// `transfer`, preceded by an SPL Memo instruction signed by `signer` when
+/// there is a memo. Solana Pay wallets put the memo right before the transfer too.
+pub fn with_memo(
+    memo: Option<&str>,
+    signer: &Pubkey,
+    transfer: Instruction,
+) -> Result<Vec<Instruction>> {
+    let Some(memo) = memo else {
+        return Ok(vec![transfer]);
+    };
+    if memo.is_empty() || memo.len() > MAX_MEMO_BYTES {
+        return Err(SolanaClientError::InvalidRequest {
+            message: format!(
+                "Memo must be 1 to {} bytes, got {}",
+                MAX_MEMO_BYTES,
+                memo.len()
+            ),
+        }
+        .into());
+    }
+
+    Ok(vec![
+        spl_memo::build_memo(memo.as_bytes(), &[signer]),
+        transfer,
+    ])
 }
 
+#[allow(dead_code)]
 pub async fn create_transaction_with_keypair(
     config: &Config,
+    actor: &str,
     to_address: &str,
     amount: f64,
     keypair: Option<&Keypair>,
 ) -> Result<String> {
-    let from_keypair = match keypair {
-        Some(k) => k,
-        None => &crate::wallet::load_keypair(config).await?,
-    };
-
-    let client = RpcClient::new(&config.solana.rpc_url);
-
-    // Parse recipient address
-    let to_pubkey =
-        Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
-            address: to_address.to_string(),
-        })?;
+    let mut audit = Audit::new(
+        actor,
+        "transfer",
+        json!({ "token": "SOL", "to": to_address, "amount": amount }),
+    );
+    let result: Result<String> = async {
+        let from_keypair = match keypair {
+            Some(k) => k,
+            None => &crate::wallet::load_keypair(config).await?,
+        };
 
-    // Check balance
-    let current_balance =
-        crate::wallet::get_balance_for_pubkey(config, &from_keypair.pubkey()).await?;
-    if current_balance < amount {
-        return Err(SolanaClientError::InsufficientBalance {
-            current: current_balance,
-            required: amount,
+        let client = rpc::client(config);
+
+        // Parse recipient address
+        let to_pubkey =
+            Pubkey::from_str(to_address).map_err(|_| SolanaClientError::InvalidAddress {
+                address: to_address.to_string(),
+            })?;
+        let lamports = crate::stake::to_lamports(amount)?;
+
+        // Check balance
+        let current_balance =
+            crate::wallet::get_balance_for_pubkey(config, &from_keypair.pubkey()).await?;
+        if current_balance < amount {
+            return Err(SolanaClientError::InsufficientBalance {
+                current: current_balance,
+                required: amount,
+            }
+            .into());
         }
-        .into());
-    }
 
-    let lamports = (amount * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+        audit.check_policy(
+            config,
+            &Intent::sol_transfer(config, &from_keypair.pubkey(), to_address, amount),
+        )?;
 
-    app_log!(
-        info,
-        "Creating transaction: {} SOL from {} to {}",
-        amount,
-        from_keypair.pubkey(),
-        to_address
-    );
+        app_log!(
+            info,
+            "Creating transaction: {} SOL from {} to {}",
+            amount,
+            from_keypair.pubkey(),
+            to_address
+        );
 
-    // Create transfer instruction
-    let instruction = system_instruction::transfer(&from_keypair.pubkey(), &to_pubkey, lamports);
+        // Create transfer instruction
+        let instruction =
+            system_instruction::transfer(&from_keypair.pubkey(), &to_pubkey, lamports);
 
-    // Get recent blockhash
-    let recent_blockhash = client.get_latest_blockhash()?;
+        // Get recent blockhash
+        let recent_blockhash = client.get_latest_blockhash()?;
+
+        // Create transaction
+        let message = Message::new(&[instruction], Some(&from_keypair.pubkey()));
+        let transaction = Transaction::new(&[&from_keypair], message, recent_blockhash);
+        audit.signature = Some(transaction.signatures[0].to_string());
 
-    // Create transaction
-    let message = Message::new(&[instruction], Some(&from_keypair.pubkey()));
-    let transaction = Transaction::new(&[&from_keypair], message, recent_blockhash);
+        // Serialize transaction for later use
+        let serialized_tx = bincode::serialize(&transaction)?;
+        let tx_string = bs58::encode(serialized_tx).into_string();
 
-    // Serialize transaction for later use
-    let serialized_tx = bincode::serialize(&transaction)?;
-    let tx_string = bs58::encode(serialized_tx).into_string();
+        app_log!(info, "Transaction created: {}", tx_string);
 
-    app_log!(info, "Transaction created: {}", tx_string);
+        Ok(tx_string)
+    }
+    .await;
 
-    Ok(tx_string)
+    audit.finish(config, result, "signed")
 }
 
 /// Core function to get transaction history without web dependencies
@@ -292,7 +525,7 @@ pub async fn fetch_transaction_history(
     limit: Option<usize>,
     before: Option<String>,
 ) -> Result<Vec<TransactionHistory>> {
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
     let limit = limit.unwrap_or(50).min(1000); // Cap at 1000
 
     app_log!(
@@ -310,7 +543,7 @@ pub async fn fetch_transaction_history(
     let signatures = client.get_signatures_for_address_with_config(
         pubkey,
         GetConfirmedSignaturesForAddress2Config {
-            before: before.as_deref().map(|s| s.parse().ok()).flatten(),
+            before: before.as_deref().and_then(|s| s.parse().ok()),
             until: None,
             limit: Some(limit),
             commitment: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
@@ -344,11 +577,7 @@ pub async fn fetch_transaction_history(
         };
 
         // Try to get transaction details for amount/type analysis
-        let (amount, token_symbol, tx_type) =
-            match client.get_transaction(&signature.parse()?, UiTransactionEncoding::JsonParsed) {
-                Ok(tx) => analyze_transaction_details(&tx),
-                Err(_) => (None, None, TransactionType::Unknown),
-            };
+        let (amount, token_symbol, tx_type, memo) = transaction_details(&client, &signature)?;
 
         let fee = None; //sig_info.fee.map(|f| f as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64);
 
@@ -362,6 +591,7 @@ pub async fn fetch_transaction_history(
             amount,
             token_symbol,
             transaction_type: tx_type,
+            memo,
             error: sig_info.err.map(|e| format!("{:?}", e)),
         });
     }
@@ -370,16 +600,62 @@ pub async fn fetch_transaction_history(
     Ok(transactions)
 }
 
+/// Up to `limit` transactions whose memo contains `memo` (all without one),
+/// paging back with `before` until enough match, the history runs out or
+/// `history.max_scanned` transactions have been looked at. Also returns the
+/// signature to continue from when there may be more.
+pub async fn search_transaction_history(
+    config: &Config,
+    pubkey: &Pubkey,
+    limit: Option<usize>,
+    mut before: Option<String>,
+    memo: Option<&str>,
+) -> Result<(Vec<TransactionHistory>, Option<String>)> {
+    let limit = limit.unwrap_or(50).min(1000);
+    let budget = config.history.max_scanned.max(limit);
+    let mut scanned = 0;
+    let mut matches = Vec::new();
+
+    loop {
+        let size = limit.min(budget - scanned);
+        let page = fetch_transaction_history(config, pubkey, Some(size), before).await?;
+        let exhausted = page.len() < size;
+        let last = page.last().map(|tx| tx.signature.clone());
+        let count = page.len();
+        scanned += count;
+
+        for (index, tx) in page.into_iter().enumerate() {
+            if memo.is_some_and(|memo| !tx.memo_contains(memo)) {
+                continue;
+            }
+            let signature = tx.signature.clone();
+            matches.push(tx);
+            if matches.len() == limit {
+                let more = !exhausted || index + 1 < count;
+                return Ok((matches, more.then_some(signature)));
+            }
+        }
+
+        if exhausted {
+            return Ok((matches, None));
+        }
+        if scanned >= budget {
+            return Ok((matches, last));
+        }
+        before = last;
+    }
+}
+
 /// Core function to get pending transactions without web dependencies
 pub async fn fetch_pending_transactions(
     config: &Config,
     pubkey: &Pubkey,
 ) -> Result<Vec<TransactionHistory>> {
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
 
     app_log!(info, "Fetching pending transactions for {}", pubkey);
 
-    // Get recent signatures with processed commitment to catch pending ones
+    // Get recent signatures; anything not yet finalized is still pending
     let signatures = client.get_signatures_for_address_with_config(
         pubkey,
         GetConfirmedSignaturesForAddress2Config {
@@ -392,38 +668,39 @@ pub async fn fetch_pending_transactions(
 
     let mut pending_transactions = Vec::new();
 
+    // getSignaturesForAddress only serves confirmed or finalized data, so
+    // "pending" means confirmed but not yet finalized. Processed-level updates
+    // are only observable live through `watch::watch_signature`.
     for sig_info in signatures {
-        // Only include transactions that are processed but not confirmed/finalized
-        if let Some(status) = &sig_info.confirmation_status {
-            if matches!(
-                status,
-                solana_transaction_status::TransactionConfirmationStatus::Processed
-            ) {
-                let signature = sig_info.signature;
-
-                let (amount, token_symbol, tx_type) = match client
-                    .get_transaction(&signature.parse()?, UiTransactionEncoding::JsonParsed)
-                {
-                    Ok(tx) => analyze_transaction_details(&tx),
-                    Err(_) => (None, None, TransactionType::Unknown),
-                };
-
-                let fee = None; // sig_info.fee.map(|f| f as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64);
-
-                pending_transactions.push(TransactionHistory {
-                    signature,
-                    status: TransactionStatus::Pending,
-                    confirmation_status: ConfirmationStatus::Processed,
-                    block_time: sig_info.block_time,
-                    slot: Some(sig_info.slot),
-                    fee,
-                    amount,
-                    token_symbol,
-                    transaction_type: tx_type,
-                    error: None,
-                });
+        let confirmation_status = match &sig_info.confirmation_status {
+            Some(solana_transaction_status::TransactionConfirmationStatus::Processed) => {
+                ConfirmationStatus::Processed
             }
-        }
+            Some(solana_transaction_status::TransactionConfirmationStatus::Confirmed) => {
+                ConfirmationStatus::Confirmed
+            }
+            _ => continue,
+        };
+
+        let signature = sig_info.signature;
+
+        let (amount, token_symbol, tx_type, memo) = transaction_details(&client, &signature)?;
+
+        let fee = None; // sig_info.fee.map(|f| f as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64);
+
+        pending_transactions.push(TransactionHistory {
+            signature,
+            status: TransactionStatus::Pending,
+            confirmation_status,
+            block_time: sig_info.block_time,
+            slot: Some(sig_info.slot),
+            fee,
+            amount,
+            token_symbol,
+            transaction_type: tx_type,
+            memo,
+            error: sig_info.err.map(|e| format!("{:?}", e)),
+        });
     }
 
     app_log!(
@@ -434,9 +711,26 @@ pub async fn fetch_pending_transactions(
     Ok(pending_transactions)
 }
 
-fn analyze_transaction_details(
-    tx: &EncodedConfirmedTransactionWithStatusMeta,
-) -> (Option<f64>, Option<String>, TransactionType) {
+// Amount, token, type and memo of a transaction
+type Details = (Option<f64>, Option<String>, TransactionType, Option<String>);
+
+// Details of a transaction, or unknowns if it cannot be fetched
+fn transaction_details(client: &RpcClient, signature: &str) -> Result<Details> {
+    // Without a supported version the RPC refuses every v0 transaction
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::JsonParsed),
+        commitment: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    Ok(
+        match client.get_transaction_with_config(&signature.parse()?, config) {
+            Ok(tx) => analyze_transaction_details(&tx),
+            Err(_) => (None, None, TransactionType::Unknown, None),
+        },
+    )
+}
+
+fn analyze_transaction_details(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Details {
     // Basic transaction analysis - can be expanded
     let mut amount = None;
     let mut token_symbol = None;
@@ -444,18 +738,16 @@ fn analyze_transaction_details(
 
     // Try to extract SOL transfer amount from pre/post balances
     if let Some(meta) = &tx.transaction.meta {
-        if let (pre_balances, post_balances) = (&meta.pre_balances, &meta.post_balances) {
-            // Look for significant balance changes (excluding fees)
-            for (i, (pre, post)) in pre_balances.iter().zip(post_balances.iter()).enumerate() {
-                let diff = (*post as i64) - (*pre as i64);
-                if diff.abs() > 1000000 {
-                    // More than 0.001 SOL
-                    amount =
-                        Some(diff.abs() as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64);
-                    token_symbol = Some("SOL".to_string());
-                    tx_type = TransactionType::Transfer;
-                    break;
-                }
+        // Look for significant balance changes (excluding fees)
+        for (pre, post) in meta.pre_balances.iter().zip(meta.post_balances.iter()) {
+            let diff = (*post as i64) - (*pre as i64);
+            if diff.abs() > 1000000 {
+                // More than 0.001 SOL
+                amount =
+                    Some(diff.abs() as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64);
+                token_symbol = Some("SOL".to_string());
+                tx_type = TransactionType::Transfer;
+                break;
             }
         }
     }
@@ -465,5 +757,31 @@ fn analyze_transaction_details(
     // - Jupiter swaps
     // - Other program interactions
 
-    (amount, token_symbol, tx_type)
+    (amount, token_symbol, tx_type, parse_memos(tx))
+}
+
+// Texts of the top-level memo instructions, which JsonParsed decodes for both
+// memo program versions
+fn parse_memos(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<String> {
+    let EncodedTransaction::Json(transaction) = &tx.transaction.transaction else {
+        return None;
+    };
+    let UiMessage::Parsed(message) = &transaction.message else {
+        return None;
+    };
+
+    let memos: Vec<&str> = message
+        .instructions
+        .iter()
+        .filter_map(|ix| match ix {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed))
+                if parsed.program == "spl-memo" =>
+            {
+                parsed.parsed.as_str()
+            }
+            _ => None,
+        })
+        .collect();
+
+    (!memos.is_empty()).then(|| memos.join("; "))
 }
@@ -0,0 +1,332 @@
+use crate::config::{self, Config, ProfileConfig, TokensConfig};
+use serde::Serialize;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const NETWORKS: &[&str] = &["devnet", "testnet", "mainnet", "mainnet-beta", "localnet"];
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "off"];
+const LOG_FORMATS: &[&str] = &["pretty", "json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One problem, pointing at the dotted key that causes it
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    pub key: String,
+    pub message: String,
+    pub severity: Severity,
+}
+
+#[derive(Default)]
+struct Issues(Vec<Issue>);
+
+impl Issues {
+    fn error(&mut self, key: &str, message: String) {
+        self.0.push(Issue {
+            key: key.to_string(),
+            message,
+            severity: Severity::Error,
+        });
+    }
+
+    fn warning(&mut self, key: &str, message: String) {
+        self.0.push(Issue {
+            key: key.to_string(),
+            message,
+            severity: Severity::Warning,
+        });
+    }
+
+    fn url(&mut self, key: &str, value: &str, schemes: &[&str]) {
+        match reqwest::Url::parse(value) {
+            Ok(url) if schemes.contains(&url.scheme()) => {}
+            Ok(url) => self.error(
+                key,
+                format!(
+                    "scheme '{}' not allowed, expected {:?}",
+                    url.scheme(),
+                    schemes
+                ),
+            ),
+            Err(e) => self.error(key, format!("'{}' is not a valid URL: {}", value, e)),
+        }
+    }
+
+    fn pubkey(&mut self, key: &str, value: &str) {
+        if Pubkey::from_str(value).is_err() {
+            self.error(key, format!("'{}' is not a valid base58 address", value));
+        }
+    }
+
+    fn one_of(&mut self, key: &str, value: &str, allowed: &[&str]) {
+        if !allowed.contains(&value) {
+            self.error(key, format!("'{}' is not one of {:?}", value, allowed));
+        }
+    }
+
+    fn commitment(&mut self, key: &str, value: &str) {
+        if CommitmentConfig::from_str(value).is_err() {
+            self.error(
+                key,
+                format!(
+                    "unknown commitment '{}', expected processed, confirmed or finalized",
+                    value
+                ),
+            );
+        }
+    }
+
+    fn tokens(&mut self, prefix: &str, tokens: &TokensConfig) {
+        self.pubkey(&format!("{}.sol", prefix), &tokens.sol);
+        self.pubkey(&format!("{}.usdc", prefix), &tokens.usdc);
+        for (symbol, mint) in &tokens.other {
+            self.pubkey(&format!("{}.{}", prefix, symbol), mint);
+        }
+    }
+}
+
+/// Check every setting that would otherwise only fail deep inside a command
+pub fn validate(config: &Config) -> Vec<Issue> {
+    let mut issues = Issues::default();
+
+    issues.one_of("solana.network", &config.solana.network, NETWORKS);
+    issues.url("solana.rpc_url", &config.solana.rpc_url, &["http", "https"]);
+    if let Some(ws_url) = &config.solana.ws_url {
+        issues.url("solana.ws_url", ws_url, &["ws", "wss"]);
+    }
+    issues.commitment("solana.commitment", &config.solana.commitment);
+    if config.solana.signature_timeout_secs == 0 {
+        issues.error(
+            "solana.signature_timeout_secs",
+            "must be greater than 0".to_string(),
+        );
+    }
+
+    issues.tokens("tokens", &config.tokens);
+
+    if config.faucet.airdrop_amount <= 0.0 {
+        issues.error(
+            "faucet.airdrop_amount",
+            "must be greater than 0".to_string(),
+        );
+    }
+
+    issues.one_of("logging.level", &config.logging.level, LOG_LEVELS);
+    issues.one_of("logging.format", &config.logging.format, LOG_FORMATS);
+    for (target, level) in &config.logging.modules {
+        issues.one_of(&format!("logging.modules.{}", target), level, LOG_LEVELS);
+    }
+    if let Some(dir) = log_dir(&config.logging.output)
+        && !dir.is_dir()
+    {
+        issues.error(
+            "logging.output",
+            format!("directory {} does not exist", dir.display()),
+        );
+    }
+
+    issues.url(
+        "jupiter.api_url",
+        &config.jupiter.api_url,
+        &["http", "https"],
+    );
+    issues.url(
+        "jupiter.price_api_url",
+        &config.jupiter.price_api_url,
+        &["http", "https"],
+    );
+    if config.jupiter.slippage_bps == 0 || config.jupiter.slippage_bps > 10_000 {
+        issues.error(
+            "jupiter.slippage_bps",
+            format!(
+                "{} is out of range, expected 1 to 10000",
+                config.jupiter.slippage_bps
+            ),
+        );
+    } else if config.jupiter.slippage_bps > 1_000 {
+        issues.warning(
+            "jupiter.slippage_bps",
+            format!(
+                "{} bps ({}%) slippage is unusually high",
+                config.jupiter.slippage_bps,
+                config.jupiter.slippage_bps as f64 / 100.0
+            ),
+        );
+    }
+
+    let secret = &config.internal.secret;
+    if secret == "change-me-in-production" || secret.len() < 16 {
+        issues.warning(
+            "internal.secret",
+            "weak or default, set SOLANIZE_INTERNAL__SECRET".to_string(),
+        );
+    }
+    let mut key_ids = HashSet::new();
+    for (index, key) in config.internal.keys.iter().enumerate() {
+        if !key_ids.insert(&key.id) {
+            issues.error(
+                &format!("internal.keys.{}.id", index),
+                format!("duplicate key id '{}'", key.id),
+            );
+        }
+        if key.secret.len() < 16 {
+            issues.error(
+                &format!("internal.keys.{}.secret", index),
+                "must be at least 16 characters".to_string(),
+            );
+        }
+    }
+
+    if let Some(url) = &config.remote.url {
+        issues.url("remote.url", url, &["http", "https"]);
+    }
+
+    if config.server.port == 0 {
+        issues.error("server.port", "must not be 0".to_string());
+    }
+
+    let policy = &config.policy;
+    for (token, limit) in &policy.limits {
+        for (name, value) in [
+            ("per_transaction", limit.per_transaction),
+            ("daily", limit.daily),
+        ] {
+            if value.is_some_and(|v| v <= 0.0) {
+                issues.error(
+                    &format!("policy.limits.{}.{}", token, name),
+                    "must be greater than 0".to_string(),
+                );
+            }
+        }
+    }
+    for (list, addresses) in [
+        ("allowed_recipients", &policy.allowed_recipients),
+        ("denied_recipients", &policy.denied_recipients),
+        ("allowed_programs", &policy.allowed_programs),
+    ] {
+        for (index, address) in addresses.iter().enumerate() {
+            issues.pubkey(&format!("policy.{}.{}", list, index), address);
+        }
+    }
+
+    for (symbol, pool) in &config.liquid_staking.pools {
+        issues.pubkey(&format!("liquid_staking.pools.{}", symbol), pool);
+    }
+
+    if let Some(recipient) = &config.pay.recipient {
+        issues.pubkey("pay.recipient", recipient);
+    }
+    // Wallets only follow https links
+    for (key, value) in [
+        ("pay.icon", &config.pay.icon),
+        ("pay.public_url", &config.pay.public_url),
+    ] {
+        if let Some(url) = value {
+            issues.url(key, url, &["https"]);
+        }
+    }
+    if config.pay.public_url.is_some() && config.pay.icon.is_none() {
+        issues.warning(
+            "pay.icon",
+            "wallets show an icon for transaction requests".to_string(),
+        );
+    }
+    for (key, value) in [
+        ("pay.expires_in_secs", config.pay.expires_in_secs),
+        ("pay.poll_interval_secs", config.pay.poll_interval_secs),
+    ] {
+        if value == 0 {
+            issues.error(key, "must be greater than 0".to_string());
+        }
+    }
+
+    if config.auth.challenges_per_minute == 0 {
+        issues.error(
+            "auth.challenges_per_minute",
+            "must be greater than 0".to_string(),
+        );
+    }
+    if config.auth.max_challenges == 0 {
+        issues.error("auth.max_challenges", "must be greater than 0".to_string());
+    }
+
+    if config.webhooks.max_backfill == 0 {
+        issues.error(
+            "webhooks.max_backfill",
+            "must be greater than 0".to_string(),
+        );
+    }
+
+    if config.history.max_scanned == 0 {
+        issues.error("history.max_scanned", "must be greater than 0".to_string());
+    }
+
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+    for name in names {
+        validate_profile(&mut issues, name, &config.profiles[name]);
+    }
+
+    if let Some(profile) = &config.profile
+        && !config.profiles.contains_key(profile)
+    {
+        issues.error("profile", format!("no profile named '{}'", profile));
+    }
+
+    unknown_keys(&mut issues, config);
+
+    issues.0
+}
+
+// Directory a file sink writes into; None for stdout/stderr
+fn log_dir(output: &str) -> Option<&Path> {
+    if output == "stdout" || output == "stderr" {
+        return None;
+    }
+    match Path::new(output).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Some(dir),
+        _ => Some(Path::new(".")),
+    }
+}
+
+fn validate_profile(issues: &mut Issues, name: &str, profile: &ProfileConfig) {
+    let prefix = format!("profiles.{}", name);
+    let network = profile.network.as_deref().unwrap_or(name);
+
+    issues.one_of(&format!("{}.network", prefix), network, NETWORKS);
+    issues.url(
+        &format!("{}.rpc_url", prefix),
+        &profile.rpc_url,
+        &["http", "https"],
+    );
+    if let Some(ws_url) = &profile.ws_url {
+        issues.url(&format!("{}.ws_url", prefix), ws_url, &["ws", "wss"]);
+    }
+    if let Some(commitment) = &profile.commitment {
+        issues.commitment(&format!("{}.commitment", prefix), commitment);
+    }
+    if let Some(tokens) = &profile.tokens {
+        issues.tokens(&format!("{}.tokens", prefix), tokens);
+    }
+}
+
+// Keys from the file, environment or flags that no setting reads, usually typos
+fn unknown_keys(issues: &mut Issues, config: &Config) {
+    let Ok(effective) = serde_json::to_value(config) else {
+        return;
+    };
+
+    for (key, source) in &config.sources {
+        if config::lookup(&effective, key).is_none() {
+            issues.error(key, format!("unknown setting (from {})", source));
+        }
+    }
+}
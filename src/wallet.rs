@@ -1,10 +1,10 @@
+use crate::api::{BalanceKind, WalletTokenInfo};
 use crate::app_log;
-use crate::{config::Config, error::SolanaClientError, token};
+use crate::{config::Config, error::SolanaClientError, rpc, token};
 use anyhow::Result;
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{Keypair, Signature, Signer},
 };
 use std::fs;
 
@@ -15,10 +15,12 @@ pub struct TokenBalance {
     pub name: String,
     pub balance: f64,
     pub decimals: u8,
+    #[allow(dead_code)]
     pub ui_amount: Option<f64>,
+    pub kind: BalanceKind,
 }
 
-pub async fn generate_wallet(config: &Config) -> Result<()> {
+pub async fn generate_wallet(config: &Config) -> Result<Pubkey> {
     app_log!(info, "Generating new wallet");
 
     let keypair = Keypair::new();
@@ -30,16 +32,19 @@ pub async fn generate_wallet(config: &Config) -> Result<()> {
 
     fs::write(&config.wallet.keypair_path, keypair_json)?;
 
-    app_log!(info, "✅ Wallet generated successfully!");
-    app_log!(info, "📍 Public Key: {}", pubkey);
-    app_log!(info, "💾 Saved to: {}", config.wallet.keypair_path);
+    app_log!(
+        info,
+        "Wallet {} saved to {}",
+        pubkey,
+        config.wallet.keypair_path
+    );
 
-    Ok(())
+    Ok(pubkey)
 }
 
 pub async fn get_wallet_tokens(config: &Config) -> Result<Vec<TokenBalance>> {
     let keypair = load_keypair(config).await?;
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
 
     app_log!(info, "Scanning wallet for SPL tokens");
 
@@ -55,6 +60,7 @@ pub async fn get_wallet_tokens(config: &Config) -> Result<Vec<TokenBalance>> {
             balance: sol_balance,
             decimals: 9,
             ui_amount: Some(sol_balance),
+            kind: BalanceKind::NativeSol,
         });
     }
 
@@ -68,63 +74,69 @@ pub async fn get_wallet_tokens(config: &Config) -> Result<Vec<TokenBalance>> {
 
     // Process each token account
     for account in accounts {
-        if let solana_account_decoder::UiAccountData::Json(token_account) = &account.account.data {
-            if let Some(info) = token_account
+        if let solana_account_decoder::UiAccountData::Json(token_account) = &account.account.data
+            && let Some(info) = token_account
                 .parsed
                 .as_object()
                 .and_then(|obj| obj.get("info"))
                 .and_then(|v| v.as_object())
-            {
-                let mint = info
-                    .get("mint")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                let token_amount = info.get("tokenAmount").and_then(|v| v.as_object());
-
-                if let Some(amount_info) = token_amount {
-                    let ui_amount = amount_info
-                        .get("uiAmount")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-
-                    let decimals = amount_info
-                        .get("decimals")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u8;
-
-                    // Skip accounts with zero balance
-                    if ui_amount <= 0.0 {
-                        continue;
-                    }
-
-                    // Try to get token info from Jupiter
-                    let (symbol, name) = match token::get_token_info(config, &mint).await {
-                        Ok(Some(token_info)) => (token_info.symbol, token_info.name),
-                        _ => {
-                            // Fallback: use mint address as symbol
-                            let short_mint = if mint.len() > 8 {
-                                format!("{}..{}", &mint[..4], &mint[mint.len() - 4..])
-                            } else {
-                                mint.clone()
-                            };
-                            (
-                                short_mint.clone(),
-                                format!("Unknown Token ({})", short_mint),
-                            )
-                        }
-                    };
-
-                    token_balances.push(TokenBalance {
-                        mint: mint.clone(),
-                        symbol,
-                        name,
-                        balance: ui_amount,
-                        decimals,
-                        ui_amount: Some(ui_amount),
-                    });
+        {
+            let mint = info
+                .get("mint")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let token_amount = info.get("tokenAmount").and_then(|v| v.as_object());
+
+            if let Some(amount_info) = token_amount {
+                let ui_amount = amount_info
+                    .get("uiAmount")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.0);
+
+                let decimals = amount_info
+                    .get("decimals")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as u8;
+
+                // Skip accounts with zero balance
+                if ui_amount <= 0.0 {
+                    continue;
                 }
+
+                // Try to get token info from Jupiter; it lists the native mint as SOL
+                let (symbol, name) = match token::get_token_info(config, &mint).await {
+                    _ if mint == config.tokens.sol => {
+                        ("wSOL".to_string(), "Wrapped SOL".to_string())
+                    }
+                    Ok(Some(token_info)) => (token_info.symbol, token_info.name),
+                    _ => {
+                        // Fallback: use mint address as symbol
+                        let short_mint = if mint.len() > 8 {
+                            format!("{}..{}", &mint[..4], &mint[mint.len() - 4..])
+                        } else {
+                            mint.clone()
+                        };
+                        (
+                            short_mint.clone(),
+                            format!("Unknown Token ({})", short_mint),
+                        )
+                    }
+                };
+
+                token_balances.push(TokenBalance {
+                    mint: mint.clone(),
+                    symbol,
+                    name,
+                    balance: ui_amount,
+                    decimals,
+                    ui_amount: Some(ui_amount),
+                    kind: match mint == config.tokens.sol {
+                        true => BalanceKind::WrappedSol,
+                        false => BalanceKind::Token,
+                    },
+                });
             }
         }
     }
@@ -139,41 +151,33 @@ pub async fn get_wallet_tokens(config: &Config) -> Result<Vec<TokenBalance>> {
     Ok(token_balances)
 }
 
-pub async fn list_wallet_tokens(config: &Config) -> Result<()> {
-    let tokens = get_wallet_tokens(config).await?;
-
-    if tokens.is_empty() {
-        app_log!(info, "💸 No tokens found in wallet");
-        return Ok(());
-    }
-
-    app_log!(info, "🪙 Wallet Token Holdings:");
-    app_log!(
-        info,
-        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
-    );
-
-    for (i, token) in tokens.iter().enumerate() {
-        app_log!(
-            info,
-            "{}. {} ({}) - {} tokens",
-            i + 1,
-            token.symbol,
-            token.name,
-            format_balance(token.balance)
-        );
-
-        // Show USD value if we can get price
-        if let Ok(price) = crate::jupiter::get_token_price(config, &token.symbol).await {
-            let usd_value = token.balance * price;
-            app_log!(info, "   💲 ~${:.2} (${:.6} per token)", usd_value, price);
-        }
-
-        app_log!(info, "   📍 {}", token.mint);
-        app_log!(info,);
+/// Token balances of `pubkey` with their USD value, where Jupiter knows a price
+pub async fn get_wallet_holdings(config: &Config, pubkey: &Pubkey) -> Result<Vec<WalletTokenInfo>> {
+    let mut holdings = Vec::new();
+
+    for token in get_wallet_tokens_for_pubkey(config, pubkey).await? {
+        // wSOL is priced as SOL, under its mint
+        let price_key = match token.kind {
+            BalanceKind::WrappedSol => &token.mint,
+            _ => &token.symbol,
+        };
+        let usd_value = crate::jupiter::get_token_price(config, price_key)
+            .await
+            .ok()
+            .map(|price| token.balance * price);
+
+        holdings.push(WalletTokenInfo {
+            symbol: token.symbol,
+            name: token.name,
+            mint: token.mint,
+            balance: token.balance,
+            decimals: token.decimals,
+            usd_value,
+            kind: token.kind,
+        });
     }
 
-    Ok(())
+    Ok(holdings)
 }
 
 pub fn format_balance(balance: f64) -> String {
@@ -213,7 +217,7 @@ pub async fn load_keypair(config: &Config) -> Result<Keypair> {
 
 pub async fn get_balance(config: &Config) -> Result<f64> {
     let keypair = load_keypair(config).await?;
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
 
     let balance = client.get_balance(&keypair.pubkey())?;
     let sol_balance = balance as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64;
@@ -223,7 +227,7 @@ pub async fn get_balance(config: &Config) -> Result<f64> {
 }
 
 pub async fn get_balance_for_pubkey(config: &Config, pubkey: &Pubkey) -> Result<f64> {
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
 
     let balance = client.get_balance(pubkey)?;
     let sol_balance = balance as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64;
@@ -236,7 +240,7 @@ pub async fn get_wallet_tokens_for_pubkey(
     config: &Config,
     pubkey: &Pubkey,
 ) -> Result<Vec<TokenBalance>> {
-    let client = RpcClient::new(&config.solana.rpc_url);
+    let client = rpc::client(config);
 
     app_log!(info, "Scanning wallet for SPL tokens: {}", pubkey);
 
@@ -252,6 +256,7 @@ pub async fn get_wallet_tokens_for_pubkey(
             balance: sol_balance,
             decimals: 9,
             ui_amount: Some(sol_balance),
+            kind: BalanceKind::NativeSol,
         });
     }
 
@@ -265,58 +270,65 @@ pub async fn get_wallet_tokens_for_pubkey(
 
     // Process each token account
     for account in accounts {
-        if let solana_account_decoder::UiAccountData::Json(token_account) = &account.account.data {
-            if let Some(info) = token_account.parsed.get("info").and_then(|v| v.as_object()) {
-                let mint = info
-                    .get("mint")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                let token_amount = info.get("tokenAmount").and_then(|v| v.as_object());
-
-                if let Some(amount_info) = token_amount {
-                    let ui_amount = amount_info
-                        .get("uiAmount")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-
-                    let decimals = amount_info
-                        .get("decimals")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u8;
-
-                    // Skip accounts with zero balance
-                    if ui_amount <= 0.0 {
-                        continue;
-                    }
-
-                    // Try to get token info from Jupiter
-                    let (symbol, name) = match token::get_token_info(config, &mint).await {
-                        Ok(Some(token_info)) => (token_info.symbol, token_info.name),
-                        _ => {
-                            // Fallback: use mint address as symbol
-                            let short_mint = if mint.len() > 8 {
-                                format!("{}..{}", &mint[..4], &mint[mint.len() - 4..])
-                            } else {
-                                mint.clone()
-                            };
-                            (
-                                short_mint.clone(),
-                                format!("Unknown Token ({})", short_mint),
-                            )
-                        }
-                    };
-
-                    token_balances.push(TokenBalance {
-                        mint: mint.clone(),
-                        symbol,
-                        name,
-                        balance: ui_amount,
-                        decimals,
-                        ui_amount: Some(ui_amount),
-                    });
+        if let solana_account_decoder::UiAccountData::Json(token_account) = &account.account.data
+            && let Some(info) = token_account.parsed.get("info").and_then(|v| v.as_object())
+        {
+            let mint = info
+                .get("mint")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+
+            let token_amount = info.get("tokenAmount").and_then(|v| v.as_object());
+
+            if let Some(amount_info) = token_amount {
+                let ui_amount = amount_info
+                    .get("uiAmount")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.0);
+
+                let decimals = amount_info
+                    .get("decimals")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as u8;
+
+                // Skip accounts with zero balance
+                if ui_amount <= 0.0 {
+                    continue;
                 }
+
+                // Try to get token info from Jupiter; it lists the native mint as SOL
+                let (symbol, name) = match token::get_token_info(config, &mint).await {
+                    _ if mint == config.tokens.sol => {
+                        ("wSOL".to_string(), "Wrapped SOL".to_string())
+                    }
+                    Ok(Some(token_info)) => (token_info.symbol, token_info.name),
+                    _ => {
+                        // Fallback: use mint address as symbol
+                        let short_mint = if mint.len() > 8 {
+                            format!("{}..{}", &mint[..4], &mint[mint.len() - 4..])
+                        } else {
+                            mint.clone()
+                        };
+                        (
+                            short_mint.clone(),
+                            format!("Unknown Token ({})", short_mint),
+                        )
+                    }
+                };
+
+                token_balances.push(TokenBalance {
+                    mint: mint.clone(),
+                    symbol,
+                    name,
+                    balance: ui_amount,
+                    decimals,
+                    ui_amount: Some(ui_amount),
+                    kind: match mint == config.tokens.sol {
+                        true => BalanceKind::WrappedSol,
+                        false => BalanceKind::Token,
+                    },
+                });
             }
         }
     }
@@ -331,34 +343,34 @@ pub async fn get_wallet_tokens_for_pubkey(
     Ok(token_balances)
 }
 
-pub async fn request_airdrop(config: &Config, amount: f64) -> Result<()> {
+/// Returns the airdrop signature and the balance once it is confirmed
+pub async fn request_airdrop(config: &Config, amount: f64) -> Result<(Signature, f64)> {
+    let lamports = crate::stake::to_lamports(amount)?;
     let keypair = load_keypair(config).await?;
-    let client = RpcClient::new(&config.solana.rpc_url);
-
-    let lamports = (amount * solana_sdk::native_token::LAMPORTS_PER_SOL as f64) as u64;
+    let client = rpc::client(config);
 
     app_log!(info, "Requesting airdrop of {} SOL", amount);
 
     match client.request_airdrop(&keypair.pubkey(), lamports) {
         Ok(signature) => {
-            app_log!(info, "✅ Airdrop requested successfully!");
-            app_log!(info, "🔗 Signature: {}", signature);
-            app_log!(info, "⏳ Waiting for confirmation...");
+            app_log!(
+                info,
+                "Airdrop requested: {}, waiting for confirmation",
+                signature
+            );
 
             // Wait for confirmation
             client.confirm_transaction(&signature)?;
 
             let new_balance = get_balance(config).await?;
-            app_log!(info, "💰 New balance: {} SOL", new_balance);
+            Ok((signature, new_balance))
         }
         Err(e) => {
             app_log!(error, "Airdrop failed: {}", e);
-            return Err(SolanaClientError::TransactionFailed {
+            Err(SolanaClientError::TransactionFailed {
                 reason: format!("Airdrop failed: {}", e),
             }
-            .into());
+            .into())
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,280 @@
+use crate::app_log;
+use crate::{config::Config, error::SolanaClientError, rpc};
+use anyhow::Result;
+use futures_util::StreamExt;
+use serde::Serialize;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+    RpcTransactionLogsFilter,
+};
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_client::rpc_response::RpcSignatureResult;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use spl_associated_token_account::get_associated_token_address;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+use utoipa::ToSchema;
+
+/// Live event pushed by a PubSub subscription.
+#[derive(Debug, Serialize, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchEvent {
+    /// A submitted signature reached a new commitment level
+    SignatureStatus {
+        signature: String,
+        commitment: String,
+        slot: u64,
+        error: Option<String>,
+    },
+    /// The signature got no further before `solana.signature_timeout_secs`.
+    /// Without any level reached it was most likely dropped or expired.
+    SignatureExpired {
+        signature: String,
+        reached: Option<String>,
+    },
+    /// A transaction mentioning the watched address was committed
+    Transaction {
+        signature: String,
+        slot: u64,
+        error: Option<String>,
+        logs: Vec<String>,
+    },
+    /// The watched account's lamport balance changed
+    BalanceChange {
+        pubkey: String,
+        slot: u64,
+        lamports: u64,
+        balance: f64,
+    },
+}
+
+/// Signatures remembered to drop the same transaction seen through several accounts
+const RECENT_SIGNATURES: usize = 256;
+
+/// Commitment levels a signature goes through, in order
+const SIGNATURE_COMMITMENTS: [CommitmentConfig; 3] = [
+    CommitmentConfig::processed(),
+    CommitmentConfig::confirmed(),
+    CommitmentConfig::finalized(),
+];
+
+async fn connect(config: &Config) -> Result<PubsubClient> {
+    let ws_url = config.solana.websocket_url();
+    app_log!(info, "Connecting to PubSub endpoint: {}", ws_url);
+
+    PubsubClient::new(&ws_url).await.map_err(|e| {
+        SolanaClientError::NetworkError {
+            source: Box::new(e),
+        }
+        .into()
+    })
+}
+
+fn commitment_label(commitment: &CommitmentConfig) -> String {
+    format!("{:?}", commitment.commitment).to_lowercase()
+}
+
+/// Track a signature through processed, confirmed and finalized, emitting one
+/// event per level. Stops early if the transaction fails, or with
+/// `SignatureExpired` once `solana.signature_timeout_secs` have passed.
+pub async fn watch_signature(
+    config: &Config,
+    signature: &Signature,
+    sender: UnboundedSender<WatchEvent>,
+) -> Result<()> {
+    let client = connect(config).await?;
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(config.solana.signature_timeout_secs);
+    let mut reached = None;
+
+    app_log!(info, "Watching signature {}", signature);
+
+    for commitment in SIGNATURE_COMMITMENTS {
+        let (mut stream, unsubscribe) = client
+            .signature_subscribe(
+                signature,
+                Some(RpcSignatureSubscribeConfig {
+                    commitment: Some(commitment),
+                    enable_received_notification: Some(false),
+                }),
+            )
+            .await
+            .map_err(|e| SolanaClientError::NetworkError {
+                source: Box::new(e),
+            })?;
+
+        let next = tokio::time::timeout_at(deadline, stream.next()).await;
+        drop(stream);
+        unsubscribe().await;
+
+        let response = match next {
+            Ok(Some(response)) => response,
+            Ok(None) => break,
+            Err(_) => {
+                app_log!(warn, "Gave up watching signature {}", signature);
+                let _ = sender.send(WatchEvent::SignatureExpired {
+                    signature: signature.to_string(),
+                    reached,
+                });
+                break;
+            }
+        };
+
+        let error = match response.value {
+            RpcSignatureResult::ProcessedSignature(result) => result.err.map(|e| e.to_string()),
+            RpcSignatureResult::ReceivedSignature(_) => None,
+        };
+        let failed = error.is_some();
+
+        let event = WatchEvent::SignatureStatus {
+            signature: signature.to_string(),
+            commitment: commitment_label(&commitment),
+            slot: response.context.slot,
+            error,
+        };
+
+        if sender.send(event).is_err() || failed {
+            break;
+        }
+        reached = Some(commitment_label(&commitment));
+    }
+
+    Ok(())
+}
+
+/// The address plus its token accounts: `logsSubscribe` only reports
+/// transactions that mention an account directly, and a token transfer into an
+/// existing token account does not mention its owner. Token accounts created
+/// after the subscription starts are not followed.
+pub fn mentioned_accounts(config: &Config, owner: &Pubkey) -> Vec<Pubkey> {
+    let mut accounts = vec![*owner];
+
+    let configured = [&config.tokens.sol, &config.tokens.usdc]
+        .into_iter()
+        .chain(config.tokens.other.values())
+        .filter_map(|mint| Pubkey::from_str(mint).ok())
+        .map(|mint| get_associated_token_address(owner, &mint));
+    accounts.extend(configured);
+
+    let client = rpc::client(config);
+    for program_id in [spl_token::id(), spl_token_2022::id()] {
+        match client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(program_id))
+        {
+            Ok(keyed) => accounts.extend(
+                keyed
+                    .iter()
+                    .filter_map(|account| Pubkey::from_str(&account.pubkey).ok()),
+            ),
+            Err(e) => app_log!(warn, "Could not list token accounts of {}: {}", owner, e),
+        }
+    }
+
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+/// Stream balance changes and committed transactions for an address until the
+/// receiving side is dropped. Transactions touching its token accounts count too.
+/// `subscribed` fires once every subscription is in place.
+pub async fn watch_address(
+    config: &Config,
+    pubkey: &Pubkey,
+    sender: UnboundedSender<WatchEvent>,
+    subscribed: Option<oneshot::Sender<()>>,
+) -> Result<()> {
+    let client = connect(config).await?;
+    let commitment = config.solana.commitment_config()?;
+
+    app_log!(
+        info,
+        "Watching address {} ({:?})",
+        pubkey,
+        commitment.commitment
+    );
+
+    let (mut account_stream, account_unsubscribe) = client
+        .account_subscribe(
+            pubkey,
+            Some(RpcAccountInfoConfig {
+                commitment: Some(commitment),
+                ..RpcAccountInfoConfig::default()
+            }),
+        )
+        .await
+        .map_err(|e| SolanaClientError::NetworkError {
+            source: Box::new(e),
+        })?;
+
+    // One subscription per account; the node accepts a single address per filter
+    let mut logs_streams = Vec::new();
+    let mut logs_unsubscribes = Vec::new();
+    for account in mentioned_accounts(config, pubkey) {
+        let (stream, unsubscribe) = client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![account.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(commitment),
+                },
+            )
+            .await
+            .map_err(|e| SolanaClientError::NetworkError {
+                source: Box::new(e),
+            })?;
+        logs_streams.push(stream);
+        logs_unsubscribes.push(unsubscribe);
+    }
+    let mut logs_stream = futures_util::stream::select_all(logs_streams);
+    if let Some(subscribed) = subscribed {
+        let _ = subscribed.send(());
+    }
+    let mut seen = VecDeque::with_capacity(RECENT_SIGNATURES);
+
+    loop {
+        let event = tokio::select! {
+            Some(update) = account_stream.next() => WatchEvent::BalanceChange {
+                pubkey: pubkey.to_string(),
+                slot: update.context.slot,
+                lamports: update.value.lamports,
+                balance: update.value.lamports as f64
+                    / solana_sdk::native_token::LAMPORTS_PER_SOL as f64,
+            },
+            Some(logs) = logs_stream.next() => {
+                if seen.contains(&logs.value.signature) {
+                    continue;
+                }
+                if seen.len() == RECENT_SIGNATURES {
+                    seen.pop_front();
+                }
+                seen.push_back(logs.value.signature.clone());
+
+                WatchEvent::Transaction {
+                    signature: logs.value.signature,
+                    slot: logs.context.slot,
+                    error: logs.value.err.map(|e| e.to_string()),
+                    logs: logs.value.logs,
+                }
+            }
+            _ = sender.closed() => break,
+            else => break,
+        };
+
+        if sender.send(event).is_err() {
+            break;
+        }
+    }
+
+    drop(account_stream);
+    drop(logs_stream);
+    account_unsubscribe().await;
+    for unsubscribe in logs_unsubscribes {
+        unsubscribe().await;
+    }
+
+    app_log!(info, "Stopped watching address {}", pubkey);
+    Ok(())
+}
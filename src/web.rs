@@ -1,387 +1,901 @@
 use crate::app_log;
 use anyhow::Result;
+use graflog::tracing::{self, Instrument};
 use rocket::{
-    State, get, post, routes, serde::json::Json,
-    http::Status,
+    FromForm, State, catch, catchers,
+    data::{self, Data, FromData, ToByteUnit},
+    delete,
+    fairing::{Fairing, Info, Kind},
+    get,
+    http::{ContentType, Status},
+    options, post,
     request::{FromRequest, Outcome},
+    response::stream::{Event, EventStream},
+    route::{self, Handler, Route},
+    routes,
+    serde::json::Json,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Serialize, de::DeserializeOwned};
 use solana_sdk::pubkey::Pubkey;
+use std::net::IpAddr;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
 
-use crate::{config::Config, jupiter, token, transaction, wallet};
+use crate::api::{
+    ApiError, ApiResponse, BalanceRequest, BalanceResponse, CleanupRequest, CleanupResponse,
+    DeadLetterResponse, ErrorCode, ErrorResponse, PayLinkAccount, PayLinkError, PayLinkMetadata,
+    PayLinkTransaction, PayRequest, PayRequestResponse, PayStatusRequest, PayStatusResponse,
+    PendingTransactionsRequest, PendingTransactionsResponse, PrepareMintRequest,
+    PrepareMintResponse, PrepareStakeRequest, PrepareStakeResponse, PrepareSwapRequest,
+    PrepareSwapResponse, PrepareTransactionRequest, PrepareTransactionResponse, PrepareWrapRequest,
+    PrepareWrapResponse, PriceRequest, PriceResponse, RegisterWebhookRequest, SearchRequest,
+    StakeAccountsRequest, StakeAccountsResponse, SubmitResponse, SubmitSignedRequest, TokenInfo,
+    TokenSearchResponse, TransactionHistoryRequest, TransactionHistoryResponse,
+    WalletTokensRequest, WalletTokensResponse, WebhookListResponse,
+};
+use crate::auth::{AuthChallenge, AuthService, AuthTokens, AuthVerification, RefreshRequest};
+use crate::idempotency::{IdempotencyStore, Lookup, StoredResponse};
+use crate::signing::{self, RequestVerifier};
+use crate::webhook::{WebhookRegistration, WebhookService};
+use crate::{
+    cleanup,
+    config::{Config, KeyScope},
+    error::SolanaClientError,
+    jupiter, metrics, mint, openapi, pay, stake, token, transaction, wallet, watch, webhook, wrap,
+};
 
 // ── Internal auth guard ───────────────────────────────────────────────────────
-// The gateway-solanize service must present "Authorization: Bearer <CLI_INTERNAL_SECRET>"
-// on every request. This prevents any other process on the VPS from calling us directly.
+// The gateway-solanize service signs every request with one of `internal.keys`
+// (see `signing.rs`). Until keys are configured it may instead present
+// "Authorization: Bearer <CLI_INTERNAL_SECRET>". This prevents any other process
+// on the VPS from calling us directly. A wallet access token obtained through
+// /auth/verify is accepted as well, but only acts for its own wallet: it may
+// prepare and broadcast transactions that wallet pays for, and nothing else
+// that needs a gateway key.
+
+/// What a caller was granted
+#[derive(Clone, Copy)]
+enum Grant {
+    Key(KeyScope),
+    Wallet(Pubkey), // Access token from /auth/verify
+}
+
+/// Any authenticated caller, identified as recorded in the audit log
+pub struct InternalAuth {
+    pub actor: String,
+    pub wallet: Option<Pubkey>, // Set for wallet access tokens
+}
+
+impl InternalAuth {
+    /// Wallet callers may only name themselves as payer or owner
+    fn acting_for(&self, pubkey: &str) -> Result<(), ApiError> {
+        match self.wallet {
+            Some(wallet) if wallet.to_string() != pubkey => Err(ApiError::new(
+                ErrorCode::Forbidden,
+                format!("Signed in as {}, cannot act for {}", wallet, pubkey),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Gateway key of any scope; wallet access tokens are refused
+pub struct KeyAuth;
+
+/// Gateway key that may broadcast transactions and manage webhooks
+pub struct SubmitAuth;
+
+/// Caller that may broadcast: a submit key, or a wallet for transactions it pays
+pub struct BroadcastAuth {
+    pub actor: String,
+    pub wallet: Option<Pubkey>,
+}
 
-pub struct InternalAuth;
+// Grant and audit identity: `key:<id>`, `wallet:<address>` or `internal`
+async fn authenticate(req: &rocket::Request<'_>) -> Outcome<(Grant, String), ()> {
+    let cached = req
+        .local_cache_async(async {
+            let verifier = req.guard::<&State<RequestVerifier>>().await.succeeded()?;
+            let auth = req.guard::<&State<AuthService>>().await.succeeded()?;
+            let headers = req.headers();
+
+            if let Some(signature) = headers.get_one(signing::SIGNATURE_HEADER) {
+                let uri = req.uri().to_string();
+                let empty_body = signing::sha256_hex(b"");
+                let signed = signing::SignedRequest {
+                    key_id: headers.get_one(signing::KEY_ID_HEADER).unwrap_or_default(),
+                    method: req.method().as_str(),
+                    path: &uri,
+                    timestamp: headers
+                        .get_one(signing::TIMESTAMP_HEADER)
+                        .unwrap_or_default(),
+                    nonce: headers.get_one(signing::NONCE_HEADER).unwrap_or_default(),
+                    body_sha256: headers
+                        .get_one(signing::CONTENT_HASH_HEADER)
+                        .unwrap_or(&empty_body),
+                    signature,
+                };
+
+                return match verifier.verify(&signed) {
+                    Ok(scope) => Some((Grant::Key(scope), format!("key:{}", signed.key_id))),
+                    Err(e) => {
+                        app_log!(
+                            warn,
+                            "Rejected signed request to {} {}: {}",
+                            req.method(),
+                            uri,
+                            e
+                        );
+                        None
+                    }
+                };
+            }
+
+            let token = headers.get_one("Authorization")?.strip_prefix("Bearer ")?;
+            verifier
+                .verify_bearer(token)
+                .map(|scope| (Grant::Key(scope), "internal".to_string()))
+                .or_else(|| {
+                    let wallet = auth.validate_access_token(token)?;
+                    let wallet = Pubkey::from_str(&wallet).ok()?;
+                    Some((Grant::Wallet(wallet), format!("wallet:{}", wallet)))
+                })
+        })
+        .await;
+
+    match cached {
+        Some(caller) => Outcome::Success(caller.clone()),
+        None => {
+            app_log!(
+                warn,
+                "Rejected unauthenticated request to {} {}",
+                req.method(),
+                req.uri()
+            );
+            Outcome::Error((Status::Unauthorized, ()))
+        }
+    }
+}
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for InternalAuth {
     type Error = ();
 
     async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
-        let config = match req.guard::<&State<Config>>().await {
-            Outcome::Success(c) => c,
-            _ => return Outcome::Error((Status::InternalServerError, ())),
-        };
-
-        let expected = format!("Bearer {}", config.internal.secret);
-        match req.headers().get_one("Authorization") {
-            Some(h) if h == expected => Outcome::Success(InternalAuth),
-            _ => {
-                app_log!(warn, "Rejected unauthenticated request to {} {}", req.method(), req.uri());
-                Outcome::Error((Status::Unauthorized, ()))
-            }
-        }
+        authenticate(req).await.map(|(grant, actor)| InternalAuth {
+            actor,
+            wallet: match grant {
+                Grant::Wallet(wallet) => Some(wallet),
+                Grant::Key(_) => None,
+            },
+        })
     }
 }
 
-#[derive(Deserialize)]
-pub struct BalanceRequest {
-    pub pubkey: String, // Public key to check balance for
+fn forbidden<T>(req: &rocket::Request<'_>, reason: &str) -> Outcome<T, ()> {
+    app_log!(warn, "{} used for {} {}", reason, req.method(), req.uri());
+    Outcome::Error((Status::Forbidden, ()))
 }
 
-#[derive(Deserialize)]
-pub struct PrepareSwapRequest {
-    pub payer_pubkey: String, // Who pays fees
-    pub from_token: String,
-    pub to_token: String,
-    pub amount: f64,
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for KeyAuth {
+    type Error = ();
+
+    async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        match authenticate(req).await {
+            Outcome::Success((Grant::Key(_), _)) => Outcome::Success(KeyAuth),
+            Outcome::Success((Grant::Wallet(_), _)) => forbidden(req, "Wallet access token"),
+            Outcome::Error(e) => Outcome::Error(e),
+            Outcome::Forward(f) => Outcome::Forward(f),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct PrepareTransactionRequest {
-    pub payer_pubkey: String, // Who pays fees and sends
-    pub to_address: String,
-    pub amount: f64,
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SubmitAuth {
+    type Error = ();
+
+    async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        match authenticate(req).await {
+            Outcome::Success((Grant::Key(KeyScope::Submit), _)) => Outcome::Success(SubmitAuth),
+            Outcome::Success((Grant::Key(KeyScope::Read), _)) => forbidden(req, "Read-only key"),
+            Outcome::Success((Grant::Wallet(_), _)) => forbidden(req, "Wallet access token"),
+            Outcome::Error(e) => Outcome::Error(e),
+            Outcome::Forward(f) => Outcome::Forward(f),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct SubmitSignedRequest {
-    pub signed_transaction: String, // Base64 encoded signed transaction
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BroadcastAuth {
+    type Error = ();
+
+    async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        match authenticate(req).await {
+            Outcome::Success((Grant::Key(KeyScope::Submit), actor)) => {
+                Outcome::Success(BroadcastAuth {
+                    actor,
+                    wallet: None,
+                })
+            }
+            Outcome::Success((Grant::Wallet(wallet), actor)) => Outcome::Success(BroadcastAuth {
+                actor,
+                wallet: Some(wallet),
+            }),
+            Outcome::Success((Grant::Key(KeyScope::Read), _)) => forbidden(req, "Read-only key"),
+            Outcome::Error(e) => Outcome::Error(e),
+            Outcome::Forward(f) => Outcome::Forward(f),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct PriceRequest {
-    pub token: String, // Token symbol or mint address
+/// JSON body whose hash is checked against the signed content-hash header.
+/// Request guards cannot see the body, so this completes the signature check.
+pub struct SignedJson<T>(pub T);
+
+impl<T> std::ops::Deref for SignedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
 }
 
-#[derive(Deserialize)]
-pub struct SearchRequest {
-    pub query: String, // Search term
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for SignedJson<T> {
+    type Error = ();
+
+    async fn from_data(req: &'r rocket::Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("json").unwrap_or(1.mebibytes());
+        let body = match data.open(limit).into_bytes().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => return data::Outcome::Error((Status::PayloadTooLarge, ())),
+            Err(_) => return data::Outcome::Error((Status::BadRequest, ())),
+        };
+
+        if req.headers().contains(signing::SIGNATURE_HEADER) {
+            let claimed = req
+                .headers()
+                .get_one(signing::CONTENT_HASH_HEADER)
+                .map(str::to_string)
+                .unwrap_or_else(|| signing::sha256_hex(b""));
+            if !signing::constant_time_eq(&claimed, &signing::sha256_hex(&body)) {
+                app_log!(warn, "Body does not match signed hash for {}", req.uri());
+                return data::Outcome::Error((Status::Unauthorized, ()));
+            }
+        }
+
+        match serde_json::from_slice(&body) {
+            Ok(value) => data::Outcome::Success(SignedJson(value)),
+            Err(_) => data::Outcome::Error((Status::UnprocessableEntity, ())),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct WalletTokensRequest {
-    pub pubkey: String, // Public key to get tokens for
+// ── Idempotency ───────────────────────────────────────────────────────────────
+// Submit and prepare endpoints accept an `Idempotency-Key` header. A retry with
+// the same key and body gets the original response back instead of running the
+// request again; reusing a key for a different body is a 409.
+
+pub struct IdempotencyKey(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = ();
+
+    async fn from_request(req: &'r rocket::Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one("Idempotency-Key") {
+            None => Outcome::Success(IdempotencyKey(None)),
+            Some(key) if !key.is_empty() && key.len() <= 255 => {
+                Outcome::Success(IdempotencyKey(Some(key.to_string())))
+            }
+            Some(_) => Outcome::Error((Status::BadRequest, ())),
+        }
+    }
 }
 
-#[derive(Serialize)]
-pub struct ApiResponse<T> {
-    pub success: bool,
-    pub data: Option<T>,
-    pub error: Option<String>,
+async fn idempotent<T, B>(
+    store: &IdempotencyStore,
+    actor: &str,
+    key: IdempotencyKey,
+    route: &str,
+    body: &B,
+    handler: impl Future<Output = ApiResult<T>>,
+) -> ApiResult<T>
+where
+    T: Serialize + DeserializeOwned,
+    B: Serialize,
+{
+    let Some(key) = key.0 else {
+        return handler.await;
+    };
+
+    let fingerprint = match serde_json::to_vec(body) {
+        Ok(body) => signing::sha256_hex(&[route.as_bytes(), b"\n", &body].concat()),
+        Err(e) => return failure("Invalid request", &e.into()),
+    };
+
+    match store.begin(actor, &key, &fingerprint) {
+        Lookup::New => {}
+        Lookup::Replay(stored) => {
+            app_log!(info, "Replaying response for idempotency key {}", key);
+            return match serde_json::from_value(stored.body) {
+                Ok(response) => (
+                    Status::from_code(stored.status).unwrap_or(Status::Ok),
+                    Json(response),
+                ),
+                Err(e) => failure("Stored response unreadable", &e.into()),
+            };
+        }
+        Lookup::Mismatch => {
+            return error_response(ApiError::new(
+                ErrorCode::Conflict,
+                "Idempotency-Key was already used with a different request".to_string(),
+            ));
+        }
+        Lookup::InProgress => {
+            return error_response(ApiError::new(
+                ErrorCode::Conflict,
+                "A request with this Idempotency-Key is still in progress".to_string(),
+            ));
+        }
+    }
+
+    let (status, response) = handler.await;
+
+    // 5xx means we never got a definite answer (RPC down, ...), so allow a retry
+    match serde_json::to_value(&response.0) {
+        Ok(body) if status.code < 500 => store.complete(
+            actor,
+            &key,
+            StoredResponse {
+                fingerprint,
+                status: status.code,
+                body,
+                created_at: chrono::Utc::now().timestamp(),
+            },
+        ),
+        _ => store.abandon(actor, &key),
+    }
+
+    (status, response)
 }
 
-#[derive(Serialize)]
-pub struct BalanceResponse {
-    pub pubkey: String,
-    pub balance: f64,
-    pub token: String,
+// ── Metrics ───────────────────────────────────────────────────────────────────
+// Every request is counted and timed under its route template (not the concrete
+// path) so pubkeys and ids don't explode the series count.
+
+pub struct RequestMetrics;
+
+#[derive(Clone, Copy)]
+struct RequestStart(Option<Instant>);
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut rocket::Request<'_>, _: &mut rocket::Data<'_>) {
+        req.local_cache(|| RequestStart(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, req: &'r rocket::Request<'_>, res: &mut rocket::Response<'r>) {
+        let route = req
+            .route()
+            .map(|r| r.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        let method = req.method().as_str();
+
+        metrics::inc(
+            metrics::HTTP_REQUESTS,
+            &[
+                ("method", method),
+                ("route", &route),
+                ("status", &res.status().code.to_string()),
+            ],
+        );
+        if let RequestStart(Some(started)) = req.local_cache(|| RequestStart(None)) {
+            metrics::observe(
+                metrics::HTTP_DURATION,
+                &[("method", method), ("route", &route)],
+                started.elapsed(),
+            );
+        }
+    }
 }
 
-#[derive(Serialize)]
-pub struct PrepareSwapResponse {
-    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
-    pub quote_info: QuoteInfo,
-    pub required_signers: Vec<String>,
-    pub recent_blockhash: String,
+// ── Request IDs ───────────────────────────────────────────────────────────────
+// Each request gets an id, taken from `X-Request-Id` when the gateway sent one.
+// Handlers run inside a `request` span carrying it, so every log line they emit
+// can be correlated; the id is echoed back in the response header.
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+#[derive(Clone)]
+struct RequestId(String);
+
+fn request_id(req: &rocket::Request<'_>) -> String {
+    req.local_cache(|| {
+        let id = req
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= 64
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+            .map(str::to_string)
+            .unwrap_or_else(|| signing::to_hex(&rand::random::<[u8; 8]>()));
+        RequestId(id)
+    })
+    .0
+    .clone()
 }
 
-#[derive(Serialize)]
-pub struct PrepareTransactionResponse {
-    pub unsigned_transaction: String, // Base64 encoded unsigned transaction
-    pub from: String,
-    pub to: String,
-    pub amount: f64,
-    pub required_signers: Vec<String>,
-    pub recent_blockhash: String,
+pub struct RequestIds;
+
+#[rocket::async_trait]
+impl Fairing for RequestIds {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ids",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r rocket::Request<'_>, res: &mut rocket::Response<'r>) {
+        let id = request_id(req);
+        app_log!(
+            info,
+            request_id = %id,
+            "{} {} -> {}",
+            req.method(),
+            req.uri(),
+            res.status().code
+        );
+        res.set_raw_header(REQUEST_ID_HEADER, id);
+    }
 }
 
-#[derive(Serialize)]
-pub struct QuoteInfo {
-    pub expected_output: f64,
-    pub price_impact: f64,
-    pub route_steps: usize,
+/// Runs a route's handler inside a span carrying the request id
+#[derive(Clone)]
+struct Traced(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Traced {
+    async fn handle<'r>(&self, req: &'r rocket::Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let span = tracing::info_span!("request", request_id = %request_id(req));
+        self.0.handle(req, data).instrument(span).await
+    }
 }
 
-#[derive(Serialize)]
-pub struct SubmitResponse {
-    pub signature: String,
-    pub status: String,
+fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Traced(route.handler));
+            route
+        })
+        .collect()
 }
 
-#[derive(Serialize)]
-pub struct PriceResponse {
-    pub token: String,
-    pub price: f64,
-    pub currency: String,
+// Served at the root for the Prometheus scraper; the server only listens on localhost
+#[get("/metrics")]
+pub fn metrics_endpoint() -> (ContentType, String) {
+    (
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        metrics::render(),
+    )
 }
 
-#[derive(Serialize)]
-pub struct TokenSearchResponse {
-    pub tokens: Vec<TokenInfo>,
-    pub count: usize,
+// Helper function to parse public key
+fn parse_public_key(pubkey: &str) -> Result<Pubkey> {
+    Pubkey::from_str(pubkey).map_err(|_| {
+        SolanaClientError::InvalidAddress {
+            address: pubkey.to_string(),
+        }
+        .into()
+    })
 }
 
-#[derive(Serialize)]
-pub struct TokenInfo {
-    pub symbol: String,
-    pub name: String,
-    pub address: String,
-    pub decimals: u8,
+// ── Responses ─────────────────────────────────────────────────────────────────
+// Failures carry a stable `code` and the matching HTTP status, so callers never
+// have to inspect the message text.
+
+type ApiResult<T> = (Status, Json<ApiResponse<T>>);
+
+fn success<T>(data: T) -> ApiResult<T> {
+    (
+        Status::Ok,
+        Json(ApiResponse {
+            success: true,
+            data: Some(data),
+            error: None,
+        }),
+    )
 }
 
-#[derive(Serialize)]
-pub struct WalletTokensResponse {
-    pub pubkey: String,
-    pub tokens: Vec<WalletTokenInfo>,
-    pub total_tokens: usize,
+fn failure<T>(context: &str, e: &anyhow::Error) -> ApiResult<T> {
+    error_response(ApiError::from_error(context, e))
 }
 
-#[derive(Serialize)]
-pub struct WalletTokenInfo {
-    pub symbol: String,
-    pub name: String,
-    pub mint: String,
-    pub balance: f64,
-    pub decimals: u8,
-    pub usd_value: Option<f64>,
+fn error_response<T>(error: ApiError) -> ApiResult<T> {
+    (
+        status_for(error.code),
+        Json(ApiResponse {
+            success: false,
+            data: None,
+            error: Some(error),
+        }),
+    )
 }
 
-// Helper function to parse public key
-fn parse_public_key(pubkey: &str) -> Result<Pubkey> {
-    Ok(Pubkey::from_str(pubkey)?)
+fn status_for(code: ErrorCode) -> Status {
+    match code {
+        ErrorCode::InvalidAddress | ErrorCode::InvalidWalletFormat | ErrorCode::InvalidRequest => {
+            Status::BadRequest
+        }
+        ErrorCode::AuthFailed | ErrorCode::Unauthorized => Status::Unauthorized,
+        ErrorCode::Forbidden | ErrorCode::PolicyViolation => Status::Forbidden,
+        ErrorCode::NotFound | ErrorCode::WalletNotFound => Status::NotFound,
+        ErrorCode::Conflict => Status::Conflict,
+        ErrorCode::RateLimited => Status::TooManyRequests,
+        ErrorCode::InsufficientBalance | ErrorCode::TransactionFailed => {
+            Status::UnprocessableEntity
+        }
+        ErrorCode::NetworkError => Status::BadGateway,
+        ErrorCode::ConfigError | ErrorCode::InternalError => Status::InternalServerError,
+    }
 }
 
-#[get("/health")]
-pub fn health() -> Json<ApiResponse<String>> {
+// Rocket-level failures (auth guard, malformed JSON, unknown route) use the same envelope
+#[catch(default)]
+fn default_catcher(status: Status, req: &rocket::Request<'_>) -> Json<ApiResponse<()>> {
+    let code = match status.code {
+        400 | 422 => ErrorCode::InvalidRequest,
+        401 => ErrorCode::Unauthorized,
+        403 => ErrorCode::Forbidden,
+        404 => ErrorCode::NotFound,
+        409 => ErrorCode::Conflict,
+        429 => ErrorCode::RateLimited,
+        _ => ErrorCode::InternalError,
+    };
+
     Json(ApiResponse {
-        success: true,
-        data: Some("OK".to_string()),
-        error: None,
+        success: false,
+        data: None,
+        error: Some(ApiError::new(
+            code,
+            format!("{} {}: {}", req.method(), req.uri(), status),
+        )),
     })
 }
 
+#[utoipa::path(
+    tag = "system",
+    responses(
+        (status = 200, description = "Server is up", body = ApiResponse<String>),
+    )
+)]
+#[get("/health")]
+pub fn health() -> ApiResult<String> {
+    success("OK".to_string())
+}
+
+// Unauthenticated like /health: the gateway generates its client from this
+#[get("/openapi.json")]
+pub fn openapi_spec() -> (ContentType, String) {
+    match openapi::spec_json() {
+        Ok(spec) => (ContentType::JSON, spec),
+        Err(e) => {
+            app_log!(error, "Failed to render OpenAPI spec: {}", e);
+            (ContentType::JSON, "{}".to_string())
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "wallet",
+    request_body = BalanceRequest,
+    responses(
+        (status = 200, body = ApiResponse<BalanceResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/balance", data = "<request>")]
 pub async fn get_balance(
     _auth: InternalAuth,
-    request: Json<BalanceRequest>,
+    request: SignedJson<BalanceRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<BalanceResponse>> {
+) -> ApiResult<BalanceResponse> {
     app_log!(info, "Balance request for pubkey: {}", request.pubkey);
 
     match parse_public_key(&request.pubkey) {
         Ok(pubkey) => match wallet::get_balance_for_pubkey(config, &pubkey).await {
-            Ok(balance) => Json(ApiResponse {
-                success: true,
-                data: Some(BalanceResponse {
-                    pubkey: request.pubkey.clone(),
-                    balance,
-                    token: "SOL".to_string(),
-                }),
-                error: None,
+            Ok(balance) => success(BalanceResponse {
+                pubkey: request.pubkey.clone(),
+                balance,
+                token: "SOL".to_string(),
             }),
             Err(e) => {
                 app_log!(error, "Failed to get balance: {}", e);
-                Json(ApiResponse {
-                    success: false,
-                    data: None,
-                    error: Some(format!("Failed to get balance: {}", e)),
-                })
+                failure("Failed to get balance", &e)
             }
         },
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid public key: {}", e)),
-        }),
+        Err(e) => failure("Invalid public key", &e),
     }
 }
 
+#[utoipa::path(
+    tag = "transactions",
+    request_body = PrepareSwapRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PrepareSwapResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 422, description = "Insufficient balance or transaction rejected", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/swap/prepare", data = "<request>")]
 pub async fn prepare_swap(
-    _auth: InternalAuth,
-    request: Json<PrepareSwapRequest>,
+    auth: InternalAuth,
+    request: SignedJson<PrepareSwapRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<PrepareSwapResponse>> {
-    app_log!(
-        info,
-        "Prepare swap request: {} {} -> {} for {}",
-        request.amount,
-        request.from_token,
-        request.to_token,
-        request.payer_pubkey
-    );
-
-    match parse_public_key(&request.payer_pubkey) {
-        Ok(payer_pubkey) => {
-            match jupiter::prepare_swap_transaction(
-                config,
-                &request.from_token,
-                &request.to_token,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareSwapResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/swap/prepare",
+        &*request,
+        async {
+            app_log!(
+                info,
+                "Prepare swap request: {} {} -> {} for {}",
                 request.amount,
-                &payer_pubkey,
-            )
-            .await
-            {
-                Ok((unsigned_tx, quote_info, signers, blockhash)) => Json(ApiResponse {
-                    success: true,
-                    data: Some(PrepareSwapResponse {
-                        unsigned_transaction: unsigned_tx,
-                        quote_info,
-                        required_signers: signers,
-                        recent_blockhash: blockhash,
-                    }),
-                    error: None,
-                }),
-                Err(e) => {
-                    app_log!(error, "Swap preparation failed: {}", e);
-                    Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        error: Some(format!("Swap preparation failed: {}", e)),
-                    })
+                request.from_token,
+                request.to_token,
+                request.payer_pubkey
+            );
+
+            if let Err(e) = auth.acting_for(&request.payer_pubkey) {
+                return error_response(e);
+            }
+
+            match parse_public_key(&request.payer_pubkey) {
+                Ok(payer_pubkey) => {
+                    match jupiter::prepare_swap_transaction(
+                        config,
+                        &auth.actor,
+                        &request.from_token,
+                        &request.to_token,
+                        request.amount,
+                        &payer_pubkey,
+                    )
+                    .await
+                    {
+                        Ok((unsigned_tx, quote_info, signers, blockhash)) => {
+                            success(PrepareSwapResponse {
+                                unsigned_transaction: unsigned_tx,
+                                quote_info,
+                                required_signers: signers,
+                                recent_blockhash: blockhash,
+                            })
+                        }
+                        Err(e) => {
+                            app_log!(error, "Swap preparation failed: {}", e);
+                            failure("Swap preparation failed", &e)
+                        }
+                    }
                 }
+                Err(e) => failure("Invalid payer public key", &e),
             }
-        }
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid payer public key: {}", e)),
-        }),
-    }
+        },
+    )
+    .await
 }
 
+#[utoipa::path(
+    tag = "transactions",
+    request_body = PrepareTransactionRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PrepareTransactionResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 422, description = "Insufficient balance or transaction rejected", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/transaction/prepare", data = "<request>")]
 pub async fn prepare_transaction(
-    _auth: InternalAuth,
-    request: Json<PrepareTransactionRequest>,
+    auth: InternalAuth,
+    request: SignedJson<PrepareTransactionRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<PrepareTransactionResponse>> {
-    app_log!(
-        info,
-        "Prepare transaction request: {} SOL from {} to {}",
-        request.amount,
-        request.payer_pubkey,
-        request.to_address
-    );
-
-    match parse_public_key(&request.payer_pubkey) {
-        Ok(payer_pubkey) => {
-            match transaction::prepare_sol_transfer(
-                config,
-                &payer_pubkey,
-                &request.to_address,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareTransactionResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/transaction/prepare",
+        &*request,
+        async {
+            app_log!(
+                info,
+                "Prepare transaction request: {} SOL from {} to {}",
                 request.amount,
-            )
-            .await
-            {
-                Ok((unsigned_tx, signers, blockhash)) => Json(ApiResponse {
-                    success: true,
-                    data: Some(PrepareTransactionResponse {
-                        unsigned_transaction: unsigned_tx,
-                        from: request.payer_pubkey.clone(),
-                        to: request.to_address.clone(),
-                        amount: request.amount,
-                        required_signers: signers,
-                        recent_blockhash: blockhash,
-                    }),
-                    error: None,
-                }),
-                Err(e) => {
-                    app_log!(error, "Transaction preparation failed: {}", e);
-                    Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        error: Some(format!("Transaction preparation failed: {}", e)),
-                    })
+                request.payer_pubkey,
+                request.to_address
+            );
+
+            if let Err(e) = auth.acting_for(&request.payer_pubkey) {
+                return error_response(e);
+            }
+
+            match parse_public_key(&request.payer_pubkey) {
+                Ok(payer_pubkey) => {
+                    match transaction::prepare_sol_transfer(
+                        config,
+                        &auth.actor,
+                        &payer_pubkey,
+                        &request.to_address,
+                        request.amount,
+                        request.memo.as_deref(),
+                    )
+                    .await
+                    {
+                        Ok((unsigned_tx, signers, blockhash)) => {
+                            success(PrepareTransactionResponse {
+                                unsigned_transaction: unsigned_tx,
+                                from: request.payer_pubkey.clone(),
+                                to: request.to_address.clone(),
+                                amount: request.amount,
+                                memo: request.memo.clone(),
+                                required_signers: signers,
+                                recent_blockhash: blockhash,
+                            })
+                        }
+                        Err(e) => {
+                            app_log!(error, "Transaction preparation failed: {}", e);
+                            failure("Transaction preparation failed", &e)
+                        }
+                    }
                 }
+                Err(e) => failure("Invalid payer public key", &e),
             }
-        }
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid payer public key: {}", e)),
-        }),
-    }
+        },
+    )
+    .await
 }
 
+#[utoipa::path(
+    tag = "transactions",
+    request_body = SubmitSignedRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<SubmitResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Key is read-only, paid by another wallet, or rejected by the spending policy", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 422, description = "Insufficient balance or transaction rejected", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/transaction/submit", data = "<request>")]
 pub async fn submit_signed_transaction(
-    _auth: InternalAuth,
-    request: Json<SubmitSignedRequest>,
+    auth: BroadcastAuth,
+    request: SignedJson<SubmitSignedRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<SubmitResponse>> {
-    app_log!(info, "Submit signed transaction request");
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<SubmitResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/transaction/submit",
+        &*request,
+        async {
+            app_log!(info, "Submit signed transaction request");
 
-    match transaction::submit_signed_transaction(config, &request.signed_transaction).await {
-        Ok(signature) => Json(ApiResponse {
-            success: true,
-            data: Some(SubmitResponse {
-                signature,
-                status: "submitted".to_string(),
-            }),
-            error: None,
-        }),
-        Err(e) => {
-            app_log!(error, "Transaction submission failed: {}", e);
-            Json(ApiResponse {
-                success: false,
-                data: None,
-                error: Some(format!("Transaction submission failed: {}", e)),
-            })
-        }
-    }
+            match transaction::submit_signed_transaction(
+                config,
+                &auth.actor,
+                &request.signed_transaction,
+                auth.wallet.as_ref(),
+            )
+            .await
+            {
+                Ok(signature) => success(SubmitResponse {
+                    signature,
+                    status: "submitted".to_string(),
+                }),
+                Err(e) => {
+                    app_log!(error, "Transaction submission failed: {}", e);
+                    failure("Transaction submission failed", &e)
+                }
+            }
+        },
+    )
+    .await
 }
 
+#[utoipa::path(
+    tag = "tokens",
+    request_body = PriceRequest,
+    responses(
+        (status = 200, body = ApiResponse<PriceResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/price", data = "<request>")]
 pub async fn get_token_price(
     _auth: InternalAuth,
-    request: Json<PriceRequest>,
+    request: SignedJson<PriceRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<PriceResponse>> {
+) -> ApiResult<PriceResponse> {
     app_log!(info, "Price request for token: {}", request.token);
 
     match jupiter::get_token_price(config, &request.token).await {
-        Ok(price) => Json(ApiResponse {
-            success: true,
-            data: Some(PriceResponse {
-                token: request.token.clone(),
-                price,
-                currency: "USD".to_string(),
-            }),
-            error: None,
+        Ok(price) => success(PriceResponse {
+            token: request.token.clone(),
+            price,
+            currency: "USD".to_string(),
         }),
         Err(e) => {
             app_log!(error, "Price fetch failed: {}", e);
-            Json(ApiResponse {
-                success: false,
-                data: None,
-                error: Some(format!("Price fetch failed: {}", e)),
-            })
+            failure("Price fetch failed", &e)
         }
     }
 }
 
+#[utoipa::path(
+    tag = "tokens",
+    request_body = SearchRequest,
+    responses(
+        (status = 200, body = ApiResponse<TokenSearchResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/tokens/search", data = "<request>")]
 pub async fn search_tokens(
     _auth: InternalAuth,
-    request: Json<SearchRequest>,
+    request: SignedJson<SearchRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<TokenSearchResponse>> {
+) -> ApiResult<TokenSearchResponse> {
     app_log!(info, "Token search request: {}", request.query);
 
     match token::search_tokens(config, &request.query).await {
@@ -398,167 +912,154 @@ pub async fn search_tokens(
 
             let count = token_infos.len();
 
-            Json(ApiResponse {
-                success: true,
-                data: Some(TokenSearchResponse {
-                    tokens: token_infos,
-                    count,
-                }),
-                error: None,
+            success(TokenSearchResponse {
+                tokens: token_infos,
+                count,
             })
         }
         Err(e) => {
             app_log!(error, "Token search failed: {}", e);
-            Json(ApiResponse {
-                success: false,
-                data: None,
-                error: Some(format!("Token search failed: {}", e)),
-            })
+            failure("Token search failed", &e)
         }
     }
 }
 
+#[utoipa::path(
+    tag = "wallet",
+    request_body = WalletTokensRequest,
+    responses(
+        (status = 200, body = ApiResponse<WalletTokensResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/wallet/tokens", data = "<request>")]
 pub async fn get_wallet_tokens(
     _auth: InternalAuth,
-    request: Json<WalletTokensRequest>,
+    request: SignedJson<WalletTokensRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<WalletTokensResponse>> {
+) -> ApiResult<WalletTokensResponse> {
     app_log!(info, "Wallet tokens request for pubkey: {}", request.pubkey);
 
     match parse_public_key(&request.pubkey) {
-        Ok(pubkey) => {
-            match wallet::get_wallet_tokens_for_pubkey(config, &pubkey).await {
-                Ok(tokens) => {
-                    let mut wallet_tokens = Vec::new();
-
-                    for token in tokens {
-                        // Try to get USD value
-                        let usd_value = if let Ok(price) =
-                            jupiter::get_token_price(config, &token.symbol).await
-                        {
-                            Some(token.balance * price)
-                        } else {
-                            None
-                        };
-
-                        wallet_tokens.push(WalletTokenInfo {
-                            symbol: token.symbol,
-                            name: token.name,
-                            mint: token.mint,
-                            balance: token.balance,
-                            decimals: token.decimals,
-                            usd_value,
-                        });
-                    }
+        Ok(pubkey) => match wallet::get_wallet_holdings(config, &pubkey).await {
+            Ok(wallet_tokens) => {
+                let total_tokens = wallet_tokens.len();
 
-                    let total_tokens = wallet_tokens.len();
-
-                    Json(ApiResponse {
-                        success: true,
-                        data: Some(WalletTokensResponse {
-                            pubkey: request.pubkey.clone(),
-                            tokens: wallet_tokens,
-                            total_tokens,
-                        }),
-                        error: None,
-                    })
-                }
-                Err(e) => {
-                    app_log!(error, "Failed to get wallet tokens: {}", e);
-                    Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        error: Some(format!("Failed to get wallet tokens: {}", e)),
-                    })
-                }
+                success(WalletTokensResponse {
+                    pubkey: request.pubkey.clone(),
+                    tokens: wallet_tokens,
+                    total_tokens,
+                })
             }
-        }
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid public key: {}", e)),
-        }),
+            Err(e) => {
+                app_log!(error, "Failed to get wallet tokens: {}", e);
+                failure("Failed to get wallet tokens", &e)
+            }
+        },
+        Err(e) => failure("Invalid public key", &e),
     }
 }
 
-pub async fn start_server(config: Config, port: u16) -> Result<()> {
+pub async fn start_server(config: Config) -> Result<()> {
+    // Localhost by default — gateway is the sole caller
+    let address = format!("{}:{}", config.server.address, config.server.port);
     let figment = rocket::Config::figment()
-        .merge(("port", port))
-        .merge(("address", "127.0.0.1")); // localhost-only — gateway is the sole caller
-
-    let rocket = rocket::custom(figment).manage(config).mount(
-        "/solana", // Changed from "/api/v1" to "/solana"
-        routes![
-            health,
-            get_balance,
-            prepare_swap,
-            prepare_transaction,
-            submit_signed_transaction,
-            get_token_price,
-            search_tokens,
-            get_wallet_tokens,
-            get_transaction_history_web,  // Renamed
-            get_pending_transactions_web, // Renamed
-        ],
-    );
+        .merge(("port", config.server.port))
+        .merge(("address", config.server.address.clone()));
 
-    app_log!(
-        info,
-        "Starting Solana API server on http://127.0.0.1:{} (localhost only)",
-        port
-    );
-    app_log!(info, "Available endpoints:");
-    app_log!(info, "  GET  /api/v1/health");
-    app_log!(info, "  POST /api/v1/balance");
-    app_log!(info, "  POST /api/v1/swap/prepare");
-    app_log!(info, "  POST /api/v1/transaction/prepare");
-    app_log!(info, "  POST /api/v1/transaction/submit");
-    app_log!(info, "  POST /api/v1/price");
-    app_log!(info, "  POST /api/v1/tokens/search");
-    app_log!(info, "  POST /api/v1/wallet/tokens");
-    app_log!(info, "  POST /api/v1/transactions/history");
-    app_log!(info, "  POST /api/v1/transactions/pending");
+    let webhooks = webhook::WebhookService::new(config.clone())?;
+    webhooks.start();
 
-    let _ = rocket.launch().await?;
+    let auth = AuthService::new(config.clone());
+    let verifier = RequestVerifier::new(config.clone());
+    let idempotency = IdempotencyStore::new(config.clone())?;
 
-    Ok(())
-}
+    let rocket = rocket::custom(figment)
+        .manage(config)
+        .manage(webhooks)
+        .manage(auth)
+        .manage(verifier)
+        .manage(idempotency)
+        .attach(RequestMetrics)
+        .attach(RequestIds)
+        .attach(PayLinkCors)
+        .register("/solana", catchers![default_catcher])
+        .mount("/", routes![metrics_endpoint])
+        .mount(
+            "/solana", // Changed from "/api/v1" to "/solana"
+            traced(routes![
+                health,
+                openapi_spec,
+                get_balance,
+                prepare_swap,
+                prepare_transaction,
+                submit_signed_transaction,
+                get_token_price,
+                search_tokens,
+                get_wallet_tokens,
+                get_transaction_history_web,  // Renamed
+                get_pending_transactions_web, // Renamed
+                prepare_stake,
+                get_stake_accounts,
+                prepare_mint,
+                prepare_cleanup,
+                prepare_wrap,
+                prepare_unwrap,
+                create_payment_request,
+                get_payment_status,
+                pay_link_metadata,
+                pay_link_transaction,
+                pay_link_preflight,
+                watch_address_web,
+                watch_signature_web,
+                register_webhook,
+                list_webhooks,
+                delete_webhook,
+                list_dead_letters,
+                auth_challenge,
+                auth_verify,
+                auth_refresh,
+            ]),
+        );
 
-#[derive(Deserialize)]
-pub struct TransactionHistoryRequest {
-    pub pubkey: String,
-    pub limit: Option<usize>,
-    pub before: Option<String>, // Signature to paginate before
-}
+    #[cfg(feature = "docs-ui")]
+    let rocket = rocket.mount(
+        "/",
+        utoipa_swagger_ui::SwaggerUi::new("/solana/docs/<_..>")
+            .config(utoipa_swagger_ui::Config::from("/solana/openapi.json")),
+    );
 
-#[derive(Deserialize)]
-pub struct PendingTransactionsRequest {
-    pub pubkey: String,
-}
+    app_log!(info, "Starting Solana API server on http://{}", address);
+    app_log!(info, "Available endpoints:");
+    for route in rocket.routes() {
+        app_log!(info, "  {:<6} {}", route.method, route.uri);
+    }
 
-#[derive(Serialize)]
-pub struct TransactionHistoryResponse {
-    pub pubkey: String,
-    pub transactions: Vec<transaction::TransactionHistory>,
-    pub total_count: usize,
-    pub has_more: bool,
-    pub next_before: Option<String>, // For pagination
-}
+    let _ = rocket.launch().await?;
 
-#[derive(Serialize)]
-pub struct PendingTransactionsResponse {
-    pub pubkey: String,
-    pub pending_transactions: Vec<transaction::TransactionHistory>,
-    pub count: usize,
+    Ok(())
 }
 
+#[utoipa::path(
+    tag = "wallet",
+    request_body = TransactionHistoryRequest,
+    responses(
+        (status = 200, body = ApiResponse<TransactionHistoryResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/transactions/history", data = "<request>")]
 pub async fn get_transaction_history_web(
     _auth: InternalAuth,
-    request: Json<TransactionHistoryRequest>,
+    request: SignedJson<TransactionHistoryRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<TransactionHistoryResponse>> {
+) -> ApiResult<TransactionHistoryResponse> {
     app_log!(
         info,
         "Transaction history request for pubkey: {}",
@@ -567,62 +1068,53 @@ pub async fn get_transaction_history_web(
 
     match parse_public_key(&request.pubkey) {
         Ok(pubkey) => {
-            match transaction::fetch_transaction_history(
+            match transaction::search_transaction_history(
                 config,
                 &pubkey,
                 request.limit,
                 request.before.clone(),
+                request.memo.as_deref(),
             )
             .await
             {
-                Ok(transactions) => {
+                Ok((transactions, next_before)) => {
                     let total_count = transactions.len();
-                    let limit = request.limit.unwrap_or(50);
-                    let has_more = total_count >= limit;
 
-                    // Get next pagination token (last signature)
-                    let next_before = if has_more && !transactions.is_empty() {
-                        Some(transactions.last().unwrap().signature.clone())
-                    } else {
-                        None
-                    };
-
-                    Json(ApiResponse {
-                        success: true,
-                        data: Some(TransactionHistoryResponse {
-                            pubkey: request.pubkey.clone(),
-                            transactions,
-                            total_count,
-                            has_more,
-                            next_before,
-                        }),
-                        error: None,
+                    success(TransactionHistoryResponse {
+                        pubkey: request.pubkey.clone(),
+                        transactions,
+                        total_count,
+                        has_more: next_before.is_some(),
+                        next_before,
                     })
                 }
                 Err(e) => {
                     app_log!(error, "Failed to get transaction history: {}", e);
-                    Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        error: Some(format!("Failed to get transaction history: {}", e)),
-                    })
+                    failure("Failed to get transaction history", &e)
                 }
             }
         }
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid public key: {}", e)),
-        }),
+        Err(e) => failure("Invalid public key", &e),
     }
 }
 
+#[utoipa::path(
+    tag = "wallet",
+    request_body = PendingTransactionsRequest,
+    responses(
+        (status = 200, body = ApiResponse<PendingTransactionsResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
 #[post("/transactions/pending", data = "<request>")]
 pub async fn get_pending_transactions_web(
     _auth: InternalAuth,
-    request: Json<PendingTransactionsRequest>,
+    request: SignedJson<PendingTransactionsRequest>,
     config: &State<Config>,
-) -> Json<ApiResponse<PendingTransactionsResponse>> {
+) -> ApiResult<PendingTransactionsResponse> {
     app_log!(
         info,
         "Pending transactions request for pubkey: {}",
@@ -634,29 +1126,802 @@ pub async fn get_pending_transactions_web(
             Ok(pending_transactions) => {
                 let count = pending_transactions.len();
 
-                Json(ApiResponse {
-                    success: true,
-                    data: Some(PendingTransactionsResponse {
-                        pubkey: request.pubkey.clone(),
-                        pending_transactions,
-                        count,
-                    }),
-                    error: None,
+                success(PendingTransactionsResponse {
+                    pubkey: request.pubkey.clone(),
+                    pending_transactions,
+                    count,
                 })
             }
             Err(e) => {
                 app_log!(error, "Failed to get pending transactions: {}", e);
-                Json(ApiResponse {
+                failure("Failed to get pending transactions", &e)
+            }
+        },
+        Err(e) => failure("Invalid public key", &e),
+    }
+}
+
+// ── Live updates (Server-Sent Events) ─────────────────────────────────────────
+// Each connection owns its own PubSub subscription; it is torn down when the
+// gateway (or the browser behind it) disconnects.
+
+fn watch_stream(
+    mut receiver: tokio::sync::mpsc::UnboundedReceiver<watch::WatchEvent>,
+    watcher: tokio::task::JoinHandle<Result<()>>,
+) -> EventStream![] {
+    EventStream! {
+        while let Some(event) = receiver.recv().await {
+            yield Event::json(&event);
+        }
+
+        match watcher.await {
+            Ok(Err(e)) => {
+                app_log!(error, "Watch stream failed: {}", e);
+                yield Event::json(&ApiResponse::<()> {
                     success: false,
                     data: None,
-                    error: Some(format!("Failed to get pending transactions: {}", e)),
+                    error: Some(ApiError::from_error("Watch failed", &e)),
                 })
+                .event("error");
+            }
+            _ => yield Event::empty().event("end"),
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "watch",
+    responses(
+        (status = 200, description = "Balance changes and transactions for the address", content_type = "text/event-stream", body = watch::WatchEvent),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[get("/watch/address/<pubkey>")]
+pub fn watch_address_web(
+    _auth: InternalAuth,
+    pubkey: &str,
+    config: &State<Config>,
+) -> Result<EventStream![], Status> {
+    app_log!(info, "Watch request for pubkey: {}", pubkey);
+
+    let pubkey = parse_public_key(pubkey).map_err(|_| Status::BadRequest)?;
+    let config = config.inner().clone();
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    let watcher =
+        tokio::spawn(async move { watch::watch_address(&config, &pubkey, sender, None).await });
+
+    Ok(watch_stream(receiver, watcher))
+}
+
+#[utoipa::path(
+    tag = "watch",
+    responses(
+        (status = 200, description = "Commitment progress of the signature", content_type = "text/event-stream", body = watch::WatchEvent),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[get("/watch/signature/<signature>")]
+pub fn watch_signature_web(
+    _auth: InternalAuth,
+    signature: &str,
+    config: &State<Config>,
+) -> Result<EventStream![], Status> {
+    app_log!(info, "Watch request for signature: {}", signature);
+
+    let signature =
+        solana_sdk::signature::Signature::from_str(signature).map_err(|_| Status::BadRequest)?;
+    let config = config.inner().clone();
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+
+    let watcher =
+        tokio::spawn(async move { watch::watch_signature(&config, &signature, sender).await });
+
+    Ok(watch_stream(receiver, watcher))
+}
+
+// ── Wallet activity webhooks ──────────────────────────────────────────────────
+
+#[utoipa::path(
+    tag = "webhooks",
+    request_body = RegisterWebhookRequest,
+    responses(
+        (status = 200, body = ApiResponse<WebhookRegistration>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Key is read-only, or a wallet access token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/webhooks", data = "<request>")]
+pub async fn register_webhook(
+    _auth: SubmitAuth,
+    request: SignedJson<RegisterWebhookRequest>,
+    webhooks: &State<Arc<WebhookService>>,
+) -> ApiResult<WebhookRegistration> {
+    app_log!(
+        info,
+        "Register webhook request for {} -> {}",
+        request.address,
+        request.callback_url
+    );
+
+    match webhooks.register(
+        &request.address,
+        request.events.clone(),
+        &request.callback_url,
+    ) {
+        Ok(registration) => success(registration),
+        Err(e) => {
+            app_log!(error, "Webhook registration failed: {}", e);
+            failure("Webhook registration failed", &e)
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "webhooks",
+    responses(
+        (status = 200, body = ApiResponse<WebhookListResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Wallet access token", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[get("/webhooks")]
+pub fn list_webhooks(
+    _auth: KeyAuth,
+    webhooks: &State<Arc<WebhookService>>,
+) -> ApiResult<WebhookListResponse> {
+    let webhooks = webhooks.list();
+    let count = webhooks.len();
+
+    success(WebhookListResponse { webhooks, count })
+}
+
+#[utoipa::path(
+    tag = "webhooks",
+    responses(
+        (status = 200, body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Key is read-only, or a wallet access token", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[delete("/webhooks/<id>")]
+pub fn delete_webhook(
+    _auth: SubmitAuth,
+    id: &str,
+    webhooks: &State<Arc<WebhookService>>,
+) -> ApiResult<String> {
+    app_log!(info, "Delete webhook request: {}", id);
+
+    match webhooks.unregister(id) {
+        Ok(true) => success(id.to_string()),
+        Ok(false) => error_response(ApiError::new(
+            ErrorCode::NotFound,
+            format!("Webhook not found: {}", id),
+        )),
+        Err(e) => {
+            app_log!(error, "Webhook removal failed: {}", e);
+            failure("Webhook removal failed", &e)
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "webhooks",
+    responses(
+        (status = 200, body = ApiResponse<DeadLetterResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Wallet access token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[get("/webhooks/dead-letters")]
+pub fn list_dead_letters(
+    _auth: KeyAuth,
+    webhooks: &State<Arc<WebhookService>>,
+) -> ApiResult<DeadLetterResponse> {
+    match webhooks.dead_letters() {
+        Ok(dead_letters) => {
+            let count = dead_letters.len();
+            success(DeadLetterResponse {
+                dead_letters,
+                count,
+            })
+        }
+        Err(e) => {
+            app_log!(error, "Failed to read dead letters: {}", e);
+            failure("Failed to read dead letters", &e)
+        }
+    }
+}
+
+// ── Sign-In-With-Solana ───────────────────────────────────────────────────────
+// These routes are deliberately unauthenticated: they are how a wallet obtains
+// a bearer token in the first place.
+
+fn auth_response(result: Result<AuthTokens>, action: &str) -> ApiResult<AuthTokens> {
+    match result {
+        Ok(tokens) => success(tokens),
+        Err(e) => {
+            app_log!(warn, "{} failed: {}", action, e);
+            failure(&format!("{} failed", action), &e)
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "auth",
+    responses(
+        (status = 200, body = ApiResponse<AuthChallenge>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 429, description = "Too many challenges requested", body = ErrorResponse),
+    )
+)]
+#[post("/auth/challenge/<wallet_address>")]
+pub fn auth_challenge(
+    wallet_address: &str,
+    client: Option<IpAddr>,
+    auth: &State<AuthService>,
+) -> ApiResult<AuthChallenge> {
+    app_log!(info, "Auth challenge request for {}", wallet_address);
+
+    match auth.issue_challenge(wallet_address, client) {
+        Ok(challenge) => success(challenge),
+        Err(e) => failure("Challenge failed", &e),
+    }
+}
+
+#[utoipa::path(
+    tag = "auth",
+    request_body = AuthVerification,
+    responses(
+        (status = 200, body = ApiResponse<AuthTokens>),
+        (status = 401, description = "Challenge or signature rejected", body = ErrorResponse),
+    )
+)]
+#[post("/auth/verify", data = "<request>")]
+pub fn auth_verify(
+    request: Json<AuthVerification>,
+    auth: &State<AuthService>,
+) -> ApiResult<AuthTokens> {
+    app_log!(info, "Auth verify request for {}", request.wallet_address);
+    auth_response(auth.verify(&request), "Verification")
+}
+
+#[utoipa::path(
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, body = ApiResponse<AuthTokens>),
+        (status = 401, description = "Unknown or expired refresh token", body = ErrorResponse),
+    )
+)]
+#[post("/auth/refresh", data = "<request>")]
+pub fn auth_refresh(
+    request: Json<RefreshRequest>,
+    auth: &State<AuthService>,
+) -> ApiResult<AuthTokens> {
+    app_log!(info, "Auth refresh request");
+    auth_response(auth.refresh(&request.refresh_token), "Refresh")
+}
+
+#[utoipa::path(
+    tag = "staking",
+    request_body = PrepareStakeRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PrepareStakeResponse>),
+        (status = 400, description = "Invalid address, amount or seed", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 422, description = "Insufficient balance", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/stake/prepare", data = "<request>")]
+pub async fn prepare_stake(
+    auth: InternalAuth,
+    request: SignedJson<PrepareStakeRequest>,
+    config: &State<Config>,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareStakeResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/stake/prepare",
+        &*request,
+        async {
+            app_log!(
+                info,
+                "Prepare stake {} request from {}",
+                stake::operation_name(&request.operation),
+                request.payer_pubkey
+            );
+
+            if let Err(e) = auth.acting_for(&request.payer_pubkey) {
+                return error_response(e);
+            }
+
+            match parse_public_key(&request.payer_pubkey) {
+                Ok(payer_pubkey) => {
+                    match stake::prepare(
+                        config,
+                        &auth.actor,
+                        &payer_pubkey,
+                        request.operation.clone(),
+                    )
+                    .await
+                    {
+                        Ok(prepared) => success(prepared),
+                        Err(e) => {
+                            app_log!(error, "Stake preparation failed: {}", e);
+                            failure("Stake preparation failed", &e)
+                        }
+                    }
+                }
+                Err(e) => failure("Invalid payer public key", &e),
             }
         },
-        Err(e) => Json(ApiResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Invalid public key: {}", e)),
-        }),
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "staking",
+    request_body = StakeAccountsRequest,
+    responses(
+        (status = 200, body = ApiResponse<StakeAccountsResponse>),
+        (status = 400, description = "Invalid address or request", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/stake/accounts", data = "<request>")]
+pub async fn get_stake_accounts(
+    _auth: InternalAuth,
+    request: SignedJson<StakeAccountsRequest>,
+    config: &State<Config>,
+) -> ApiResult<StakeAccountsResponse> {
+    app_log!(
+        info,
+        "Stake accounts request for pubkey: {}",
+        request.pubkey
+    );
+
+    match parse_public_key(&request.pubkey) {
+        Ok(pubkey) => {
+            let epochs = request.epochs.unwrap_or(stake::DEFAULT_REWARD_EPOCHS);
+            match stake::list_accounts(config, &pubkey, epochs).await {
+                Ok(accounts) => success(accounts),
+                Err(e) => {
+                    app_log!(error, "Failed to get stake accounts: {}", e);
+                    failure("Failed to get stake accounts", &e)
+                }
+            }
+        }
+        Err(e) => failure("Invalid public key", &e),
     }
 }
+
+#[utoipa::path(
+    tag = "mints",
+    request_body = PrepareMintRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<PrepareMintResponse>),
+        (status = 400, description = "Invalid address, amount, seed or metadata", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/token/prepare", data = "<request>")]
+pub async fn prepare_mint(
+    auth: InternalAuth,
+    request: SignedJson<PrepareMintRequest>,
+    config: &State<Config>,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareMintResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/token/prepare",
+        &*request,
+        async {
+            app_log!(
+                info,
+                "Prepare token {} request from {}",
+                mint::operation_name(&request.operation),
+                request.payer_pubkey
+            );
+
+            if let Err(e) = auth.acting_for(&request.payer_pubkey) {
+                return error_response(e);
+            }
+
+            match parse_public_key(&request.payer_pubkey) {
+                Ok(payer_pubkey) => {
+                    match mint::prepare(
+                        config,
+                        &auth.actor,
+                        &payer_pubkey,
+                        request.operation.clone(),
+                    )
+                    .await
+                    {
+                        Ok(prepared) => success(prepared),
+                        Err(e) => {
+                            app_log!(error, "Token preparation failed: {}", e);
+                            failure("Token preparation failed", &e)
+                        }
+                    }
+                }
+                Err(e) => failure("Invalid payer public key", &e),
+            }
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "wallet",
+    request_body = CleanupRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, description = "Accounts to clear and the transactions that do it, in submission order", body = ApiResponse<CleanupResponse>),
+        (status = 400, description = "Invalid owner", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 502, description = "RPC or Jupiter unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/cleanup/prepare", data = "<request>")]
+pub async fn prepare_cleanup(
+    auth: InternalAuth,
+    request: SignedJson<CleanupRequest>,
+    config: &State<Config>,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<CleanupResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/cleanup/prepare",
+        &*request,
+        async {
+            app_log!(info, "Prepare cleanup request for {}", request.owner_pubkey);
+
+            if let Err(e) = auth.acting_for(&request.owner_pubkey) {
+                return error_response(e);
+            }
+
+            match parse_public_key(&request.owner_pubkey) {
+                Ok(owner) => match cleanup::prepare(config, &auth.actor, &owner, &request).await {
+                    Ok(prepared) => success(prepared),
+                    Err(e) => {
+                        app_log!(error, "Cleanup preparation failed: {}", e);
+                        failure("Cleanup preparation failed", &e)
+                    }
+                },
+                Err(e) => failure("Invalid owner public key", &e),
+            }
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    request_body = PrepareWrapRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, description = "Moves SOL into the owner's wrapped SOL account, creating it if needed", body = ApiResponse<PrepareWrapResponse>),
+        (status = 400, description = "Invalid owner or missing amount", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/wrap/prepare", data = "<request>")]
+pub async fn prepare_wrap(
+    auth: InternalAuth,
+    request: SignedJson<PrepareWrapRequest>,
+    config: &State<Config>,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareWrapResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/wrap/prepare",
+        &*request,
+        prepare_wrap_direction(config, &auth, &request, wrap::Direction::Wrap),
+    )
+    .await
+}
+
+#[utoipa::path(
+    tag = "transactions",
+    request_body = PrepareWrapRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response for retries with the same key"),
+    ),
+    responses(
+        (status = 200, description = "Moves wrapped SOL back to the owner; without an amount the account is closed", body = ApiResponse<PrepareWrapResponse>),
+        (status = 400, description = "Invalid owner or amount, no wrapped SOL account, or not enough in it", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Rejected by the spending policy, or not the signed-in wallet", body = ErrorResponse),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/unwrap/prepare", data = "<request>")]
+pub async fn prepare_unwrap(
+    auth: InternalAuth,
+    request: SignedJson<PrepareWrapRequest>,
+    config: &State<Config>,
+    idempotency_key: IdempotencyKey,
+    idempotency: &State<IdempotencyStore>,
+) -> ApiResult<PrepareWrapResponse> {
+    idempotent(
+        idempotency,
+        &auth.actor,
+        idempotency_key,
+        "/unwrap/prepare",
+        &*request,
+        prepare_wrap_direction(config, &auth, &request, wrap::Direction::Unwrap),
+    )
+    .await
+}
+
+async fn prepare_wrap_direction(
+    config: &Config,
+    auth: &InternalAuth,
+    request: &PrepareWrapRequest,
+    direction: wrap::Direction,
+) -> ApiResult<PrepareWrapResponse> {
+    app_log!(
+        info,
+        "Prepare {} request for {}",
+        direction.name(),
+        request.owner_pubkey
+    );
+
+    if let Err(e) = auth.acting_for(&request.owner_pubkey) {
+        return error_response(e);
+    }
+
+    match parse_public_key(&request.owner_pubkey) {
+        Ok(owner) => {
+            match wrap::prepare(config, &auth.actor, &owner, direction, request.amount).await {
+                Ok(prepared) => success(prepared),
+                Err(e) => {
+                    app_log!(error, "{} preparation failed: {}", direction.name(), e);
+                    failure("Wrap preparation failed", &e)
+                }
+            }
+        }
+        Err(e) => failure("Invalid owner public key", &e),
+    }
+}
+
+#[utoipa::path(
+    tag = "pay",
+    request_body = PayRequest,
+    responses(
+        (status = 200, description = "A solana: transfer request URL and the reference to look the payment up by", body = ApiResponse<PayRequestResponse>),
+        (status = 400, description = "Invalid recipient, amount or token", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/pay/request", data = "<request>")]
+pub async fn create_payment_request(
+    _auth: InternalAuth,
+    request: SignedJson<PayRequest>,
+    config: &State<Config>,
+) -> ApiResult<PayRequestResponse> {
+    app_log!(info, "Payment request for {:?}", request.recipient);
+
+    match pay::create_request(config, &request).await {
+        Ok(created) => success(created),
+        Err(e) => {
+            app_log!(error, "Payment request failed: {}", e);
+            failure("Payment request failed", &e)
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "pay",
+    request_body = PayStatusRequest,
+    responses(
+        (status = 200, description = "pending, paid, underpaid or expired", body = ApiResponse<PayStatusResponse>),
+        (status = 400, description = "Invalid reference, recipient or token", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 502, description = "RPC unreachable", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("signature" = []))
+)]
+#[post("/pay/status", data = "<request>")]
+pub async fn get_payment_status(
+    _auth: InternalAuth,
+    request: SignedJson<PayStatusRequest>,
+    config: &State<Config>,
+) -> ApiResult<PayStatusResponse> {
+    app_log!(info, "Payment status request for {}", request.reference);
+
+    match pay::check(config, &request).await {
+        Ok(status) => success(status),
+        Err(e) => {
+            app_log!(error, "Payment lookup failed: {}", e);
+            failure("Payment lookup failed", &e)
+        }
+    }
+}
+
+// ── Solana Pay transaction requests ───────────────────────────────────────────
+// Called by the payer's wallet rather than the gateway, so there are no
+// credentials and CORS is open. The checkout travels in the link's query
+// string; responses use the spec's shapes instead of the API envelope.
+
+const PAY_LINK_PATH: &str = "/solana/pay/tx";
+
+#[derive(FromForm)]
+pub struct PayLinkQuery {
+    recipient: String,
+    amount: f64,
+    #[field(name = "spl-token")]
+    spl_token: Option<String>,
+    #[field(name = "pay-with")]
+    pay_with: Option<String>,
+    reference: Option<String>,
+    message: Option<String>,
+    memo: Option<String>,
+    expires: i64,
+    sig: String, // From `pay::transaction_request_url`
+}
+
+pub struct PayLinkCors;
+
+#[rocket::async_trait]
+impl Fairing for PayLinkCors {
+    fn info(&self) -> Info {
+        Info {
+            name: "Solana Pay CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r rocket::Request<'_>, res: &mut rocket::Response<'r>) {
+        if req.uri().path() == PAY_LINK_PATH {
+            res.set_raw_header("Access-Control-Allow-Origin", "*");
+            res.set_raw_header("Access-Control-Allow-Methods", "GET, POST, OPTIONS");
+            res.set_raw_header("Access-Control-Allow-Headers", "Content-Type");
+        }
+    }
+}
+
+#[utoipa::path(
+    tag = "pay",
+    responses(
+        (status = 200, description = "Label and icon the wallet shows before asking for the account", body = PayLinkMetadata),
+    )
+)]
+#[get("/pay/tx")]
+pub fn pay_link_metadata(config: &State<Config>) -> Json<PayLinkMetadata> {
+    Json(PayLinkMetadata {
+        label: config
+            .pay
+            .label
+            .clone()
+            .unwrap_or_else(|| "Solanize".to_string()),
+        icon: config.pay.icon.clone().unwrap_or_default(),
+    })
+}
+
+#[utoipa::path(
+    tag = "pay",
+    request_body = PayLinkAccount,
+    params(
+        ("recipient" = String, Query, description = "Who is paid"),
+        ("amount" = f64, Query, description = "What the recipient receives, in whole tokens"),
+        ("spl-token" = Option<String>, Query, description = "Mint the recipient receives; SOL if omitted"),
+        ("pay-with" = Option<String>, Query, description = "Mint or SOL the payer spends, swapped through Jupiter; defaults to spl-token"),
+        ("reference" = Option<String>, Query, description = "Key added to the transaction to find the payment by"),
+        ("message" = Option<String>, Query, description = "Shown by the wallet with the transaction"),
+        ("memo" = Option<String>, Query, description = "Recorded with the transfer"),
+        ("expires" = i64, Query, description = "Unix time after which the link is refused"),
+        ("sig" = String, Query, description = "HMAC of the preceding query, set by `pay request`"),
+    ),
+    responses(
+        (status = 200, description = "Unsigned transaction for the account to sign and send", body = PayLinkTransaction),
+        (status = 400, description = "Invalid account or checkout, or the link expired", body = PayLinkError),
+        (status = 401, description = "Link not signed by this server", body = PayLinkError),
+        (status = 403, description = "Rejected by the spending policy", body = PayLinkError),
+        (status = 422, description = "Not enough to pay with", body = PayLinkError),
+        (status = 502, description = "RPC or Jupiter unreachable", body = PayLinkError),
+    )
+)]
+#[post("/pay/tx?<query..>", data = "<request>")]
+pub async fn pay_link_transaction(
+    query: PayLinkQuery,
+    request: Json<PayLinkAccount>,
+    config: &State<Config>,
+) -> Result<Json<PayLinkTransaction>, (Status, Json<PayLinkError>)> {
+    app_log!(
+        info,
+        "Transaction request from {} for {} {:?} to {}",
+        request.account,
+        query.amount,
+        query.spl_token,
+        query.recipient
+    );
+
+    let result = async {
+        let account = parse_public_key(&request.account)?;
+        let checkout = pay::Checkout::parse(
+            config,
+            &query.recipient,
+            query.amount,
+            query.spl_token.as_deref(),
+            query.pay_with.as_deref(),
+            query.reference.as_deref(),
+            query.message.clone(),
+            query.memo.clone(),
+        )?;
+        pay::verify_link(config, &checkout, query.expires, &query.sig)?;
+        pay::transaction(config, &account, &checkout).await
+    }
+    .await;
+
+    result.map(Json).map_err(|e| {
+        app_log!(error, "Transaction request failed: {}", e);
+        let error = ApiError::from_error("Transaction request failed", &e);
+        (
+            status_for(error.code),
+            Json(PayLinkError {
+                message: error.message,
+            }),
+        )
+    })
+}
+
+// CORS preflight from browser wallets
+#[options("/pay/tx")]
+pub fn pay_link_preflight() -> Status {
+    Status::NoContent
+}
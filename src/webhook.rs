@@ -0,0 +1,787 @@
+use crate::app_log;
+use crate::signing::to_hex;
+use crate::{config::Config, error::SolanaClientError, rpc, watch};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedTransaction, UiMessage, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+
+/// Header carrying `sha256=<hex hmac>` over `"{timestamp}.{body}"`
+pub const SIGNATURE_HEADER: &str = "X-Solanize-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Solanize-Timestamp";
+
+const JUPITER_PROGRAM_ID: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    SolTransfer,
+    TokenTransfer,
+    SwapCompleted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct WebhookRegistration {
+    pub id: String,
+    pub address: String,
+    pub events: Vec<WebhookEventType>,
+    pub callback_url: String,
+    pub created_at: i64,
+}
+
+/// Net balance movement of the watched address within one transaction
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct BalanceDelta {
+    pub mint: String, // Native SOL uses the wrapped SOL mint
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct WebhookPayload {
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event: WebhookEventType,
+    pub address: String,
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub changes: Vec<BalanceDelta>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct DeadLetter {
+    pub payload: WebhookPayload,
+    pub callback_url: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: i64,
+}
+
+/// How far an address has been processed: the newest slot reached and the
+/// signatures already handled in it
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct Cursor {
+    slot: u64,
+    signatures: Vec<String>,
+}
+
+impl Cursor {
+    fn covers(&self, slot: u64, signature: &str) -> bool {
+        slot < self.slot || (slot == self.slot && self.signatures.iter().any(|s| s == signature))
+    }
+
+    fn advance(&mut self, slot: u64, signature: &str) {
+        if slot > self.slot {
+            self.slot = slot;
+            self.signatures.clear();
+        }
+        if slot == self.slot && !self.covers(slot, signature) {
+            self.signatures.push(signature.to_string());
+        }
+    }
+}
+
+/// Owns webhook registrations and one PubSub watcher per registered address
+pub struct WebhookService {
+    config: Config,
+    http: Client,
+    registrations: Mutex<Vec<WebhookRegistration>>,
+    watchers: Mutex<HashMap<String, JoinHandle<()>>>,
+    /// Per address, persisted so a restart picks up where it stopped
+    cursors: Mutex<HashMap<String, Cursor>>,
+    /// Signatures being processed or waiting for a retry
+    pending: Mutex<HashSet<String>>,
+}
+
+impl WebhookService {
+    pub fn new(config: Config) -> Result<Arc<Self>> {
+        let registrations = load_registrations(&config.webhooks.store_path)?;
+        let cursors = load_cursors(&config.webhooks.cursor_path)?;
+        let http = Client::builder()
+            .timeout(Duration::from_secs(config.webhooks.timeout_secs))
+            .build()?;
+
+        app_log!(
+            info,
+            "Loaded {} webhook registrations from {}",
+            registrations.len(),
+            config.webhooks.store_path
+        );
+
+        Ok(Arc::new(Self {
+            config,
+            http,
+            registrations: Mutex::new(registrations),
+            watchers: Mutex::new(HashMap::new()),
+            cursors: Mutex::new(cursors),
+            pending: Mutex::new(HashSet::new()),
+        }))
+    }
+
+    /// Spawn watchers for every address restored from disk
+    pub fn start(self: &Arc<Self>) {
+        let addresses: Vec<String> = self
+            .registrations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.address.clone())
+            .collect();
+
+        for address in addresses {
+            self.ensure_watcher(&address);
+        }
+    }
+
+    pub fn list(&self) -> Vec<WebhookRegistration> {
+        self.registrations.lock().unwrap().clone()
+    }
+
+    pub fn register(
+        self: &Arc<Self>,
+        address: &str,
+        events: Vec<WebhookEventType>,
+        callback_url: &str,
+    ) -> Result<WebhookRegistration> {
+        Pubkey::from_str(address).map_err(|_| SolanaClientError::InvalidAddress {
+            address: address.to_string(),
+        })?;
+
+        let url =
+            reqwest::Url::parse(callback_url).map_err(|e| SolanaClientError::InvalidRequest {
+                message: format!("Invalid callback URL: {}", e),
+            })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(SolanaClientError::InvalidRequest {
+                message: format!("Unsupported callback URL scheme: {}", url.scheme()),
+            }
+            .into());
+        }
+
+        if events.is_empty() {
+            return Err(SolanaClientError::InvalidRequest {
+                message: "At least one event type is required".to_string(),
+            }
+            .into());
+        }
+
+        let registration = WebhookRegistration {
+            id: generate_id(),
+            address: address.to_string(),
+            events,
+            callback_url: callback_url.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+
+        {
+            let mut registrations = self.registrations.lock().unwrap();
+            registrations.push(registration.clone());
+            save_registrations(&self.config.webhooks.store_path, &registrations)?;
+        }
+
+        app_log!(
+            info,
+            "Registered webhook {} for {} -> {}",
+            registration.id,
+            registration.address,
+            registration.callback_url
+        );
+
+        self.ensure_watcher(address);
+        Ok(registration)
+    }
+
+    /// Remove a registration; returns false if the id is unknown
+    pub fn unregister(&self, id: &str) -> Result<bool> {
+        let address = {
+            let mut registrations = self.registrations.lock().unwrap();
+            let Some(pos) = registrations.iter().position(|r| r.id == id) else {
+                return Ok(false);
+            };
+            let removed = registrations.remove(pos);
+            save_registrations(&self.config.webhooks.store_path, &registrations)?;
+
+            // Keep the watcher alive while other registrations need it
+            if registrations.iter().any(|r| r.address == removed.address) {
+                None
+            } else {
+                Some(removed.address)
+            }
+        };
+
+        if let Some(address) = address {
+            if let Some(handle) = self.watchers.lock().unwrap().remove(&address) {
+                handle.abort();
+                app_log!(info, "Stopped webhook watcher for {}", address);
+            }
+
+            // A later registration starts fresh rather than replaying the gap
+            let mut cursors = self.cursors.lock().unwrap();
+            if cursors.remove(&address).is_some() {
+                save_cursors(&self.config.webhooks.cursor_path, &cursors)?;
+            }
+        }
+
+        app_log!(info, "Removed webhook {}", id);
+        Ok(true)
+    }
+
+    pub fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let path = &self.config.webhooks.dead_letter_path;
+        if !std::path::Path::new(path).exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(path)?;
+        let letters = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        Ok(letters)
+    }
+
+    fn ensure_watcher(self: &Arc<Self>, address: &str) {
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(address) {
+            return;
+        }
+
+        let service = Arc::clone(self);
+        let owned_address = address.to_string();
+        let handle = tokio::spawn(async move { service.run_watcher(owned_address).await });
+
+        watchers.insert(address.to_string(), handle);
+    }
+
+    /// Follow an address forever, reconnecting when the PubSub socket drops.
+    /// Every (re)subscription first catches up on what landed while no
+    /// socket was listening.
+    async fn run_watcher(self: Arc<Self>, address: String) {
+        let Ok(pubkey) = Pubkey::from_str(&address) else {
+            return;
+        };
+
+        loop {
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+            let (subscribed, ready) = oneshot::channel();
+            let config = self.config.clone();
+            let watcher = tokio::spawn(async move {
+                watch::watch_address(&config, &pubkey, sender, Some(subscribed)).await
+            });
+
+            // Live notifications queue up meanwhile, so nothing falls in between
+            if ready.await.is_ok()
+                && let Err(e) = self.backfill(&address, &pubkey).await
+            {
+                app_log!(warn, "Webhook backfill for {} failed: {}", address, e);
+            }
+
+            while let Some(event) = receiver.recv().await {
+                if let watch::WatchEvent::Transaction {
+                    signature,
+                    slot,
+                    error: None,
+                    ..
+                } = event
+                {
+                    self.dispatch(&address, signature, slot);
+                }
+            }
+
+            match watcher.await {
+                Ok(Err(e)) => app_log!(warn, "Webhook watcher for {} dropped: {}", address, e),
+                Err(e) => app_log!(warn, "Webhook watcher for {} aborted: {}", address, e),
+                Ok(Ok(())) => {}
+            }
+
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Dispatch successful transactions newer than the address's cursor, oldest
+    /// first. Without a cursor yet, one is started at the current slot.
+    async fn backfill(self: &Arc<Self>, address: &str, pubkey: &Pubkey) -> Result<()> {
+        let client = rpc::client(&self.config);
+        let commitment = CommitmentConfig::confirmed();
+
+        let cursor = self.cursors.lock().unwrap().get(address).cloned();
+        let Some(cursor) = cursor else {
+            let slot = client.get_slot_with_commitment(commitment)?;
+            let mut cursors = self.cursors.lock().unwrap();
+            cursors.entry(address.to_string()).or_insert(Cursor {
+                slot,
+                signatures: Vec::new(),
+            });
+            return save_cursors(&self.config.webhooks.cursor_path, &cursors);
+        };
+
+        let max_backfill = self.config.webhooks.max_backfill;
+        let mut missed = Vec::new();
+
+        for account in watch::mentioned_accounts(&self.config, pubkey) {
+            let mut before = None;
+            let mut scanned = 0;
+
+            loop {
+                let limit = (max_backfill - scanned).min(1000);
+                let page = client.get_signatures_for_address_with_config(
+                    &account,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(limit),
+                        commitment: Some(commitment),
+                    },
+                )?;
+                scanned += page.len();
+                let mut done = page.len() < limit;
+                before = page
+                    .last()
+                    .and_then(|status| Signature::from_str(&status.signature).ok());
+
+                for status in page {
+                    if status.slot < cursor.slot {
+                        done = true;
+                        break;
+                    }
+                    if status.err.is_none() && !cursor.covers(status.slot, &status.signature) {
+                        missed.push((status.slot, status.signature));
+                    }
+                }
+
+                if done {
+                    break;
+                }
+                if scanned >= max_backfill {
+                    app_log!(
+                        warn,
+                        "Webhook backfill for {} stopped after {} signatures of {}",
+                        address,
+                        scanned,
+                        account
+                    );
+                    break;
+                }
+            }
+        }
+
+        missed.sort();
+        missed.dedup();
+        if !missed.is_empty() {
+            app_log!(
+                info,
+                "Backfilling {} webhook transactions for {}",
+                missed.len(),
+                address
+            );
+        }
+        for (slot, signature) in missed {
+            self.dispatch(address, signature, slot);
+        }
+
+        Ok(())
+    }
+
+    /// Start processing a signature unless it is already done or in flight
+    fn dispatch(self: &Arc<Self>, address: &str, signature: String, slot: u64) {
+        let done = self
+            .cursors
+            .lock()
+            .unwrap()
+            .get(address)
+            .is_some_and(|cursor| cursor.covers(slot, &signature));
+        if done || !self.pending.lock().unwrap().insert(signature.clone()) {
+            return;
+        }
+
+        tokio::spawn(Arc::clone(self).process(address.to_string(), signature, slot));
+    }
+
+    /// Handle one signature, putting it back in line with backoff while its
+    /// activity cannot be fetched (the node may not have it yet). The cursor
+    /// moves past it either way.
+    async fn process(self: Arc<Self>, address: String, signature: String, slot: u64) {
+        let mut attempts = 0;
+
+        loop {
+            let Err(e) = self.handle_transaction(&address, &signature).await else {
+                break;
+            };
+            if attempts == self.config.webhooks.max_retries {
+                app_log!(
+                    error,
+                    "Webhook processing failed for {} after {} attempts: {}",
+                    signature,
+                    attempts + 1,
+                    e
+                );
+                break;
+            }
+
+            attempts += 1;
+            app_log!(
+                warn,
+                "Webhook processing failed for {}, retry {} queued: {}",
+                signature,
+                attempts,
+                e
+            );
+            tokio::time::sleep(self.backoff(attempts)).await;
+        }
+
+        self.pending.lock().unwrap().remove(&signature);
+
+        let mut cursors = self.cursors.lock().unwrap();
+        cursors
+            .entry(address.clone())
+            .or_default()
+            .advance(slot, &signature);
+        if let Err(e) = save_cursors(&self.config.webhooks.cursor_path, &cursors) {
+            app_log!(
+                error,
+                "Failed to save webhook cursor for {}: {}",
+                address,
+                e
+            );
+        }
+    }
+
+    async fn handle_transaction(&self, address: &str, signature: &str) -> Result<()> {
+        let Some(activity) = fetch_activity(&self.config, address, signature).await? else {
+            return Ok(());
+        };
+
+        let registrations: Vec<WebhookRegistration> = self
+            .registrations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.address == address)
+            .cloned()
+            .collect();
+
+        for event in &activity.events {
+            for registration in registrations.iter().filter(|r| r.events.contains(event)) {
+                let payload = WebhookPayload {
+                    delivery_id: generate_id(),
+                    webhook_id: registration.id.clone(),
+                    event: *event,
+                    address: address.to_string(),
+                    signature: signature.to_string(),
+                    slot: activity.slot,
+                    block_time: activity.block_time,
+                    changes: activity.changes.clone(),
+                };
+
+                self.deliver(registration, payload).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// POST the payload with exponential backoff, dead-lettering on exhaustion
+    async fn deliver(&self, registration: &WebhookRegistration, payload: WebhookPayload) {
+        let webhooks = &self.config.webhooks;
+        let mut last_error = String::new();
+        let mut attempts = 0;
+
+        while attempts <= webhooks.max_retries {
+            if attempts > 0 {
+                tokio::time::sleep(self.backoff(attempts)).await;
+            }
+            attempts += 1;
+
+            match self.post_signed(&registration.callback_url, &payload).await {
+                Ok(()) => {
+                    app_log!(
+                        info,
+                        "Delivered webhook {} ({:?}) for {}",
+                        payload.delivery_id,
+                        payload.event,
+                        payload.signature
+                    );
+                    return;
+                }
+                Err(e) => {
+                    app_log!(
+                        warn,
+                        "Webhook delivery {} attempt {} failed: {}",
+                        payload.delivery_id,
+                        attempts,
+                        e
+                    );
+                    last_error = e.to_string();
+                }
+            }
+        }
+
+        let letter = DeadLetter {
+            payload,
+            callback_url: registration.callback_url.clone(),
+            attempts,
+            last_error,
+            failed_at: chrono::Utc::now().timestamp(),
+        };
+
+        if let Err(e) = append_dead_letter(&webhooks.dead_letter_path, &letter) {
+            app_log!(error, "Failed to write dead letter: {}", e);
+        }
+    }
+
+    async fn post_signed(&self, url: &str, payload: &WebhookPayload) -> Result<()> {
+        let body = serde_json::to_string(payload)?;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = sign_payload(&self.signing_secret(), &timestamp, &body)?;
+
+        let response = self
+            .http
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(SolanaClientError::NetworkError {
+                source: format!("Callback returned {}", response.status()).into(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Delay before retry number `attempt`, doubling from `retry_backoff_ms`
+    /// up to `max_backoff_ms`
+    fn backoff(&self, attempt: u32) -> Duration {
+        let webhooks = &self.config.webhooks;
+        let factor = 1u64
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        let delay = webhooks
+            .retry_backoff_ms
+            .saturating_mul(factor)
+            .min(webhooks.max_backoff_ms);
+        Duration::from_millis(delay)
+    }
+
+    fn signing_secret(&self) -> String {
+        self.config
+            .webhooks
+            .signing_secret
+            .clone()
+            .unwrap_or_else(|| self.config.internal.secret.clone())
+    }
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`; receivers recompute and compare
+pub fn sign_payload(secret: &str, timestamp: &str, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    Ok(to_hex(&mac.finalize().into_bytes()))
+}
+
+fn generate_id() -> String {
+    bs58::encode(rand::random::<[u8; 16]>()).into_string()
+}
+
+struct Activity {
+    slot: u64,
+    block_time: Option<i64>,
+    changes: Vec<BalanceDelta>,
+    events: Vec<WebhookEventType>,
+}
+
+/// Classify what a confirmed transaction did to `address`
+async fn fetch_activity(
+    config: &Config,
+    address: &str,
+    signature: &str,
+) -> Result<Option<Activity>> {
+    let client = rpc::client(config);
+    let signature = Signature::from_str(signature)?;
+
+    let tx = client.get_transaction_with_config(
+        &signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::JsonParsed),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+
+    let Some(meta) = &tx.transaction.meta else {
+        return Ok(None);
+    };
+    if meta.err.is_some() {
+        return Ok(None);
+    }
+
+    let account_keys: Vec<(String, bool)> = match &tx.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Parsed(message) => message
+                .account_keys
+                .iter()
+                .map(|a| (a.pubkey.clone(), a.signer))
+                .collect(),
+            UiMessage::Raw(message) => message
+                .account_keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    (
+                        key.clone(),
+                        i < message.header.num_required_signatures as usize,
+                    )
+                })
+                .collect(),
+        },
+        _ => return Ok(None),
+    };
+
+    let mut changes = Vec::new();
+    let mut signed_by_address = false;
+
+    if let Some(index) = account_keys.iter().position(|(key, _)| key == address) {
+        signed_by_address = account_keys[index].1;
+
+        let pre = meta.pre_balances.get(index).copied().unwrap_or(0) as i128;
+        let post = meta.post_balances.get(index).copied().unwrap_or(0) as i128;
+        // The fee payer's balance also drops by the fee; that is not a transfer
+        let fee = if index == 0 { meta.fee as i128 } else { 0 };
+        let lamports = post - pre + fee;
+
+        if lamports != 0 {
+            changes.push(BalanceDelta {
+                mint: config.tokens.sol.clone(),
+                amount: lamports as f64 / solana_sdk::native_token::LAMPORTS_PER_SOL as f64,
+            });
+        }
+    }
+
+    let pre_tokens: Vec<UiTransactionTokenBalance> =
+        Option::from(meta.pre_token_balances.clone()).unwrap_or_default();
+    let post_tokens: Vec<UiTransactionTokenBalance> =
+        Option::from(meta.post_token_balances.clone()).unwrap_or_default();
+
+    let mut token_deltas: HashMap<String, f64> = HashMap::new();
+    for (balances, sign) in [(&pre_tokens, -1.0), (&post_tokens, 1.0)] {
+        for balance in balances {
+            let owner: Option<String> = Option::from(balance.owner.clone());
+            if owner.as_deref() == Some(address) {
+                let amount = balance.ui_token_amount.ui_amount.unwrap_or(0.0);
+                *token_deltas.entry(balance.mint.clone()).or_insert(0.0) += sign * amount;
+            }
+        }
+    }
+    changes.extend(
+        token_deltas
+            .into_iter()
+            .filter(|(_, amount)| *amount != 0.0)
+            .map(|(mint, amount)| BalanceDelta { mint, amount }),
+    );
+
+    let logs: Vec<String> = Option::from(meta.log_messages.clone()).unwrap_or_default();
+    let used_jupiter = logs.iter().any(|log| log.contains(JUPITER_PROGRAM_ID));
+    let has_outflow = changes.iter().any(|c| c.amount < 0.0);
+    let has_inflow = changes.iter().any(|c| c.amount > 0.0);
+
+    let mut events = Vec::new();
+    if signed_by_address && has_inflow && has_outflow && used_jupiter {
+        events.push(WebhookEventType::SwapCompleted);
+    } else {
+        if changes
+            .iter()
+            .any(|c| c.mint == config.tokens.sol && c.amount > 0.0)
+        {
+            events.push(WebhookEventType::SolTransfer);
+        }
+        if changes
+            .iter()
+            .any(|c| c.mint != config.tokens.sol && c.amount > 0.0)
+        {
+            events.push(WebhookEventType::TokenTransfer);
+        }
+    }
+
+    if events.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Activity {
+        slot: tx.slot,
+        block_time: tx.block_time,
+        changes,
+        events,
+    }))
+}
+
+fn load_registrations(path: &str) -> Result<Vec<WebhookRegistration>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_registrations(path: &str, registrations: &[WebhookRegistration]) -> Result<()> {
+    let content = serde_json::to_string_pretty(registrations)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn load_cursors(path: &str) -> Result<HashMap<String, Cursor>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_cursors(path: &str, cursors: &HashMap<String, Cursor>) -> Result<()> {
+    let content = serde_json::to_string_pretty(cursors)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn append_dead_letter(path: &str, letter: &DeadLetter) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(letter)?)?;
+
+    app_log!(
+        warn,
+        "Dead-lettered webhook {} after {} attempts",
+        letter.payload.delivery_id,
+        letter.attempts
+    );
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use crate::api::PrepareWrapResponse;
+use crate::app_log;
+use crate::audit::Audit;
+use crate::policy::Intent;
+use crate::stake::{to_lamports, to_sol};
+use crate::{config::Config, error::SolanaClientError, rpc, signing, transaction, wallet};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_program::program_pack::Pack;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer};
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::instruction as token_instruction;
+use spl_token_2022::state::Account;
+
+const NATIVE_DECIMALS: u8 = 9;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    Wrap,
+    Unwrap,
+}
+
+impl Direction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Wrap => "wrap",
+            Direction::Unwrap => "unwrap",
+        }
+    }
+}
+
+/// A wrap or unwrap signed by the local wallet and confirmed
+#[derive(Debug, Serialize)]
+pub struct WrapResult {
+    pub operation: &'static str,
+    pub amount: f64, // SOL moved into or out of the account
+    pub account: String,
+    pub closed: bool, // The wrapped SOL account no longer exists
+    pub signature: String,
+}
+
+// The instructions for one wrap or unwrap
+struct Plan {
+    instructions: Vec<Instruction>,
+    account: Pubkey,
+    lamports: u64,
+    closed: bool,
+}
+
+/// Wrapped SOL account of `owner`: the associated token account for the native mint
+pub fn wrapped_sol_address(owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &spl_token::native_mint::id())
+}
+
+/// Move `amount` SOL into, or out of, the wallet's wrapped SOL account.
+/// Unwrapping without an amount closes the account and returns its rent too.
+pub async fn execute(
+    config: &Config,
+    actor: &str,
+    direction: Direction,
+    amount: Option<f64>,
+) -> Result<WrapResult> {
+    let mut audit = Audit::new(actor, direction.name(), json!({ "amount": amount }));
+    let result: Result<WrapResult> = async {
+        let keypair = wallet::load_keypair(config).await?;
+        let client = rpc::client(config);
+
+        let plan = plan(&client, &keypair.pubkey(), direction, amount)?;
+        audit.check_policy(
+            config,
+            &Intent::instructions(&keypair.pubkey(), &plan.instructions),
+        )?;
+
+        app_log!(
+            info,
+            "{} {} SOL: {}",
+            direction.name(),
+            to_sol(plan.lamports),
+            plan.account
+        );
+
+        let signature =
+            transaction::sign_and_send(config, &mut audit, &keypair, &[], &plan.instructions)?;
+
+        Ok(WrapResult {
+            operation: direction.name(),
+            amount: to_sol(plan.lamports),
+            account: plan.account.to_string(),
+            closed: plan.closed,
+            signature: signature.to_string(),
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "confirmed")
+}
+
+/// Build the wrap or unwrap unsigned for `owner`, who is the only signer
+pub async fn prepare(
+    config: &Config,
+    actor: &str,
+    owner: &Pubkey,
+    direction: Direction,
+    amount: Option<f64>,
+) -> Result<PrepareWrapResponse> {
+    let mut audit = Audit::new(
+        actor,
+        direction.name(),
+        json!({ "owner": owner.to_string(), "amount": amount }),
+    );
+    let result: Result<PrepareWrapResponse> = async {
+        let client = rpc::client(config);
+
+        let plan = plan(&client, owner, direction, amount)?;
+        audit.check_policy(config, &Intent::instructions(owner, &plan.instructions))?;
+
+        app_log!(
+            info,
+            "Preparing {} of {} SOL for {}",
+            direction.name(),
+            to_sol(plan.lamports),
+            owner
+        );
+
+        let prepared = transaction::prepare_unsigned(config, owner, &plan.instructions)?;
+        Ok(PrepareWrapResponse {
+            unsigned_transaction: prepared.unsigned_transaction,
+            account: plan.account.to_string(),
+            amount: to_sol(plan.lamports),
+            closed: plan.closed,
+            required_signers: prepared.required_signers,
+            recent_blockhash: prepared.recent_blockhash,
+        })
+    }
+    .await;
+
+    audit.finish(config, result, "prepared")
+}
+
+fn plan(
+    client: &RpcClient,
+    owner: &Pubkey,
+    direction: Direction,
+    amount: Option<f64>,
+) -> Result<Plan> {
+    let account = wrapped_sol_address(owner);
+    let native_mint = spl_token::native_mint::id();
+    let token_program = spl_token::id();
+
+    match direction {
+        Direction::Wrap => {
+            let amount = amount.ok_or_else(|| invalid("An amount is required to wrap SOL"))?;
+            let lamports = to_lamports(amount)?;
+
+            // Lamports sent to a native account only count once it is synced
+            let instructions = vec![
+                create_associated_token_account_idempotent(
+                    owner,
+                    owner,
+                    &native_mint,
+                    &token_program,
+                ),
+                system_instruction::transfer(owner, &account, lamports),
+                token_instruction::sync_native(&token_program, &account)?,
+            ];
+            Ok(Plan {
+                instructions,
+                account,
+                lamports,
+                closed: false,
+            })
+        }
+        Direction::Unwrap => {
+            let balance = wrapped_balance(client, &account)?
+                .ok_or_else(|| invalid(format!("No wrapped SOL account at {}", account)))?;
+            let lamports = match amount {
+                Some(amount) => to_lamports(amount)?,
+                None => balance,
+            };
+            if lamports > balance {
+                return Err(SolanaClientError::InsufficientBalance {
+                    current: to_sol(balance),
+                    required: to_sol(lamports),
+                }
+                .into());
+            }
+
+            if lamports == balance {
+                let close =
+                    token_instruction::close_account(&token_program, &account, owner, owner, &[])?;
+                return Ok(Plan {
+                    instructions: vec![close],
+                    account,
+                    lamports,
+                    closed: true,
+                });
+            }
+
+            // A native account cannot release part of its balance, so move
+            // that part to a temporary account derived from the owner and close it
+            let seed = format!("unwrap-{}", signing::to_hex(&rand::random::<[u8; 8]>()));
+            let temporary = Pubkey::create_with_seed(owner, &seed, &token_program)?;
+            let rent = client.get_minimum_balance_for_rent_exemption(Account::LEN)?;
+
+            let instructions = vec![
+                system_instruction::create_account_with_seed(
+                    owner,
+                    &temporary,
+                    owner,
+                    &seed,
+                    rent,
+                    Account::LEN as u64,
+                    &token_program,
+                ),
+                token_instruction::initialize_account3(
+                    &token_program,
+                    &temporary,
+                    &native_mint,
+                    owner,
+                )?,
+                token_instruction::transfer_checked(
+                    &token_program,
+                    &account,
+                    &native_mint,
+                    &temporary,
+                    owner,
+                    &[],
+                    lamports,
+                    NATIVE_DECIMALS,
+                )?,
+                token_instruction::close_account(&token_program, &temporary, owner, owner, &[])?,
+            ];
+            Ok(Plan {
+                instructions,
+                account,
+                lamports,
+                closed: false,
+            })
+        }
+    }
+}
+
+// Token balance of the wrapped SOL account in lamports, None if it does not exist
+fn wrapped_balance(client: &RpcClient, account: &Pubkey) -> Result<Option<u64>> {
+    let Some(data) = client
+        .get_account_with_commitment(account, client.commitment())?
+        .value
+        .map(|a| a.data)
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        StateWithExtensions::<Account>::unpack(&data)?.base.amount,
+    ))
+}
+
+fn invalid(message: impl Into<String>) -> anyhow::Error {
+    SolanaClientError::InvalidRequest {
+        message: message.into(),
+    }
+    .into()
+}